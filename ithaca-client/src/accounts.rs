@@ -0,0 +1,103 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use ithaca_smart_contract_sol::{
+    AccessController, ClientBalance, Contract, Fundlock, Governance, KeeperPool, Ledger, Member,
+    Multisig, ParameterChangeRequest, Position, Proposal, Role, RoleMembers, TokenValidator,
+    WhitelistedToken, Withdrawals,
+};
+
+// Decodes a single account of a known type, checking its discriminator
+pub fn decode<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut &data[..])
+}
+
+// Any account owned by the program
+pub enum IthacaAccount {
+    AccessController(AccessController),
+    Role(Role),
+    RoleMembers(RoleMembers),
+    Member(Member),
+    Multisig(Multisig),
+    Proposal(Proposal),
+    Governance(Governance),
+    ParameterChangeRequest(ParameterChangeRequest),
+    TokenValidator(TokenValidator),
+    WhitelistedToken(WhitelistedToken),
+    Fundlock(Fundlock),
+    KeeperPool(KeeperPool),
+    ClientBalance(ClientBalance),
+    Withdrawals(Withdrawals),
+    Ledger(Ledger),
+    Contract(Contract),
+    Position(Position),
+}
+
+impl IthacaAccount {
+    // Picks the account type from the discriminator, useful when scanning program accounts
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let discriminator = data
+            .get(..8)
+            .ok_or(ErrorCode::AccountDiscriminatorNotFound)?;
+        let account = match discriminator {
+            d if d == AccessController::DISCRIMINATOR => Self::AccessController(decode(data)?),
+            d if d == Role::DISCRIMINATOR => Self::Role(decode(data)?),
+            d if d == RoleMembers::DISCRIMINATOR => Self::RoleMembers(decode(data)?),
+            d if d == Member::DISCRIMINATOR => Self::Member(decode(data)?),
+            d if d == Multisig::DISCRIMINATOR => Self::Multisig(decode(data)?),
+            d if d == Proposal::DISCRIMINATOR => Self::Proposal(decode(data)?),
+            d if d == Governance::DISCRIMINATOR => Self::Governance(decode(data)?),
+            d if d == ParameterChangeRequest::DISCRIMINATOR => {
+                Self::ParameterChangeRequest(decode(data)?)
+            }
+            d if d == TokenValidator::DISCRIMINATOR => Self::TokenValidator(decode(data)?),
+            d if d == WhitelistedToken::DISCRIMINATOR => Self::WhitelistedToken(decode(data)?),
+            d if d == Fundlock::DISCRIMINATOR => Self::Fundlock(decode(data)?),
+            d if d == KeeperPool::DISCRIMINATOR => Self::KeeperPool(decode(data)?),
+            d if d == ClientBalance::DISCRIMINATOR => Self::ClientBalance(decode(data)?),
+            d if d == Withdrawals::DISCRIMINATOR => Self::Withdrawals(decode(data)?),
+            d if d == Ledger::DISCRIMINATOR => Self::Ledger(decode(data)?),
+            d if d == Contract::DISCRIMINATOR => Self::Contract(decode(data)?),
+            d if d == Position::DISCRIMINATOR => Self::Position(decode(data)?),
+            _ => return Err(ErrorCode::AccountDiscriminatorMismatch.into()),
+        };
+        Ok(account)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn serialize<T: AccountSerialize>(account: &T) -> Vec<u8> {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn accounts_are_decoded_by_discriminator() {
+        let client = Pubkey::new_unique();
+        let data = serialize(&Position {
+            contract_id: 7,
+            client,
+            size: 3,
+            bump: 255,
+        });
+
+        match IthacaAccount::decode(&data).unwrap() {
+            IthacaAccount::Position(position) => {
+                assert_eq!(position.contract_id, 7);
+                assert_eq!(position.client, client);
+                assert_eq!(position.size, 3);
+            }
+            _ => panic!("Expected a position"),
+        }
+        assert!(decode::<Contract>(&data).is_err());
+    }
+
+    #[test]
+    fn unknown_accounts_are_rejected() {
+        assert!(IthacaAccount::decode(&[0; 4]).is_err());
+        assert!(IthacaAccount::decode(&[0; 64]).is_err());
+    }
+}
@@ -0,0 +1,1448 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token::{self, native_mint};
+use ithaca_smart_contract_sol::{
+    accounts, instruction, ContractParams, FundMovementParamOptimized, ParameterChange,
+    PositionsParam, ProposalAccount, Roles, Subsystem, TokenStatus, ID,
+};
+
+use crate::pda;
+
+// Instruction builders for every program entrypoint except dummy_for_idl, which only exists
+// to get the contract and position accounts into the IDL
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+// Access controller, token validator and fundlock of a single deployment, `admin` signs the
+// admin instructions and only differs from the creator once the admin has been transferred
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ithaca {
+    pub creator: Pubkey,
+    pub admin: Pubkey,
+    pub access_controller: Pubkey,
+    pub token_validator: Pubkey,
+    pub fundlock: Pubkey,
+}
+
+// Token accounts of a client for the fundlock of a single token
+struct ClientAccounts {
+    fundlock_token_vault: Pubkey,
+    client_balance: Pubkey,
+    withdrawals: Pubkey,
+}
+
+impl Ithaca {
+    pub fn new(creator: Pubkey) -> Self {
+        let (access_controller, _) = pda::access_controller(&creator);
+        let (token_validator, _) = pda::token_validator(&access_controller);
+        let (fundlock, _) = pda::fundlock(&access_controller, &token_validator);
+        Self {
+            creator,
+            admin: creator,
+            access_controller,
+            token_validator,
+            fundlock,
+        }
+    }
+
+    pub fn with_admin(self, admin: Pubkey) -> Self {
+        Self { admin, ..self }
+    }
+
+    pub fn role(&self, role: &Roles) -> Pubkey {
+        pda::role(&self.access_controller, role).0
+    }
+
+    pub fn member(&self, member: &Pubkey) -> Pubkey {
+        pda::member(&self.access_controller, member).0
+    }
+
+    pub fn role_members(&self, role: &Roles, page_index: u64) -> Pubkey {
+        pda::role_members(&self.role(role), page_index).0
+    }
+
+    pub fn whitelisted_token(&self, mint: &Pubkey) -> Pubkey {
+        pda::whitelisted_token(&self.token_validator, mint).0
+    }
+
+    pub fn fundlock_token_vault(&self, mint: &Pubkey) -> Pubkey {
+        pda::fundlock_token_vault(&self.fundlock, mint).0
+    }
+
+    pub fn client_balance(&self, mint: &Pubkey, client_ata: &Pubkey) -> Pubkey {
+        pda::client_balance(&self.fundlock_token_vault(mint), client_ata).0
+    }
+
+    pub fn withdrawals(&self, mint: &Pubkey, client_ata: &Pubkey) -> Pubkey {
+        pda::withdrawals(&self.fundlock, &self.client_balance(mint, client_ata)).0
+    }
+
+    pub fn keeper_pool(&self) -> Pubkey {
+        pda::keeper_pool(&self.fundlock).0
+    }
+
+    pub fn multisig(&self) -> Pubkey {
+        pda::multisig(&self.access_controller).0
+    }
+
+    pub fn multisig_authority(&self) -> Pubkey {
+        pda::multisig_authority(&self.multisig()).0
+    }
+
+    pub fn proposal(&self, proposal_id: u64) -> Pubkey {
+        pda::proposal(&self.multisig(), proposal_id).0
+    }
+
+    pub fn governance(&self) -> Pubkey {
+        pda::governance(&self.access_controller).0
+    }
+
+    pub fn parameter_change(&self, change_id: u64) -> Pubkey {
+        pda::parameter_change(&self.governance(), change_id).0
+    }
+
+    fn client_accounts(&self, mint: &Pubkey, client_ata: &Pubkey) -> ClientAccounts {
+        let fundlock_token_vault = self.fundlock_token_vault(mint);
+        let (client_balance, _) = pda::client_balance(&fundlock_token_vault, client_ata);
+        ClientAccounts {
+            fundlock_token_vault,
+            client_balance,
+            withdrawals: pda::withdrawals(&self.fundlock, &client_balance).0,
+        }
+    }
+
+    // Access control
+
+    pub fn init_access_controller(&self) -> Instruction {
+        ix(
+            accounts::InitAccessController {
+                admin: self.admin,
+                access_controller: self.access_controller,
+                role: self.role(&Roles::Admin),
+                member: self.member(&self.admin),
+                role_members: self.role_members(&Roles::Admin, 0),
+                system_program: system_program::ID,
+            },
+            instruction::InitAccessController {},
+        )
+    }
+
+    pub fn propose_admin(&self, new_admin: Pubkey) -> Instruction {
+        ix(
+            accounts::ProposeAdmin {
+                admin: self.admin,
+                access_controller: self.access_controller,
+            },
+            instruction::ProposeAdmin { new_admin },
+        )
+    }
+
+    // Signed by the proposed admin, `self.admin` is still the admin being replaced and
+    // `page_index` the admin role members page listing it
+    pub fn accept_admin(&self, new_admin: Pubkey, page_index: u64) -> Instruction {
+        ix(
+            accounts::AcceptAdmin {
+                new_admin,
+                previous_admin: self.admin,
+                access_controller: self.access_controller,
+                role: self.role(&Roles::Admin),
+                previous_admin_member: self.member(&self.admin),
+                new_admin_member: self.member(&new_admin),
+                role_members: self.role_members(&Roles::Admin, page_index),
+                system_program: system_program::ID,
+            },
+            instruction::AcceptAdmin { page_index },
+        )
+    }
+
+    // `page_index` is a page with room, see roles::page_with_room, and `expires_at` bounds
+    // the granted role only
+    pub fn grant_role(
+        &self,
+        role: &Roles,
+        new_member: Pubkey,
+        page_index: u64,
+        expires_at: Option<i64>,
+    ) -> Instruction {
+        ix(
+            accounts::GrantRole {
+                admin: self.admin,
+                access_controller: self.access_controller,
+                role: self.role(role),
+                member: self.member(&new_member),
+                role_members: self.role_members(role, page_index),
+                system_program: system_program::ID,
+            },
+            instruction::GrantRole {
+                role_granted: *role,
+                new_member,
+                page_index,
+                expires_at,
+            },
+        )
+    }
+
+    // Signed by the admin or by the member itself, `page_index` is the page listing the member,
+    // see roles::page_listing
+    pub fn renounce_role(
+        &self,
+        authority: Pubkey,
+        role: &Roles,
+        member: Pubkey,
+        page_index: u64,
+    ) -> Instruction {
+        ix(
+            accounts::RenounceRole {
+                authority,
+                admin: self.admin,
+                access_controller: self.access_controller,
+                role: self.role(role),
+                member: self.member(&member),
+                role_members: self.role_members(role, page_index),
+                system_program: system_program::ID,
+            },
+            instruction::RenounceRole {
+                role_renounced: *role,
+                member_pk: member,
+                page_index,
+            },
+        )
+    }
+
+    // Answers through the return data, decoded with roles::has_role
+    pub fn check_role(&self, role: &Roles, member: Pubkey) -> Instruction {
+        ix(
+            accounts::CheckRole {
+                access_controller: self.access_controller,
+                member: Some(self.member(&member)),
+            },
+            instruction::CheckRole {
+                role_checked: *role,
+                member_pk: member,
+            },
+        )
+    }
+
+    // Rewrites an access controller created before roles were typed, run before migrate_role
+    pub fn migrate_access_controller(&self) -> Instruction {
+        ix(
+            accounts::MigrateAccessController {
+                admin: self.admin,
+                access_controller: self.access_controller,
+                system_program: system_program::ID,
+            },
+            instruction::MigrateAccessController {},
+        )
+    }
+
+    // Rewrites a role account created before roles were typed, run before migrate_member
+    pub fn migrate_role(&self, role: &Roles) -> Instruction {
+        ix(
+            accounts::MigrateRole {
+                admin: self.admin,
+                access_controller: self.access_controller,
+                role: self.role(role),
+            },
+            instruction::MigrateRole {
+                role_migrated: *role,
+            },
+        )
+    }
+
+    // Lists the migrated member in `page_index` like grant_role
+    pub fn migrate_member(&self, role: &Roles, member: Pubkey, page_index: u64) -> Instruction {
+        ix(
+            accounts::MigrateMember {
+                admin: self.admin,
+                access_controller: self.access_controller,
+                role: self.role(role),
+                legacy_member: pda::legacy_member(&self.role(role), &member).0,
+                member: self.member(&member),
+                role_members: self.role_members(role, page_index),
+                system_program: system_program::ID,
+            },
+            instruction::MigrateMember {
+                role_migrated: *role,
+                member_pk: member,
+                page_index,
+            },
+        )
+    }
+
+    pub fn set_pause(
+        &self,
+        guardian: Pubkey,
+        paused: bool,
+        paused_subsystems: Vec<Subsystem>,
+        releases_allowed: bool,
+    ) -> Instruction {
+        ix(
+            accounts::SetPause {
+                guardian,
+                access_controller: self.access_controller,
+                role: self.role(&Roles::Guardian),
+                member: self.member(&guardian),
+            },
+            instruction::SetPause {
+                paused,
+                paused_subsystems,
+                releases_allowed,
+            },
+        )
+    }
+
+    // Multisig, once the multisig authority is the admin the admin instructions are built
+    // with `with_admin(multisig_authority())` and wrapped into proposals
+
+    pub fn init_multisig(&self, signers: Vec<Pubkey>, threshold: u8) -> Instruction {
+        ix(
+            accounts::InitMultisig {
+                admin: self.admin,
+                access_controller: self.access_controller,
+                multisig: self.multisig(),
+                system_program: system_program::ID,
+            },
+            instruction::InitMultisig { signers, threshold },
+        )
+    }
+
+    // Only executable through a proposal
+    pub fn set_multisig_signers(&self, signers: Vec<Pubkey>, threshold: u8) -> Instruction {
+        ix(
+            accounts::SetMultisigSigners {
+                authority: self.multisig_authority(),
+                multisig: self.multisig(),
+            },
+            instruction::SetMultisigSigners { signers, threshold },
+        )
+    }
+
+    // `proposal_id` is the multisig's current proposal count
+    pub fn create_proposal(
+        &self,
+        proposer: Pubkey,
+        proposal_id: u64,
+        proposed: &Instruction,
+    ) -> Instruction {
+        ix(
+            accounts::CreateProposal {
+                proposer,
+                multisig: self.multisig(),
+                proposal: self.proposal(proposal_id),
+                system_program: system_program::ID,
+            },
+            instruction::CreateProposal {
+                accounts: proposed
+                    .accounts
+                    .iter()
+                    .map(|account| ProposalAccount {
+                        pubkey: account.pubkey,
+                        is_signer: account.is_signer,
+                        is_writable: account.is_writable,
+                    })
+                    .collect(),
+                data: proposed.data.clone(),
+            },
+        )
+    }
+
+    pub fn approve_proposal(&self, signer: Pubkey, proposal_id: u64) -> Instruction {
+        ix(
+            accounts::ApproveProposal {
+                signer,
+                multisig: self.multisig(),
+                proposal: self.proposal(proposal_id),
+            },
+            instruction::ApproveProposal {},
+        )
+    }
+
+    // The proposed accounts follow as remaining accounts, without the signer flags since the
+    // multisig authority only signs within the program
+    pub fn execute_proposal(
+        &self,
+        executor: Pubkey,
+        proposal_id: u64,
+        proposed: &Instruction,
+    ) -> Instruction {
+        let mut instruction = ix(
+            accounts::ExecuteProposal {
+                executor,
+                multisig: self.multisig(),
+                proposal: self.proposal(proposal_id),
+                authority: self.multisig_authority(),
+                ithaca_program: ID,
+            },
+            instruction::ExecuteProposal {},
+        );
+        instruction
+            .accounts
+            .extend(proposed.accounts.iter().map(|account| AccountMeta {
+                is_signer: false,
+                ..account.clone()
+            }));
+        instruction
+    }
+
+    // Governance, `target` is the fundlock, whitelisted token or ledger account of the change
+
+    pub fn init_governance(&self, min_delay: i64) -> Instruction {
+        ix(
+            accounts::InitGovernance {
+                admin: self.admin,
+                access_controller: self.access_controller,
+                governance: self.governance(),
+                system_program: system_program::ID,
+            },
+            instruction::InitGovernance { min_delay },
+        )
+    }
+
+    // `change_id` is the governance's current change count
+    pub fn schedule_parameter_change(
+        &self,
+        change_id: u64,
+        target: Pubkey,
+        change: ParameterChange,
+        eta: i64,
+    ) -> Instruction {
+        ix(
+            accounts::ScheduleParameterChange {
+                admin: self.admin,
+                access_controller: self.access_controller,
+                governance: self.governance(),
+                target,
+                parameter_change: self.parameter_change(change_id),
+                system_program: system_program::ID,
+            },
+            instruction::ScheduleParameterChange { change, eta },
+        )
+    }
+
+    pub fn cancel_parameter_change(&self, change_id: u64, proposer: Pubkey) -> Instruction {
+        ix(
+            accounts::CancelParameterChange {
+                admin: self.admin,
+                access_controller: self.access_controller,
+                governance: self.governance(),
+                parameter_change: self.parameter_change(change_id),
+                proposer,
+            },
+            instruction::CancelParameterChange {},
+        )
+    }
+
+    pub fn execute_parameter_change(
+        &self,
+        executor: Pubkey,
+        change_id: u64,
+        target: Pubkey,
+        proposer: Pubkey,
+    ) -> Instruction {
+        ix(
+            accounts::ExecuteParameterChange {
+                executor,
+                access_controller: self.access_controller,
+                governance: self.governance(),
+                parameter_change: self.parameter_change(change_id),
+                target,
+                proposer,
+            },
+            instruction::ExecuteParameterChange {},
+        )
+    }
+
+    // Token validator
+
+    pub fn init_token_validator(&self) -> Instruction {
+        ix(
+            accounts::InitTokenValidator {
+                admin: self.admin,
+                access_controller: self.access_controller,
+                role: self.role(&Roles::Admin),
+                member: self.member(&self.admin),
+                token_validator: self.token_validator,
+                system_program: system_program::ID,
+            },
+            instruction::InitTokenValidator {},
+        )
+    }
+
+    // Token-2022 mints with a permanent delegate, transfer hook, close authority, default
+    // account state or non-transferable extension need allow_dangerous_extensions
+    pub fn add_token_to_whitelist(
+        &self,
+        mint: Pubkey,
+        token_precision: u8,
+        allow_dangerous_extensions: bool,
+    ) -> Instruction {
+        ix(
+            accounts::AddTokenToWhitelist {
+                admin: self.admin,
+                access_controller: self.access_controller,
+                role: self.role(&Roles::Admin),
+                member: self.member(&self.admin),
+                token_validator: self.token_validator,
+                new_token_to_whitelist: mint,
+                whitelisted_token: self.whitelisted_token(&mint),
+                system_program: system_program::ID,
+            },
+            instruction::AddTokenToWhitelist {
+                token_precision,
+                allow_dangerous_extensions,
+            },
+        )
+    }
+
+    pub fn remove_token_from_whitelist(&self, mint: Pubkey) -> Instruction {
+        ix(
+            accounts::RemoveTokenFromWhitelist {
+                admin: self.admin,
+                access_controller: self.access_controller,
+                role: self.role(&Roles::Admin),
+                member: self.member(&self.admin),
+                token_validator: self.token_validator,
+                token_to_remove: mint,
+                whitelisted_token: self.whitelisted_token(&mint),
+                system_program: system_program::ID,
+            },
+            instruction::RemoveTokenFromWhitelist {},
+        )
+    }
+
+    // Only a stricter config than the current one is accepted, see ParameterChange to loosen it
+    pub fn set_token_config(
+        &self,
+        mint: Pubkey,
+        status: TokenStatus,
+        deposit_cap: Option<u64>,
+        client_deposit_cap: Option<u64>,
+        min_deposit: u64,
+    ) -> Instruction {
+        ix(
+            accounts::SetTokenConfig {
+                admin: self.admin,
+                access_controller: self.access_controller,
+                role: self.role(&Roles::Admin),
+                member: self.member(&self.admin),
+                token_validator: self.token_validator,
+                token: mint,
+                whitelisted_token: self.whitelisted_token(&mint),
+                system_program: system_program::ID,
+            },
+            instruction::SetTokenConfig {
+                status,
+                deposit_cap,
+                client_deposit_cap,
+                min_deposit,
+            },
+        )
+    }
+
+    // Rewrites a whitelisted token created before tokens had a config, `total_deposits` is the
+    // sum of the client balances of the token and `ledger_count` the number of its ledgers
+    pub fn migrate_whitelisted_token(
+        &self,
+        mint: Pubkey,
+        total_deposits: u64,
+        ledger_count: u64,
+    ) -> Instruction {
+        ix(
+            accounts::MigrateWhitelistedToken {
+                admin: self.admin,
+                access_controller: self.access_controller,
+                token_validator: self.token_validator,
+                token: mint,
+                whitelisted_token: self.whitelisted_token(&mint),
+                system_program: system_program::ID,
+            },
+            instruction::MigrateWhitelistedToken {
+                total_deposits,
+                ledger_count,
+            },
+        )
+    }
+
+    // Fundlock
+
+    pub fn init_fundlock(&self, trade_lock: i64, release_lock: i64) -> Instruction {
+        ix(
+            accounts::InitFundlock {
+                caller: self.admin,
+                access_controller: self.access_controller,
+                role: self.role(&Roles::Admin),
+                member: self.member(&self.admin),
+                token_validator: self.token_validator,
+                fundlock: self.fundlock,
+                system_program: system_program::ID,
+            },
+            instruction::InitFundlock {
+                trade_lock,
+                release_lock,
+            },
+        )
+    }
+
+    // Rewrites a fundlock created before the withdrawal limit was stored
+    pub fn migrate_fundlock(&self) -> Instruction {
+        ix(
+            accounts::MigrateFundlock {
+                admin: self.admin,
+                access_controller: self.access_controller,
+                fundlock: self.fundlock,
+                system_program: system_program::ID,
+            },
+            instruction::MigrateFundlock {},
+        )
+    }
+
+    // Rewrites the balance held for client_ata created before the release destination was stored,
+    // any payer can sign
+    pub fn migrate_client_balance(
+        &self,
+        payer: Pubkey,
+        client_ata: Pubkey,
+        mint: Pubkey,
+    ) -> Instruction {
+        ix(
+            accounts::MigrateClientBalance {
+                payer,
+                client_balance: self.client_accounts(&mint, &client_ata).client_balance,
+                system_program: system_program::ID,
+            },
+            instruction::MigrateClientBalance {},
+        )
+    }
+
+    // `token_program` is the program owning the mint. The extra accounts of a Token-2022
+    // transfer hook are appended to deposit and release instructions by the caller.
+    pub fn deposit_fundlock(
+        &self,
+        client: Pubkey,
+        client_ata: Pubkey,
+        mint: Pubkey,
+        token_program: Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        let client_accounts = self.client_accounts(&mint, &client_ata);
+        ix(
+            accounts::DepositFundlock {
+                client,
+                access_controller: self.access_controller,
+                token_validator: self.token_validator,
+                fundlock: self.fundlock,
+                token: mint,
+                whitelisted_token: self.whitelisted_token(&mint),
+                fundlock_token_vault: client_accounts.fundlock_token_vault,
+                client_balance: client_accounts.client_balance,
+                client_ata,
+                withdrawals: client_accounts.withdrawals,
+                system_program: system_program::ID,
+                token_program,
+            },
+            instruction::DepositFundlock { amount },
+        )
+    }
+
+    pub fn withdraw_fundlock(
+        &self,
+        client: Pubkey,
+        client_ata: Pubkey,
+        mint: Pubkey,
+        token_program: Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        let client_accounts = self.client_accounts(&mint, &client_ata);
+        ix(
+            accounts::WithdrawFundlock {
+                client,
+                access_controller: self.access_controller,
+                token_validator: self.token_validator,
+                fundlock: self.fundlock,
+                token: mint,
+                whitelisted_token: self.whitelisted_token(&mint),
+                fundlock_token_vault: client_accounts.fundlock_token_vault,
+                client_balance: client_accounts.client_balance,
+                client_ata,
+                withdrawals: client_accounts.withdrawals,
+                system_program: system_program::ID,
+                token_program,
+            },
+            instruction::WithdrawFundlock { amount },
+        )
+    }
+
+    pub fn release_fundlock(
+        &self,
+        client: Pubkey,
+        client_ata: Pubkey,
+        mint: Pubkey,
+        token_program: Pubkey,
+        index: u64,
+    ) -> Instruction {
+        let client_accounts = self.client_accounts(&mint, &client_ata);
+        ix(
+            accounts::ReleaseFundlock {
+                client,
+                access_controller: self.access_controller,
+                token_validator: self.token_validator,
+                fundlock: self.fundlock,
+                token: mint,
+                whitelisted_token: self.whitelisted_token(&mint),
+                fundlock_token_vault: client_accounts.fundlock_token_vault,
+                client_balance: client_accounts.client_balance,
+                client_ata,
+                withdrawals: client_accounts.withdrawals,
+                system_program: system_program::ID,
+                token_program,
+            },
+            instruction::ReleaseFundlock { index },
+        )
+    }
+
+    // client_ata keys the balance, the release goes to destination
+    pub fn release_fundlock_to(
+        &self,
+        client: Pubkey,
+        client_ata: Pubkey,
+        destination: Pubkey,
+        mint: Pubkey,
+        token_program: Pubkey,
+        index: u64,
+    ) -> Instruction {
+        let client_accounts = self.client_accounts(&mint, &client_ata);
+        ix(
+            accounts::ReleaseFundlockTo {
+                client,
+                access_controller: self.access_controller,
+                token_validator: self.token_validator,
+                fundlock: self.fundlock,
+                token: mint,
+                whitelisted_token: self.whitelisted_token(&mint),
+                fundlock_token_vault: client_accounts.fundlock_token_vault,
+                client_balance: client_accounts.client_balance,
+                destination,
+                withdrawals: client_accounts.withdrawals,
+                system_program: system_program::ID,
+                token_program,
+            },
+            instruction::ReleaseFundlockTo { index },
+        )
+    }
+
+    pub fn set_client_destination(
+        &self,
+        client: Pubkey,
+        client_ata: Pubkey,
+        destination: Pubkey,
+        mint: Pubkey,
+    ) -> Instruction {
+        let client_accounts = self.client_accounts(&mint, &client_ata);
+        ix(
+            accounts::SetClientDestination {
+                client,
+                access_controller: self.access_controller,
+                token_validator: self.token_validator,
+                fundlock: self.fundlock,
+                token: mint,
+                fundlock_token_vault: client_accounts.fundlock_token_vault,
+                client_balance: client_accounts.client_balance,
+                destination,
+            },
+            instruction::SetClientDestination {},
+        )
+    }
+
+    // client_ata keys the balance, the release goes to destination
+    pub fn release_all_matured(
+        &self,
+        client: Pubkey,
+        client_ata: Pubkey,
+        destination: Pubkey,
+        mint: Pubkey,
+        token_program: Pubkey,
+    ) -> Instruction {
+        let client_accounts = self.client_accounts(&mint, &client_ata);
+        ix(
+            accounts::ReleaseAllMatured {
+                client,
+                access_controller: self.access_controller,
+                token_validator: self.token_validator,
+                fundlock: self.fundlock,
+                token: mint,
+                whitelisted_token: self.whitelisted_token(&mint),
+                fundlock_token_vault: client_accounts.fundlock_token_vault,
+                client_balance: client_accounts.client_balance,
+                client_ata,
+                destination,
+                withdrawals: client_accounts.withdrawals,
+                system_program: system_program::ID,
+                token_program,
+            },
+            instruction::ReleaseAllMatured {},
+        )
+    }
+
+    pub fn set_keeper_tip(&self, tip: u64, min_release: u64) -> Instruction {
+        ix(
+            accounts::SetKeeperTip {
+                admin: self.admin,
+                access_controller: self.access_controller,
+                role: self.role(&Roles::Admin),
+                member: self.member(&self.admin),
+                token_validator: self.token_validator,
+                fundlock: self.fundlock,
+                keeper_pool: self.keeper_pool(),
+                system_program: system_program::ID,
+            },
+            instruction::SetKeeperTip { tip, min_release },
+        )
+    }
+
+    // Releases the matured withdrawals of the balance held for client_ata to its registered
+    // destination, claiming the keeper tip when claim_tip is set
+    pub fn crank_release(
+        &self,
+        keeper: Pubkey,
+        client_ata: Pubkey,
+        destination: Pubkey,
+        mint: Pubkey,
+        token_program: Pubkey,
+        claim_tip: bool,
+    ) -> Instruction {
+        let client_accounts = self.client_accounts(&mint, &client_ata);
+        ix(
+            accounts::CrankRelease {
+                keeper,
+                access_controller: self.access_controller,
+                token_validator: self.token_validator,
+                fundlock: self.fundlock,
+                token: mint,
+                whitelisted_token: self.whitelisted_token(&mint),
+                fundlock_token_vault: client_accounts.fundlock_token_vault,
+                client_balance: client_accounts.client_balance,
+                destination,
+                withdrawals: client_accounts.withdrawals,
+                keeper_pool: claim_tip.then(|| self.keeper_pool()),
+                token_program,
+            },
+            instruction::CrankRelease {},
+        )
+    }
+
+    // Releases the matured withdrawals of client's native SOL balance back to client
+    pub fn crank_release_sol(
+        &self,
+        keeper: Pubkey,
+        client: Pubkey,
+        claim_tip: bool,
+    ) -> Instruction {
+        let client_accounts = self.client_accounts(&native_mint::ID, &client);
+        ix(
+            accounts::CrankReleaseSol {
+                keeper,
+                access_controller: self.access_controller,
+                token_validator: self.token_validator,
+                fundlock: self.fundlock,
+                token: native_mint::ID,
+                whitelisted_token: self.whitelisted_token(&native_mint::ID),
+                fundlock_token_vault: client_accounts.fundlock_token_vault,
+                client_balance: client_accounts.client_balance,
+                client,
+                withdrawals: client_accounts.withdrawals,
+                unwrap_account: pda::sol_release(&client_accounts.fundlock_token_vault, &client).0,
+                keeper_pool: claim_tip.then(|| self.keeper_pool()),
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+            },
+            instruction::CrankReleaseSol {},
+        )
+    }
+
+    // Native SOL balances are keyed by the client's system account in place of a token account
+
+    pub fn deposit_fundlock_sol(&self, client: Pubkey, amount: u64) -> Instruction {
+        let client_accounts = self.client_accounts(&native_mint::ID, &client);
+        ix(
+            accounts::DepositFundlockSol {
+                client,
+                access_controller: self.access_controller,
+                token_validator: self.token_validator,
+                fundlock: self.fundlock,
+                token: native_mint::ID,
+                whitelisted_token: self.whitelisted_token(&native_mint::ID),
+                fundlock_token_vault: client_accounts.fundlock_token_vault,
+                client_balance: client_accounts.client_balance,
+                withdrawals: client_accounts.withdrawals,
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+            },
+            instruction::DepositFundlockSol { amount },
+        )
+    }
+
+    pub fn withdraw_fundlock_sol(&self, client: Pubkey, amount: u64) -> Instruction {
+        let client_accounts = self.client_accounts(&native_mint::ID, &client);
+        ix(
+            accounts::WithdrawFundlockSol {
+                client,
+                access_controller: self.access_controller,
+                token_validator: self.token_validator,
+                fundlock: self.fundlock,
+                token: native_mint::ID,
+                whitelisted_token: self.whitelisted_token(&native_mint::ID),
+                fundlock_token_vault: client_accounts.fundlock_token_vault,
+                client_balance: client_accounts.client_balance,
+                withdrawals: client_accounts.withdrawals,
+                system_program: system_program::ID,
+            },
+            instruction::WithdrawFundlockSol { amount },
+        )
+    }
+
+    pub fn release_fundlock_sol(&self, client: Pubkey, index: u64) -> Instruction {
+        let client_accounts = self.client_accounts(&native_mint::ID, &client);
+        ix(
+            accounts::ReleaseFundlockSol {
+                client,
+                access_controller: self.access_controller,
+                token_validator: self.token_validator,
+                fundlock: self.fundlock,
+                token: native_mint::ID,
+                whitelisted_token: self.whitelisted_token(&native_mint::ID),
+                fundlock_token_vault: client_accounts.fundlock_token_vault,
+                client_balance: client_accounts.client_balance,
+                withdrawals: client_accounts.withdrawals,
+                unwrap_account: pda::sol_release(&client_accounts.fundlock_token_vault, &client).0,
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+            },
+            instruction::ReleaseFundlockSol { index },
+        )
+    }
+
+    pub fn release_all_matured_sol(&self, client: Pubkey) -> Instruction {
+        let client_accounts = self.client_accounts(&native_mint::ID, &client);
+        ix(
+            accounts::ReleaseAllMaturedSol {
+                client,
+                access_controller: self.access_controller,
+                token_validator: self.token_validator,
+                fundlock: self.fundlock,
+                token: native_mint::ID,
+                whitelisted_token: self.whitelisted_token(&native_mint::ID),
+                fundlock_token_vault: client_accounts.fundlock_token_vault,
+                client_balance: client_accounts.client_balance,
+                withdrawals: client_accounts.withdrawals,
+                unwrap_account: pda::sol_release(&client_accounts.fundlock_token_vault, &client).0,
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+            },
+            instruction::ReleaseAllMaturedSol {},
+        )
+    }
+
+    // The balance key is the client's token account, or the client itself for native SOL
+    pub fn cancel_withdrawal(
+        &self,
+        client: Pubkey,
+        balance_key: Pubkey,
+        mint: Pubkey,
+        index: u64,
+        amount: u64,
+    ) -> Instruction {
+        let client_accounts = self.client_accounts(&mint, &balance_key);
+        ix(
+            accounts::CancelWithdrawal {
+                client,
+                access_controller: self.access_controller,
+                token_validator: self.token_validator,
+                fundlock: self.fundlock,
+                token: mint,
+                fundlock_token_vault: client_accounts.fundlock_token_vault,
+                client_balance: client_accounts.client_balance,
+                withdrawals: client_accounts.withdrawals,
+            },
+            instruction::CancelWithdrawal { index, amount },
+        )
+    }
+
+    // Kamino
+
+    pub fn deposit_kamino(
+        &self,
+        reserve: &KaminoReserve,
+        client: Pubkey,
+        client_ata: Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        let fundlock_token_vault = self.fundlock_token_vault(&reserve.liquidity_mint);
+        ix(
+            accounts::DepositKamino {
+                client,
+                access_controller: self.access_controller,
+                token_validator: self.token_validator,
+                fundlock: self.fundlock,
+                token: reserve.liquidity_mint,
+                whitelisted_token: self.whitelisted_token(&reserve.liquidity_mint),
+                fundlock_token_vault,
+                client_ata,
+                client_balance: pda::client_balance(&fundlock_token_vault, &client_ata).0,
+                system_program: system_program::ID,
+                token_program: reserve.liquidity_token_program,
+                collateral_token_program: spl_token::ID,
+                reserve: reserve.reserve,
+                lending_market: reserve.lending_market,
+                lending_market_authority: reserve.lending_market_authority,
+                reserve_liquidity_supply: reserve.reserve_liquidity_supply,
+                reserve_collateral_token: reserve.reserve_collateral_mint,
+                fundlock_collateral_vault: pda::fundlock_collateral_vault(
+                    &fundlock_token_vault,
+                    &reserve.reserve_collateral_mint,
+                )
+                .0,
+                kamino_program: kamino_program_id(),
+                instructions: sysvar::instructions::ID,
+            },
+            instruction::DepositKamino { amount },
+        )
+    }
+
+    pub fn redeem_kamino(
+        &self,
+        reserve: &KaminoReserve,
+        client: Pubkey,
+        client_ata: Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        let fundlock_token_vault = self.fundlock_token_vault(&reserve.liquidity_mint);
+        ix(
+            accounts::ReedemKamino {
+                client,
+                access_controller: self.access_controller,
+                token_validator: self.token_validator,
+                fundlock: self.fundlock,
+                token: reserve.liquidity_mint,
+                whitelisted_token: self.whitelisted_token(&reserve.liquidity_mint),
+                fundlock_token_vault,
+                client_ata,
+                client_balance: pda::client_balance(&fundlock_token_vault, &client_ata).0,
+                system_program: system_program::ID,
+                token_program: reserve.liquidity_token_program,
+                collateral_token_program: spl_token::ID,
+                reserve: reserve.reserve,
+                lending_market: reserve.lending_market,
+                lending_market_authority: reserve.lending_market_authority,
+                reserve_liquidity_supply: reserve.reserve_liquidity_supply,
+                reserve_collateral_token: reserve.reserve_collateral_mint,
+                fundlock_collateral_vault: pda::fundlock_collateral_vault(
+                    &fundlock_token_vault,
+                    &reserve.reserve_collateral_mint,
+                )
+                .0,
+                kamino_program: kamino_program_id(),
+                instructions: sysvar::instructions::ID,
+            },
+            instruction::RedeemKamino { amount },
+        )
+    }
+}
+
+fn kamino_program_id() -> Pubkey {
+    use anchor_lang::Id;
+    ithaca_smart_contract_sol::KLend::id()
+}
+
+// Kamino reserve accounts used to stake the fundlock liquidity of a whitelisted token
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KaminoReserve {
+    pub reserve: Pubkey,
+    pub lending_market: Pubkey,
+    pub lending_market_authority: Pubkey,
+    pub liquidity_mint: Pubkey,
+    // Program of the liquidity mint, the collateral mint is always an SPL Token mint
+    pub liquidity_token_program: Pubkey,
+    pub reserve_liquidity_supply: Pubkey,
+    pub reserve_collateral_mint: Pubkey,
+}
+
+// Ledger of an underlying and strike token pair
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Market {
+    pub ithaca: Ithaca,
+    pub underlying_token: Pubkey,
+    pub strike_token: Pubkey,
+    pub ledger: Pubkey,
+}
+
+// Fund movement of a client identified by its underlying and strike token accounts
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FundMovement {
+    pub underlying_ata: Pubkey,
+    pub strike_ata: Pubkey,
+    pub underlying_amount: i64,
+    pub strike_amount: i64,
+}
+
+// Liquidation of a client's positions and balances in favour of the ledger's insurance fund,
+// whose balances are identified by its token accounts
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Liquidation {
+    pub client: Pubkey,
+    pub client_underlying_ata: Pubkey,
+    pub client_strike_ata: Pubkey,
+    pub insurance_underlying_ata: Pubkey,
+    pub insurance_strike_ata: Pubkey,
+    // Ledger's insurance fund, which takes over the closed out positions
+    pub insurance_fund: Pubkey,
+    // Contracts whose positions of the client are closed out
+    pub contract_ids: Vec<u64>,
+    pub underlying_amount: u64,
+    pub strike_amount: u64,
+}
+
+impl Market {
+    pub fn new(ithaca: Ithaca, underlying_token: Pubkey, strike_token: Pubkey) -> Self {
+        let (ledger, _) = pda::ledger(
+            &ithaca.access_controller,
+            &ithaca.token_validator,
+            &underlying_token,
+            &strike_token,
+        );
+        Self {
+            ithaca,
+            underlying_token,
+            strike_token,
+            ledger,
+        }
+    }
+
+    pub fn contract(&self, contract_id: u64) -> Pubkey {
+        pda::contract(&self.ledger, contract_id).0
+    }
+
+    pub fn position(&self, contract_id: u64, client: &Pubkey) -> Pubkey {
+        pda::position(&self.contract(contract_id), client).0
+    }
+
+    pub fn init_ledger(&self, rent_payer: Pubkey, insurance_fund: Pubkey) -> Instruction {
+        let ithaca = &self.ithaca;
+        ix(
+            accounts::InitLedger {
+                admin: ithaca.admin,
+                access_controller: ithaca.access_controller,
+                role: ithaca.role(&Roles::Admin),
+                member: ithaca.member(&ithaca.admin),
+                token_validator: ithaca.token_validator,
+                fundlock: ithaca.fundlock,
+                underlying_token: self.underlying_token,
+                whitelisted_underlying_token: ithaca.whitelisted_token(&self.underlying_token),
+                strike_token: self.strike_token,
+                whitelisted_strike_token: ithaca.whitelisted_token(&self.strike_token),
+                ledger: self.ledger,
+                system_program: system_program::ID,
+            },
+            instruction::InitLedger {
+                rent_payer,
+                insurance_fund,
+            },
+        )
+    }
+
+    // Rewrites a ledger created before backend ids were remembered
+    pub fn migrate_ledger(&self, rent_payer: Pubkey, insurance_fund: Pubkey) -> Instruction {
+        let ithaca = &self.ithaca;
+        ix(
+            accounts::MigrateLedger {
+                admin: ithaca.admin,
+                access_controller: ithaca.access_controller,
+                ledger: self.ledger,
+                system_program: system_program::ID,
+            },
+            instruction::MigrateLedger {
+                rent_payer,
+                insurance_fund,
+            },
+        )
+    }
+
+    // Remaining accounts hold a (contract, position) pair for every position, contract params
+    // are only required for the contracts that don't exist yet
+    pub fn create_contracts_and_positions(
+        &self,
+        caller: Pubkey,
+        positions: Vec<PositionsParam>,
+        contract_params: Vec<ContractParams>,
+        backend_id: u64,
+    ) -> Instruction {
+        let ithaca = &self.ithaca;
+        let remaining_accounts = positions
+            .iter()
+            .flat_map(|position| self.position_accounts(position.contract_id, &position.client))
+            .collect::<Vec<_>>();
+        let mut instruction = ix(
+            accounts::CreateOrUpdatePositions {
+                caller,
+                access_controller: ithaca.access_controller,
+                role_util: ithaca.role(&Roles::UtilityAccount),
+                member_util: ithaca.member(&caller),
+                token_validator: ithaca.token_validator,
+                underlying_token: self.underlying_token,
+                whitelisted_underlying_token: ithaca.whitelisted_token(&self.underlying_token),
+                strike_token: self.strike_token,
+                whitelisted_strike_token: ithaca.whitelisted_token(&self.strike_token),
+                ledger: self.ledger,
+                system_program: system_program::ID,
+            },
+            instruction::CreateContractsAndPositions {
+                position_params: positions,
+                contract_params,
+                backend_id,
+            },
+        );
+        instruction.accounts.extend(remaining_accounts);
+        instruction
+    }
+
+    pub fn settle_contract(
+        &self,
+        caller: Pubkey,
+        contract_id: u64,
+        settlement_price: u64,
+    ) -> Instruction {
+        let ithaca = &self.ithaca;
+        ix(
+            accounts::SettleContract {
+                caller,
+                access_controller: ithaca.access_controller,
+                role: ithaca.role(&Roles::UtilityAccount),
+                member: ithaca.member(&caller),
+                ledger: self.ledger,
+                contract: self.contract(contract_id),
+            },
+            instruction::SettleContract {
+                contract_id,
+                settlement_price,
+            },
+        )
+    }
+
+    // Remaining accounts hold every contract followed by the positions of the given clients
+    pub fn close_positions(
+        &self,
+        caller: Pubkey,
+        rent_payer: Pubkey,
+        contracts: &[(u64, Vec<Pubkey>)],
+    ) -> Instruction {
+        let ithaca = &self.ithaca;
+        let mut instruction = ix(
+            accounts::ClosePositions {
+                caller,
+                access_controller: ithaca.access_controller,
+                role: ithaca.role(&Roles::UtilityAccount),
+                member: ithaca.member(&caller),
+                ledger: self.ledger,
+                rent_payer,
+            },
+            instruction::ClosePositions {},
+        );
+        for (contract_id, clients) in contracts {
+            let contract = self.contract(*contract_id);
+            instruction.accounts.push(AccountMeta::new(contract, false));
+            instruction.accounts.extend(
+                clients
+                    .iter()
+                    .map(|client| AccountMeta::new(pda::position(&contract, client).0, false)),
+            );
+        }
+        instruction
+    }
+
+    // Remaining accounts hold the underlying balance, strike balance, underlying withdrawals
+    // and strike withdrawals of every client, in the order of the fund movements
+    pub fn update_fund_movements(
+        &self,
+        caller: Pubkey,
+        fund_movements: &[FundMovement],
+        backend_id: u64,
+    ) -> Instruction {
+        let ithaca = &self.ithaca;
+        let mut instruction = ix(
+            accounts::UpdateFundMovements {
+                caller,
+                access_controller: ithaca.access_controller,
+                role: ithaca.role(&Roles::UtilityAccount),
+                member: ithaca.member(&caller),
+                token_validator: ithaca.token_validator,
+                fundlock: ithaca.fundlock,
+                underlying_token: self.underlying_token,
+                whitelisted_underlying_token: ithaca.whitelisted_token(&self.underlying_token),
+                strike_token: self.strike_token,
+                whitelisted_strike_token: ithaca.whitelisted_token(&self.strike_token),
+                ledger: self.ledger,
+                system_program: system_program::ID,
+            },
+            instruction::UpdateFundMovements {
+                fund_movements: fund_movements
+                    .iter()
+                    .map(|fund_movement| FundMovementParamOptimized {
+                        underlying_amount: fund_movement.underlying_amount,
+                        strike_amount: fund_movement.strike_amount,
+                    })
+                    .collect(),
+                backend_id,
+            },
+        );
+        for fund_movement in fund_movements {
+            let underlying =
+                ithaca.client_accounts(&self.underlying_token, &fund_movement.underlying_ata);
+            let strike = ithaca.client_accounts(&self.strike_token, &fund_movement.strike_ata);
+            instruction.accounts.extend([
+                AccountMeta::new(underlying.client_balance, false),
+                AccountMeta::new(strike.client_balance, false),
+                AccountMeta::new(underlying.withdrawals, false),
+                AccountMeta::new(strike.withdrawals, false),
+            ]);
+        }
+        instruction
+    }
+
+    // Remaining accounts hold a (contract, position, insurance fund position) triple for every
+    // liquidated contract
+    pub fn liquidate_client(
+        &self,
+        liquidator: Pubkey,
+        liquidation: Liquidation,
+        backend_id: u64,
+    ) -> Instruction {
+        let ithaca = &self.ithaca;
+        let remaining_accounts = liquidation
+            .contract_ids
+            .iter()
+            .flat_map(|contract_id| {
+                let [contract, position] =
+                    self.position_accounts(*contract_id, &liquidation.client);
+                let insurance_position = AccountMeta::new(
+                    pda::position(&contract.pubkey, &liquidation.insurance_fund).0,
+                    false,
+                );
+                [contract, position, insurance_position]
+            })
+            .collect::<Vec<_>>();
+        let mut instruction = ix(
+            accounts::LiquidateClient {
+                liquidator,
+                access_controller: ithaca.access_controller,
+                role_liquidator: ithaca.role(&Roles::Liquidator),
+                member_liquidator: ithaca.member(&liquidator),
+                token_validator: ithaca.token_validator,
+                fundlock: ithaca.fundlock,
+                underlying_token: self.underlying_token,
+                whitelisted_underlying_token: ithaca.whitelisted_token(&self.underlying_token),
+                strike_token: self.strike_token,
+                whitelisted_strike_token: ithaca.whitelisted_token(&self.strike_token),
+                ledger: self.ledger,
+                fundlock_underlying_vault: ithaca.fundlock_token_vault(&self.underlying_token),
+                fundlock_strike_vault: ithaca.fundlock_token_vault(&self.strike_token),
+                client_underlying_balance: ithaca
+                    .client_balance(&self.underlying_token, &liquidation.client_underlying_ata),
+                client_strike_balance: ithaca
+                    .client_balance(&self.strike_token, &liquidation.client_strike_ata),
+                insurance_underlying_balance: ithaca.client_balance(
+                    &self.underlying_token,
+                    &liquidation.insurance_underlying_ata,
+                ),
+                insurance_strike_balance: ithaca
+                    .client_balance(&self.strike_token, &liquidation.insurance_strike_ata),
+                system_program: system_program::ID,
+            },
+            instruction::LiquidateClient {
+                client: liquidation.client,
+                underlying_amount: liquidation.underlying_amount,
+                strike_amount: liquidation.strike_amount,
+                backend_id,
+            },
+        );
+        instruction.accounts.extend(remaining_accounts);
+        instruction
+    }
+
+    fn position_accounts(&self, contract_id: u64, client: &Pubkey) -> [AccountMeta; 2] {
+        let contract = self.contract(contract_id);
+        [
+            AccountMeta::new(contract, false),
+            AccountMeta::new(pda::position(&contract, client).0, false),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_are_followed_by_contract_and_position_pairs() {
+        let market = Market::new(
+            Ithaca::new(Pubkey::new_unique()),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let caller = Pubkey::new_unique();
+        let clients = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let positions = clients
+            .iter()
+            .enumerate()
+            .map(|(i, client)| PositionsParam {
+                contract_id: i as u64,
+                client: *client,
+                size: 10,
+            })
+            .collect();
+
+        let instruction = market.create_contracts_and_positions(caller, positions, vec![], 1);
+        let remaining_accounts = &instruction.accounts[instruction.accounts.len() - 4..];
+        for (i, client) in clients.iter().enumerate() {
+            assert_eq!(remaining_accounts[i * 2].pubkey, market.contract(i as u64));
+            assert_eq!(
+                remaining_accounts[i * 2 + 1].pubkey,
+                market.position(i as u64, client)
+            );
+            assert!(remaining_accounts[i * 2 + 1].is_writable);
+        }
+    }
+
+    #[test]
+    fn fund_movements_are_followed_by_balances_and_withdrawals() {
+        let ithaca = Ithaca::new(Pubkey::new_unique());
+        let market = Market::new(ithaca, Pubkey::new_unique(), Pubkey::new_unique());
+        let fund_movement = FundMovement {
+            underlying_ata: Pubkey::new_unique(),
+            strike_ata: Pubkey::new_unique(),
+            underlying_amount: -5,
+            strike_amount: 5,
+        };
+
+        let instruction = market.update_fund_movements(Pubkey::new_unique(), &[fund_movement], 1);
+        let remaining_accounts = instruction.accounts[instruction.accounts.len() - 4..]
+            .iter()
+            .map(|meta| meta.pubkey)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            remaining_accounts,
+            [
+                ithaca.client_balance(&market.underlying_token, &fund_movement.underlying_ata),
+                ithaca.client_balance(&market.strike_token, &fund_movement.strike_ata),
+                ithaca.withdrawals(&market.underlying_token, &fund_movement.underlying_ata),
+                ithaca.withdrawals(&market.strike_token, &fund_movement.strike_ata),
+            ]
+        );
+    }
+}
@@ -0,0 +1,13 @@
+// Off-chain client for the Ithaca program: PDA derivation, instruction builders and account
+// decoders built on top of the program crate so that seeds and layouts can't drift apart
+pub mod accounts;
+pub mod instructions;
+pub mod pda;
+pub mod roles;
+
+pub use accounts::IthacaAccount;
+pub use instructions::{FundMovement, Ithaca, KaminoReserve, Liquidation, Market};
+pub use ithaca_smart_contract_sol::{
+    ContractParams, FundMovementParamOptimized, ParameterChange, PayoffType, PositionsParam,
+    ProposalAccount, Roles, Subsystem, TokenStatus, ID,
+};
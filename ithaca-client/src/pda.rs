@@ -0,0 +1,195 @@
+use anchor_lang::prelude::Pubkey;
+use ithaca_smart_contract_sol::{Roles, ID};
+
+// PDA derivations mirroring the seeds used by the program's account contexts,
+// every function returns the address along with its canonical bump
+
+// Seeded by the creator of the access controller, which stays fixed across admin transfers
+pub fn access_controller(creator: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"access_controller", creator.as_ref()], &ID)
+}
+
+pub fn role(access_controller: &Pubkey, role: &Roles) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"role",
+            access_controller.as_ref(),
+            role.as_str().as_bytes(),
+        ],
+        &ID,
+    )
+}
+
+// Page `page_index` of the members listed for the role
+pub fn role_members(role: &Pubkey, page_index: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"role_members",
+            role.as_ref(),
+            page_index.to_le_bytes().as_ref(),
+        ],
+        &ID,
+    )
+}
+
+// Holds every role granted to the member
+pub fn member(access_controller: &Pubkey, member: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"member", access_controller.as_ref(), member.as_ref()],
+        &ID,
+    )
+}
+
+// Member accounts created before roles were typed, one per granted role, see migrate_member
+pub fn legacy_member(role: &Pubkey, member: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"member", role.as_ref(), member.as_ref()], &ID)
+}
+
+pub fn token_validator(access_controller: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"token_validator", access_controller.as_ref()], &ID)
+}
+
+pub fn whitelisted_token(token_validator: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"whitelisted_token",
+            token_validator.as_ref(),
+            mint.as_ref(),
+        ],
+        &ID,
+    )
+}
+
+pub fn fundlock(access_controller: &Pubkey, token_validator: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"fundlock",
+            access_controller.as_ref(),
+            token_validator.as_ref(),
+        ],
+        &ID,
+    )
+}
+
+pub fn fundlock_token_vault(fundlock: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"fundlock_token_vault", fundlock.as_ref(), mint.as_ref()],
+        &ID,
+    )
+}
+
+pub fn client_balance(fundlock_token_vault: &Pubkey, client_ata: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"client_balance",
+            fundlock_token_vault.as_ref(),
+            client_ata.as_ref(),
+        ],
+        &ID,
+    )
+}
+
+// wSOL account a native SOL release is unwrapped through, closed in the same instruction
+pub fn sol_release(fundlock_token_vault: &Pubkey, client: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"sol_release",
+            fundlock_token_vault.as_ref(),
+            client.as_ref(),
+        ],
+        &ID,
+    )
+}
+
+pub fn withdrawals(fundlock: &Pubkey, client_balance: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"withdrawals", fundlock.as_ref(), client_balance.as_ref()],
+        &ID,
+    )
+}
+
+pub fn keeper_pool(fundlock: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"keeper_pool", fundlock.as_ref()], &ID)
+}
+
+pub fn ledger(
+    access_controller: &Pubkey,
+    token_validator: &Pubkey,
+    underlying_token: &Pubkey,
+    strike_token: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"ledger",
+            access_controller.as_ref(),
+            token_validator.as_ref(),
+            underlying_token.as_ref(),
+            strike_token.as_ref(),
+        ],
+        &ID,
+    )
+}
+
+pub fn contract(ledger: &Pubkey, contract_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"contract",
+            ledger.as_ref(),
+            contract_id.to_le_bytes().as_ref(),
+        ],
+        &ID,
+    )
+}
+
+pub fn position(contract: &Pubkey, client: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"position", contract.as_ref(), client.as_ref()], &ID)
+}
+
+pub fn fundlock_collateral_vault(
+    fundlock_token_vault: &Pubkey,
+    collateral_mint: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"fundlock_collateral_vault",
+            fundlock_token_vault.as_ref(),
+            collateral_mint.as_ref(),
+        ],
+        &ID,
+    )
+}
+
+pub fn multisig(access_controller: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"multisig", access_controller.as_ref()], &ID)
+}
+
+// Data-less PDA signing the executed proposals, becomes the admin once the admin is transferred
+pub fn multisig_authority(multisig: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"multisig_authority", multisig.as_ref()], &ID)
+}
+
+pub fn proposal(multisig: &Pubkey, proposal_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"proposal",
+            multisig.as_ref(),
+            proposal_id.to_le_bytes().as_ref(),
+        ],
+        &ID,
+    )
+}
+
+pub fn governance(access_controller: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"governance", access_controller.as_ref()], &ID)
+}
+
+pub fn parameter_change(governance: &Pubkey, change_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"parameter_change",
+            governance.as_ref(),
+            change_id.to_le_bytes().as_ref(),
+        ],
+        &ID,
+    )
+}
@@ -0,0 +1,109 @@
+use anchor_lang::prelude::*;
+use ithaca_smart_contract_sol::error::AccessControlError;
+use ithaca_smart_contract_sol::{Role, RoleMembers, ID, ROLE_MEMBERS_PAGE_SIZE};
+
+use crate::pda;
+
+// Role membership rebuilt from the RoleMembers pages of a role
+
+// Addresses of the pages to fetch, in page order
+pub fn page_addresses(role_address: &Pubkey, role: &Role) -> Vec<Pubkey> {
+    (0..role.page_count)
+        .map(|page_index| pda::role_members(role_address, page_index).0)
+        .collect()
+}
+
+// Every member listed for the role, `pages` being the accounts at page_addresses. Members of
+// roles migrated from the untyped layout are only listed once migrated, so the result can
+// be shorter than the role member count until then.
+pub fn members(role_address: &Pubkey, role: &Role, pages: &[RoleMembers]) -> Result<Vec<Pubkey>> {
+    require!(
+        pages.len() as u64 == role.page_count,
+        AccessControlError::InvalidRoleMembersPage
+    );
+    let mut members = Vec::new();
+    for (page_index, page) in pages.iter().enumerate() {
+        require!(
+            page.role == *role_address && page.page_index == page_index as u64,
+            AccessControlError::InvalidRoleMembersPage
+        );
+        members.extend_from_slice(&page.members);
+    }
+    Ok(members)
+}
+
+// Page to pass to grant_role or migrate_member, the first page with room or the next page
+pub fn page_with_room(role: &Role, pages: &[RoleMembers]) -> u64 {
+    pages
+        .iter()
+        .find(|page| page.members.len() < ROLE_MEMBERS_PAGE_SIZE)
+        .map_or(role.page_count, |page| page.page_index)
+}
+
+// Page to pass to renounce_role or accept_admin for a listed member
+pub fn page_listing(member: &Pubkey, pages: &[RoleMembers]) -> Option<u64> {
+    pages
+        .iter()
+        .find(|page| page.members.contains(member))
+        .map(|page| page.page_index)
+}
+
+// Answer of check_role, decoded from the return data of a simulated or confirmed transaction
+pub fn has_role(program_id: &Pubkey, return_data: &[u8]) -> Result<bool> {
+    require_keys_eq!(*program_id, ID, ErrorCode::InvalidProgramId);
+    Ok(bool::try_from_slice(return_data)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ithaca_smart_contract_sol::Roles;
+
+    fn page(role_address: Pubkey, page_index: u64, members: Vec<Pubkey>) -> RoleMembers {
+        RoleMembers {
+            role: role_address,
+            page_index,
+            members,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn members_are_collected_across_pages() {
+        let role_address = Pubkey::new_unique();
+        let role = Role {
+            role: Roles::Liquidator,
+            member_count: ROLE_MEMBERS_PAGE_SIZE as u64 + 1,
+            page_count: 2,
+            bump: 255,
+        };
+        let full: Vec<Pubkey> = (0..ROLE_MEMBERS_PAGE_SIZE)
+            .map(|_| Pubkey::new_unique())
+            .collect();
+        let last = Pubkey::new_unique();
+        let pages = vec![
+            page(role_address, 0, full.clone()),
+            page(role_address, 1, vec![last]),
+        ];
+
+        let listed = members(&role_address, &role, &pages).unwrap();
+        assert_eq!(listed.len() as u64, role.member_count);
+        assert_eq!(listed[..ROLE_MEMBERS_PAGE_SIZE], full[..]);
+        assert_eq!(page_with_room(&role, &pages), 1);
+        assert_eq!(page_listing(&last, &pages), Some(1));
+        assert_eq!(page_listing(&Pubkey::new_unique(), &pages), None);
+
+        // Missing or foreign pages are rejected
+        assert!(members(&role_address, &role, &pages[..1]).is_err());
+        let foreign = vec![pages[0].clone(), page(Pubkey::new_unique(), 1, vec![])];
+        assert!(members(&role_address, &role, &foreign).is_err());
+    }
+
+    #[test]
+    fn check_role_answers_are_only_read_from_ithaca() {
+        assert!(has_role(&ID, &[1]).unwrap());
+        assert!(!has_role(&ID, &[0]).unwrap());
+        assert!(has_role(&Pubkey::new_unique(), &[1]).is_err());
+        assert!(has_role(&ID, &[]).is_err());
+    }
+}
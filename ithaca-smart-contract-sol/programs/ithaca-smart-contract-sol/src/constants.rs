@@ -1,7 +1,20 @@
 use anchor_lang::prelude::*;
 use std::str::FromStr;
 
-pub const ALLOWED_WITHDRAWAL_LIMIT: usize = 5;
+// Withdrawals a client can have queued in a new fundlock, changed through governance
+pub const DEFAULT_WITHDRAWAL_LIMIT: u64 = 5;
+// Highest withdrawal limit, which bounds the size of the withdrawal queues
+pub const MAX_WITHDRAWAL_LIMIT: u64 = 64;
+// Release locks have to be shorter than a week, in seconds
+pub const MAX_RELEASE_LOCK: i64 = 604800;
+// Amount of the highest processed backend ids remembered by each ledger
+pub const BACKEND_ID_WINDOW: usize = 32;
+// Amount of roles, see Roles
+pub const ROLE_COUNT: usize = 4;
+// Amount of members listed in each page of a role's member list
+pub const ROLE_MEMBERS_PAGE_SIZE: usize = 32;
+// Maximum amount of signers of an access controller's multisig
+pub const MAX_MULTISIG_SIGNERS: usize = 10;
 pub const KAMINO_PROGRAM_ID: &str = "KLend2g3cP87fffoy8q1mQqGKjrxjC8boSyAYavgmjD";
 
 #[derive(Clone)]
@@ -13,35 +26,65 @@ impl anchor_lang::Id for KLend {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+// Carried in the role instructions and stored in each Role account, a member holds
+// its roles as a bitmask indexed by the enum discriminant
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Roles {
     Admin,
     UtilityAccount,
     Liquidator,
+    Guardian,
 }
 
 impl Roles {
+    // Role PDAs are still seeded by the role names, so existing role accounts keep their address
     pub fn as_str(&self) -> &'static str {
         match self {
             Roles::Admin => "DEFAULT_ADMIN_ROLE",
             Roles::UtilityAccount => "UTILITY_ACCOUNT_ROLE",
             Roles::Liquidator => "LIQUIDATOR_ROLE",
+            Roles::Guardian => "GUARDIAN_ROLE",
         }
     }
 
-    pub fn from_str(role: &str) -> Option<Self> {
-        match role {
-            "DEFAULT_ADMIN_ROLE" => Some(Roles::Admin),
-            "UTILITY_ACCOUNT_ROLE" => Some(Roles::UtilityAccount),
-            "LIQUIDATOR_ROLE" => Some(Roles::Liquidator),
-            _ => None,
-        }
+    pub fn bit(&self) -> u8 {
+        1 << (*self as u8)
+    }
+
+    pub fn index(&self) -> usize {
+        *self as usize
     }
+}
+
+// Lifecycle of a whitelisted token, from the least to the most restrictive status. Delisted
+// tokens are withdraw only, so that the client balances left in the fundlock can still be
+// taken out.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenStatus {
+    Active,
+    // No new deposits, trading and withdrawals carry on
+    DepositDisabled,
+    // No new deposits or fund movements, only withdrawals and releases
+    WithdrawOnly,
+}
+
+// Parts of the program that can be paused on their own, the access controller holds the paused
+// ones as a bitmask indexed by the enum discriminant. Role management and the multisig are never
+// paused so that a compromised account can still be revoked.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Subsystem {
+    Deposits,
+    Withdrawals,
+    Releases,
+    // Fund movements, settlements, position closing and liquidations
+    Ledger,
+    Kamino,
+    // Token whitelisting, fundlock and ledger initialization and parameter governance
+    Config,
+}
 
-    pub fn is_valid_role(role: &str) -> bool {
-        matches!(
-            role,
-            "DEFAULT_ADMIN_ROLE" | "UTILITY_ACCOUNT_ROLE" | "LIQUIDATOR_ROLE"
-        )
+impl Subsystem {
+    pub fn bit(&self) -> u8 {
+        1 << (*self as u8)
     }
 }
@@ -10,6 +10,30 @@ pub enum AccessControlError {
     LastMember,
     #[msg("This member doesn't not have any role assigned")]
     NoRole,
+    #[msg("Only the pending admin can accept the admin transfer")]
+    UnauthorizedPendingAdmin,
+    #[msg("The current admin cannot be proposed as the new admin")]
+    InvalidPendingAdmin,
+    #[msg("The member already has this role")]
+    RoleAlreadyGranted,
+    #[msg("The account is not a legacy access control account or has already been migrated")]
+    AlreadyMigrated,
+    #[msg("This instruction is paused")]
+    Paused,
+    #[msg("The role members page is full")]
+    RoleMembersPageFull,
+    #[msg("Role members pages have to be created in order")]
+    InvalidRoleMembersPage,
+    #[msg("The member is not listed in this role members page")]
+    MemberNotListed,
+    #[msg("The roles of this member have expired")]
+    RoleExpired,
+    #[msg("The role expiry must be in the future")]
+    InvalidExpiry,
+    #[msg("Only the admin or the member itself can renounce a role")]
+    UnauthorizedRenounce,
+    #[msg("The current admin keeps the admin role until the admin is transferred")]
+    AdminCannotRenounce,
 }
 
 #[error_code]
@@ -18,6 +42,22 @@ pub enum TokenValidatorError {
     NonFungibleToken,
     #[msg("The provided token is not whitelisted")]
     TokenNotWhitelisted,
+    #[msg("The mint has extensions that let others move or lock the vault funds")]
+    DangerousMintExtension,
+    #[msg("Deposits of the token are disabled")]
+    DepositsDisabled,
+    #[msg("The token is withdraw only")]
+    TokenWithdrawOnly,
+    #[msg("The deposit is below the token's minimum deposit")]
+    DepositBelowMinimum,
+    #[msg("The deposit would exceed the token's deposit cap")]
+    DepositCapExceeded,
+    #[msg("The deposit would exceed the token's per client balance deposit cap")]
+    ClientDepositCapExceeded,
+    #[msg("Only a stricter token config can be set directly, loosening it goes through governance")]
+    ConfigNotTightened,
+    #[msg("The account is not a legacy whitelisted token account or has already been migrated")]
+    AlreadyMigrated,
 }
 
 #[error_code]
@@ -26,7 +66,7 @@ pub enum FundlockError {
     AmountZero,
     #[msg("Insufficient funds for the operation")]
     InsufficientFunds,
-    #[msg("The withdrawal queue limit of 5 has been reached")]
+    #[msg("The withdrawal queue limit has been reached")]
     WithdrawalLimitReached,
     #[msg("Withdrawal under this index is not found")]
     InvalidIndex,
@@ -42,6 +82,14 @@ pub enum FundlockError {
     InvalidTradeLock,
     #[msg("The provided release lock is too long")]
     ReleaseLockTooLong,
+    #[msg("The provided release lock is invalid")]
+    InvalidReleaseLock,
+    #[msg("None of the queued withdrawals are past the release lock")]
+    NoMaturedWithdrawals,
+    #[msg("The client balance belongs to another client")]
+    NotBalanceOwner,
+    #[msg("The account is not a legacy fundlock or client balance account or has already been migrated")]
+    AlreadyMigrated,
 }
 
 #[error_code]
@@ -56,5 +104,82 @@ pub enum LedgerError {
     EmptyFundMovementArray,
     #[msg("The provided amounts array is too empty")]
     EmptyAmountsArray,
+    #[msg("A batch with the provided backend id was already processed")]
+    DuplicateBackendId,
+    #[msg("The provided backend id is older than every id remembered by the ledger")]
+    StaleBackendId,
+    #[msg("No contract params were provided for a new contract")]
+    MissingContractParams,
+    #[msg("The provided contract params are invalid")]
+    InvalidContractParams,
+    #[msg("The contract has expired")]
+    ContractExpired,
+    #[msg("The contract has already been settled")]
+    ContractSettled,
+    #[msg("The contract cannot be settled before its expiry")]
+    ContractNotExpired,
+    #[msg("Only flat positions can be closed")]
+    PositionNotClosable,
+    #[msg("The contract has been closed, its id cannot be used again")]
+    ContractClosed,
+    #[msg("The account is not a legacy ledger account or has already been migrated")]
+    AlreadyMigrated,
+}
+
+#[error_code]
+pub enum LiquidationError {
+    #[msg("Nothing to liquidate: no positions or amounts were provided")]
+    NothingToLiquidate,
+    #[msg("The liquidator cannot liquidate its own balances")]
+    SelfLiquidation,
+    #[msg("The provided account does not belong to the liquidated client")]
+    ClientMismatch,
+    #[msg("The provided account does not belong to the ledger's insurance fund")]
+    InsuranceFundMismatch,
+    #[msg("The insurance fund cannot be liquidated")]
+    InsuranceFundLiquidation,
+}
 
+#[error_code]
+pub enum MultisigError {
+    #[msg("The threshold must be between one and the amount of signers")]
+    InvalidThreshold,
+    #[msg("The multisig has too many signers")]
+    TooManySigners,
+    #[msg("The multisig signers must be unique")]
+    DuplicateSigner,
+    #[msg("The caller is not a signer of the multisig")]
+    NotASigner,
+    #[msg("The signer has already approved this proposal")]
+    AlreadyApproved,
+    #[msg("The proposal has already been executed")]
+    ProposalExecuted,
+    #[msg("The proposal does not have enough approvals")]
+    ThresholdNotReached,
+    #[msg("The multisig signers changed after the proposal was created")]
+    StaleProposal,
+    #[msg("The provided accounts do not match the proposed instruction")]
+    ProposalAccountMismatch,
+}
+
+#[error_code]
+pub enum GovernanceError {
+    #[msg("The ETA must be at least the minimum delay away")]
+    EtaTooEarly,
+    #[msg("The parameter change cannot be executed before its ETA")]
+    ChangeNotReady,
+    #[msg("The target account does not match the parameter change")]
+    InvalidTarget,
+    #[msg("Invalid value for the parameter")]
+    InvalidParameterValue,
+    #[msg("The token precision cannot change once ledgers derived their multipliers from it")]
+    TokenHasLedgers,
+}
+
+#[error_code]
+pub enum MathError {
+    #[msg("Arithmetic overflow or underflow")]
+    MathOverflow,
+    #[msg("The operation would result in a negative balance")]
+    NegativeBalance,
 }
@@ -0,0 +1,389 @@
+use crate::constants::{Roles, TokenStatus};
+use crate::state::{ParameterChange, PayoffType};
+use anchor_lang::prelude::*;
+
+// Access control events
+#[event]
+pub struct AccessControllerInitialized {
+    pub access_controller: Pubkey,
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct AdminProposed {
+    pub access_controller: Pubkey,
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+}
+
+#[event]
+pub struct AdminTransferred {
+    pub access_controller: Pubkey,
+    pub previous_admin: Pubkey,
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct RoleGranted {
+    pub access_controller: Pubkey,
+    pub role: Roles,
+    pub member: Pubkey,
+    pub member_count: u64,
+    // Role members page listing the member
+    pub page_index: u64,
+    pub expires_at: Option<i64>,
+}
+
+#[event]
+pub struct RoleRenounced {
+    pub access_controller: Pubkey,
+    pub role: Roles,
+    pub member: Pubkey,
+    // The admin or the member itself
+    pub renounced_by: Pubkey,
+    pub member_count: u64,
+    // Role members page listing the member
+    pub page_index: u64,
+}
+
+#[event]
+pub struct AccessControllerMigrated {
+    pub access_controller: Pubkey,
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct RoleMigrated {
+    pub access_controller: Pubkey,
+    pub role: Roles,
+    pub member_count: u64,
+}
+
+#[event]
+pub struct MemberMigrated {
+    pub access_controller: Pubkey,
+    pub role: Roles,
+    pub member: Pubkey,
+}
+
+// `paused_subsystems` is the bitmask stored in the access controller
+#[event]
+pub struct PauseStateChanged {
+    pub access_controller: Pubkey,
+    pub guardian: Pubkey,
+    pub paused: bool,
+    pub paused_subsystems: u8,
+    pub releases_allowed: bool,
+}
+
+// Multisig events
+#[event]
+pub struct MultisigInitialized {
+    pub access_controller: Pubkey,
+    pub multisig: Pubkey,
+    pub authority: Pubkey,
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
+#[event]
+pub struct MultisigSignersChanged {
+    pub multisig: Pubkey,
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+    pub signers_version: u64,
+}
+
+#[event]
+pub struct ProposalCreated {
+    pub multisig: Pubkey,
+    pub proposal: Pubkey,
+    pub proposal_id: u64,
+    pub proposer: Pubkey,
+}
+
+#[event]
+pub struct ProposalApproved {
+    pub multisig: Pubkey,
+    pub proposal_id: u64,
+    pub signer: Pubkey,
+    pub approvals: u8,
+}
+
+#[event]
+pub struct ProposalExecuted {
+    pub multisig: Pubkey,
+    pub proposal_id: u64,
+    pub executor: Pubkey,
+}
+
+// Governance events
+#[event]
+pub struct GovernanceInitialized {
+    pub access_controller: Pubkey,
+    pub governance: Pubkey,
+    pub min_delay: i64,
+}
+
+#[event]
+pub struct ParameterChangeScheduled {
+    pub governance: Pubkey,
+    pub change_id: u64,
+    pub target: Pubkey,
+    pub change: ParameterChange,
+    pub eta: i64,
+}
+
+#[event]
+pub struct ParameterChangeCancelled {
+    pub governance: Pubkey,
+    pub change_id: u64,
+}
+
+#[event]
+pub struct ParameterChangeExecuted {
+    pub governance: Pubkey,
+    pub change_id: u64,
+    pub target: Pubkey,
+    pub change: ParameterChange,
+}
+
+// Token validator events
+#[event]
+pub struct TokenValidatorInitialized {
+    pub access_controller: Pubkey,
+    pub token_validator: Pubkey,
+}
+
+#[event]
+pub struct TokenWhitelisted {
+    pub token_validator: Pubkey,
+    pub token_mint: Pubkey,
+    pub token_decimals: u8,
+    pub token_precision: u8,
+}
+
+#[event]
+pub struct TokenRemovedFromWhitelist {
+    pub token_validator: Pubkey,
+    pub token_mint: Pubkey,
+}
+
+#[event]
+pub struct TokenConfigUpdated {
+    pub token_validator: Pubkey,
+    pub token_mint: Pubkey,
+    pub status: TokenStatus,
+    pub deposit_cap: Option<u64>,
+    pub client_deposit_cap: Option<u64>,
+    pub min_deposit: u64,
+}
+
+#[event]
+pub struct WhitelistedTokenMigrated {
+    pub token_validator: Pubkey,
+    pub token_mint: Pubkey,
+    pub total_deposits: u64,
+    pub ledger_count: u64,
+}
+
+// Fundlock events
+#[event]
+pub struct FundlockInitialized {
+    pub fundlock: Pubkey,
+    pub trade_lock: i64,
+    pub release_lock: i64,
+    pub withdrawal_limit: u64,
+}
+
+#[event]
+pub struct FundlockMigrated {
+    pub fundlock: Pubkey,
+    pub withdrawal_limit: u64,
+}
+
+#[event]
+pub struct ClientBalanceMigrated {
+    pub client_balance: Pubkey,
+    pub client: Pubkey,
+    pub destination: Pubkey,
+}
+
+#[event]
+pub struct Deposited {
+    pub fundlock: Pubkey,
+    pub client: Pubkey,
+    pub token: Pubkey,
+    pub amount: u64,
+    pub balance: u64,
+}
+
+#[event]
+pub struct WithdrawalQueued {
+    pub fundlock: Pubkey,
+    pub client: Pubkey,
+    pub token: Pubkey,
+    pub amount: u64,
+    pub index: u64,
+    pub timestamp: i64,
+    pub active_withdrawals_amount: u64,
+}
+
+#[event]
+pub struct WithdrawalReleased {
+    pub fundlock: Pubkey,
+    pub client: Pubkey,
+    pub token: Pubkey,
+    pub amount: u64,
+    pub index: u64,
+    pub active_withdrawals_amount: u64,
+}
+
+#[event]
+pub struct MaturedWithdrawalsReleased {
+    pub fundlock: Pubkey,
+    pub client: Pubkey,
+    pub token: Pubkey,
+    pub amount: u64,
+    pub released_count: u64,
+    pub active_withdrawals_amount: u64,
+}
+
+#[event]
+pub struct KeeperTipSet {
+    pub fundlock: Pubkey,
+    pub keeper_pool: Pubkey,
+    pub tip: u64,
+    pub min_release: u64,
+}
+
+#[event]
+pub struct KeeperTipped {
+    pub keeper_pool: Pubkey,
+    pub keeper: Pubkey,
+    pub tip: u64,
+}
+
+#[event]
+pub struct ClientDestinationSet {
+    pub fundlock: Pubkey,
+    pub client: Pubkey,
+    pub token: Pubkey,
+    pub client_balance: Pubkey,
+    pub destination: Pubkey,
+}
+
+#[event]
+pub struct WithdrawalCancelled {
+    pub fundlock: Pubkey,
+    pub client: Pubkey,
+    pub token: Pubkey,
+    pub amount: u64,
+    pub index: u64,
+    pub balance: u64,
+    pub active_withdrawals_amount: u64,
+}
+
+// Ledger events
+#[event]
+pub struct LedgerInitialized {
+    pub ledger: Pubkey,
+    pub underlying_token: Pubkey,
+    pub strike_token: Pubkey,
+    pub underlying_multiplier: i64,
+    pub strike_multiplier: i64,
+    pub rent_payer: Pubkey,
+    pub insurance_fund: Pubkey,
+}
+
+#[event]
+pub struct LedgerMigrated {
+    pub ledger: Pubkey,
+    pub rent_payer: Pubkey,
+    pub insurance_fund: Pubkey,
+}
+
+#[event]
+pub struct ContractCreated {
+    pub ledger: Pubkey,
+    pub contract_id: u64,
+    pub expiry: i64,
+    pub strike: u64,
+    pub payoff_type: PayoffType,
+}
+
+#[event]
+pub struct ContractSettled {
+    pub ledger: Pubkey,
+    pub contract_id: u64,
+    pub settlement_price: u64,
+}
+
+#[event]
+pub struct ContractClosed {
+    pub ledger: Pubkey,
+    pub contract_id: u64,
+    pub rent_payer: Pubkey,
+}
+
+#[event]
+pub struct PositionUpdated {
+    pub ledger: Pubkey,
+    pub contract_id: u64,
+    pub client: Pubkey,
+    // Net size after the change
+    pub size: i64,
+    pub size_change: i64,
+    pub backend_id: u64,
+}
+
+#[event]
+pub struct PositionClosed {
+    pub ledger: Pubkey,
+    pub contract_id: u64,
+    pub client: Pubkey,
+    pub rent_payer: Pubkey,
+}
+
+// Emitted once per client and token, `amount` is the signed change applied to the balance
+// and `funded_from_withdrawals` is the part taken from the client's withdrawal queue
+#[event]
+pub struct FundMovementApplied {
+    pub ledger: Pubkey,
+    pub client: Pubkey,
+    pub token: Pubkey,
+    pub amount: i64,
+    pub balance: u64,
+    pub funded_from_withdrawals: u64,
+    pub backend_id: u64,
+}
+
+#[event]
+pub struct ClientLiquidated {
+    pub ledger: Pubkey,
+    pub client: Pubkey,
+    pub liquidator: Pubkey,
+    pub insurance_fund: Pubkey,
+    pub underlying_amount: u64,
+    pub strike_amount: u64,
+    pub backend_id: u64,
+}
+
+// Kamino events
+#[event]
+pub struct KaminoDeposited {
+    pub fundlock: Pubkey,
+    pub client: Pubkey,
+    pub token: Pubkey,
+    pub amount: u64,
+    pub collateral_amount: u64,
+}
+
+#[event]
+pub struct KaminoRedeemed {
+    pub fundlock: Pubkey,
+    pub client: Pubkey,
+    pub token: Pubkey,
+    pub collateral_amount: u64,
+    pub liquidity_amount: u64,
+}
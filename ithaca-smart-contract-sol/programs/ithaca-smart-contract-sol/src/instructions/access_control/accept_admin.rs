@@ -0,0 +1,101 @@
+use crate::error::AccessControlError;
+use crate::events::AdminTransferred;
+use crate::math;
+use crate::state::access_controller_state::{AccessController, Member, Role, RoleMembers};
+use crate::Roles;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+#[instruction(page_index: u64)]
+pub struct AcceptAdmin<'info> {
+    #[account(mut)]
+    pub new_admin: Signer<'info>,
+    /// CHECK: Current admin, only receives the rent of its admin role membership
+    #[account(mut, address = access_controller.admin @ AccessControlError::UnauthorizedAdmin)]
+    pub previous_admin: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+        constraint = access_controller.pending_admin == Some(new_admin.key()) @ AccessControlError::UnauthorizedPendingAdmin
+    )]
+    pub access_controller: Account<'info, AccessController>,
+    #[account(
+        mut,
+        seeds = [b"role".as_ref(), access_controller.key().as_ref(), Roles::Admin.as_str().as_bytes()],
+        bump = role.bump
+    )]
+    pub role: Account<'info, Role>,
+    // The admin role membership moves from the previous to the new admin
+    #[account(
+        mut,
+        seeds = [b"member".as_ref(), access_controller.key().as_ref(), previous_admin.key().as_ref()],
+        bump = previous_admin_member.bump
+    )]
+    pub previous_admin_member: Account<'info, Member>,
+    #[account(
+        init_if_needed,
+        payer = new_admin,
+        seeds = [b"member".as_ref(), access_controller.key().as_ref(), new_admin.key().as_ref()],
+        space = Member::INIT_SPACE,
+        bump
+    )]
+    pub new_admin_member: Account<'info, Member>,
+    // Page listing the previous admin, the new admin takes its place
+    #[account(
+        init_if_needed,
+        payer = new_admin,
+        seeds = [b"role_members".as_ref(), role.key().as_ref(), page_index.to_le_bytes().as_ref()],
+        space = RoleMembers::INIT_SPACE,
+        bump
+    )]
+    pub role_members: Account<'info, RoleMembers>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> AcceptAdmin<'info> {
+    pub fn accept_admin(&mut self, page_index: u64, bumps: &AcceptAdminBumps) -> Result<()> {
+        let previous_admin = self.access_controller.admin;
+        self.access_controller.admin = self.new_admin.key();
+        self.access_controller.pending_admin = None;
+
+        // The previous admin keeps its other roles
+        if self.previous_admin_member.has_role(Roles::Admin) {
+            self.previous_admin_member.remove_role(Roles::Admin);
+            self.role.member_count = math::sub(self.role.member_count, 1)?;
+            require!(
+                self.role_members.remove(&previous_admin),
+                AccessControlError::MemberNotListed
+            );
+        }
+
+        // The new admin may already have been granted the admin role
+        if !self.new_admin_member.has_role(Roles::Admin) {
+            self.new_admin_member.member = self.new_admin.key();
+            self.new_admin_member.add_role(Roles::Admin, None);
+            self.new_admin_member.bump = bumps.new_admin_member;
+            self.role.member_count = math::add(self.role.member_count, 1)?;
+            self.role_members.add(
+                &mut self.role,
+                page_index,
+                bumps.role_members,
+                self.new_admin.key(),
+            )?;
+        }
+        // The admin role never expires, the other roles of the new admin keep their expiry
+        self.new_admin_member.expires_at[Roles::Admin.index()] = None;
+
+        if self.previous_admin_member.roles == 0 {
+            self.previous_admin_member
+                .close(self.previous_admin.to_account_info())?;
+        }
+
+        emit!(AdminTransferred {
+            access_controller: self.access_controller.key(),
+            previous_admin,
+            admin: self.new_admin.key(),
+        });
+
+        Ok(())
+    }
+}
@@ -1,39 +1,37 @@
-use crate::error::AccessControlError;
-use crate::state::access_controller_state::{AccessController, Member, Role};
+use crate::state::access_controller_state::{AccessController, Member};
+use crate::Roles;
 use anchor_lang::prelude::*;
 
 #[derive(Accounts)]
-#[instruction(_role_checked: String, _member_pk: Pubkey)]
+#[instruction(role_checked: Roles, member_pk: Pubkey)]
 pub struct CheckRole<'info> {
-    #[account(mut)]
-    pub caller: Signer<'info>,
     #[account(
-        seeds = [b"access_controller".as_ref(), access_controller.admin.as_ref()],
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
         bump = access_controller.bump,
     )]
     pub access_controller: Account<'info, AccessController>,
+    /// CHECK: The member PDA of member_pk, which may not exist or may be left out
     #[account(
-        seeds = [b"role".as_ref(), access_controller.key().as_ref(), _role_checked.as_str().as_bytes()],
-        bump = role.bump,
+        seeds = [b"member".as_ref(), access_controller.key().as_ref(), member_pk.as_ref()],
+        bump,
     )]
-    pub role: Account<'info, Role>,
-    #[account(
-        seeds = [b"member".as_ref(), role.key().as_ref(), _member_pk.as_ref()],
-        bump = member.bump,
-    )]
-    pub member: Account<'info, Member>,
-    pub system_program: Program<'info, System>,
+    pub member: Option<UncheckedAccount<'info>>,
 }
 
 impl<'info> CheckRole<'info> {
-    // Will check the role according to the signer's pubkey
-    pub fn check_role(&mut self, _role_checked: String, _member_pk: Pubkey) -> Result<()> {
-        require!(
-            !self.member.to_account_info().data_is_empty(),
-            AccessControlError::NoRole
-        );
+    // Answers whether member_pk holds an unexpired role, returned to the caller through the
+    // return data so that other programs can gate on it over CPI
+    pub fn check_role(&self, role_checked: Roles, member_pk: Pubkey) -> Result<bool> {
+        let Some(member) = &self.member else {
+            return Ok(false);
+        };
+        if member.owner != &crate::ID || member.data_is_empty() {
+            return Ok(false);
+        }
+        let member = Member::try_deserialize(&mut &member.try_borrow_data()?[..])?;
+        let has_role = member.has_role(role_checked) && !member.is_expired(role_checked)?;
 
-        msg!("{:?} has a role assigned: {:?}", _member_pk, self.role.role);
-        Ok(())
+        msg!("{:?} has role {:?}: {}", member_pk, role_checked, has_role);
+        Ok(has_role)
     }
 }
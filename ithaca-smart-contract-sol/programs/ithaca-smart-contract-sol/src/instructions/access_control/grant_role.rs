@@ -1,17 +1,19 @@
 use crate::error::AccessControlError;
-use crate::state::access_controller_state::{AccessController, Member, Role};
+use crate::events::RoleGranted;
+use crate::math;
+use crate::state::access_controller_state::{AccessController, Member, Role, RoleMembers};
 use crate::Roles;
 use anchor_lang::prelude::*;
 
 #[derive(Accounts)]
-#[instruction(role_granted: String, new_member: Pubkey)]
+#[instruction(role_granted: Roles, new_member: Pubkey, page_index: u64)]
 pub struct GrantRole<'info> {
     // Roles can be granted my the main access controller admin only
     #[account(mut,
         constraint = admin.key() == access_controller.admin @ AccessControlError::UnauthorizedAdmin)]
     pub admin: Signer<'info>,
     #[account(
-        seeds = [b"access_controller".as_ref(), access_controller.admin.as_ref()],
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
         bump = access_controller.bump,
     )]
     pub access_controller: Account<'info, AccessController>,
@@ -23,40 +25,63 @@ pub struct GrantRole<'info> {
         bump
     )]
     pub role: Account<'info, Role>,
+    // A single member account holds every role granted to the member
     #[account(
-        init,
+        init_if_needed,
         payer = admin,
-        seeds = [b"member".as_ref(), role.key().as_ref(), new_member.as_ref()],
+        seeds = [b"member".as_ref(), access_controller.key().as_ref(), new_member.as_ref()],
         space = Member::INIT_SPACE,
         bump 
     )]
     pub member: Account<'info, Member>,
+    // Any page with room, or the role's next page
+    #[account(
+        init_if_needed,
+        payer = admin,
+        seeds = [b"role_members".as_ref(), role.key().as_ref(), page_index.to_le_bytes().as_ref()],
+        space = RoleMembers::INIT_SPACE,
+        bump
+    )]
+    pub role_members: Account<'info, RoleMembers>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> GrantRole<'info> {
     //will grant the role to the new member according to the role's name
-    pub fn grant_role(&mut self, role_granted: String, new_member: Pubkey, bumps: &GrantRoleBumps) -> Result<()> {
+    // `expires_at` bounds the granted role only, the other roles of the member keep theirs
+    pub fn grant_role(&mut self, role_granted: Roles, new_member: Pubkey, page_index: u64, expires_at: Option<i64>, bumps: &GrantRoleBumps) -> Result<()> {
         require!(
             self.access_controller.admin == *self.admin.key,
             AccessControlError::UnauthorizedAdmin
         );
         require!(
-            Roles::is_valid_role(&role_granted),
-            AccessControlError::InvalidRole
+            !self.member.has_role(role_granted),
+            AccessControlError::RoleAlreadyGranted
         );
-        self.role.set_inner(Role {
-            role: role_granted.clone(),
-            member_count: self.role.member_count + 1,
-            bump: bumps.role,
-        });
-        self.member.set_inner(Member {
+        if let Some(expires_at) = expires_at {
+            require!(
+                expires_at > Clock::get()?.unix_timestamp,
+                AccessControlError::InvalidExpiry
+            );
+        }
+        self.role.role = role_granted;
+        self.role.member_count = math::add(self.role.member_count, 1)?;
+        self.role.bump = bumps.role;
+        self.role_members
+            .add(&mut self.role, page_index, bumps.role_members, new_member)?;
+        self.member.member = new_member;
+        self.member.add_role(role_granted, expires_at);
+        self.member.bump = bumps.member;
+
+        emit!(RoleGranted {
+            access_controller: self.access_controller.key(),
+            role: role_granted,
             member: new_member,
-            bump: bumps.member,
+            member_count: self.role.member_count,
+            page_index,
+            expires_at,
         });
 
-        msg!("User {} granted role {} successfully", new_member.to_string(), role_granted);
-
         Ok(())
 
     }
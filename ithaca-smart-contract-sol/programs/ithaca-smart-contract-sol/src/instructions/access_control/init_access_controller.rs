@@ -1,5 +1,6 @@
 use crate::constants::*;
-use crate::state::access_controller_state::{AccessController, Member, Role};
+use crate::events::AccessControllerInitialized;
+use crate::state::access_controller_state::{AccessController, Member, Role, RoleMembers};
 use anchor_lang::prelude::*;
 
 #[derive(Accounts)]
@@ -22,18 +23,27 @@ pub struct InitAccessController<'info> {
         payer = admin,
         seeds = [b"role".as_ref(), access_controller.key().as_ref(), Roles::Admin.as_str().as_bytes()],
         bump,
-        space = AccessController::INIT_SPACE,
+        space = Role::INIT_SPACE,
     )]
     pub role: Account<'info, Role>,
-    // using "member" and signer's role's pk as the seeds, generate a new PDA
+    // using "member", access_controller's pk and signer's pk as the seeds, generate a new PDA
     #[account(
         init,
         payer = admin,
-        seeds = [b"member".as_ref(), role.key().as_ref(), admin.key().as_ref()],
+        seeds = [b"member".as_ref(), access_controller.key().as_ref(), admin.key().as_ref()],
         bump,
-        space = AccessController::INIT_SPACE,
+        space = Member::INIT_SPACE,
     )]
     pub member: Account<'info, Member>,
+    // First page of the admin role members, listing the signer
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"role_members".as_ref(), role.key().as_ref(), 0u64.to_le_bytes().as_ref()],
+        bump,
+        space = RoleMembers::INIT_SPACE,
+    )]
+    pub role_members: Account<'info, RoleMembers>,
     pub system_program: Program<'info, System>,
 }
 
@@ -42,20 +52,37 @@ impl<'info> InitAccessController<'info> {
     // controller, and the signer's pubkey as the first member of the role ADMIN_ROLE
     pub fn initialize(&mut self, bumps: &InitAccessControllerBumps) -> Result<()> {
         self.access_controller.set_inner(AccessController {
+            creator: self.admin.key(),
             admin: self.admin.key(), // Set admin public key
+            pending_admin: None,
+            paused: false,
+            paused_subsystems: 0,
+            releases_allowed: false,
             bump: bumps.access_controller,
         });
         self.role.set_inner(Role {
-            role: Roles::Admin.as_str().to_string(),
+            role: Roles::Admin,
             member_count: 1,
+            page_count: 1,
             bump: bumps.role,
         });
         self.member.set_inner(Member {
             member: self.admin.key(),
+            roles: Roles::Admin.bit(),
+            expires_at: [None; ROLE_COUNT],
             bump: bumps.member,
         });
+        self.role_members.set_inner(RoleMembers {
+            role: self.role.key(),
+            page_index: 0,
+            members: vec![self.admin.key()],
+            bump: bumps.role_members,
+        });
 
-        msg!("Access controller initialized successfully");
+        emit!(AccessControllerInitialized {
+            access_controller: self.access_controller.key(),
+            admin: self.admin.key(),
+        });
         Ok(())
     }
 }
@@ -0,0 +1,64 @@
+use crate::error::AccessControlError;
+use crate::events::AccessControllerMigrated;
+use crate::migration;
+use crate::state::access_controller_state::{AccessController, LegacyAccessController};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct MigrateAccessController<'info> {
+    // Admin stored in the legacy account, which also seeded it
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    /// CHECK: Access controller still in its legacy layout, checked by the handler against the admin
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"access_controller".as_ref(), admin.key().as_ref()],
+        bump
+    )]
+    pub access_controller: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateAccessController<'info> {
+    // Rewrites the access controller with the current layout, paid by the admin. Must run
+    // before the roles and members are migrated, the legacy admin becomes the creator so that
+    // every derived PDA stays valid.
+    pub fn migrate_access_controller(&mut self) -> Result<()> {
+        let legacy_access_controller: LegacyAccessController =
+            migration::load_legacy::<AccessController, _>(
+                &self.access_controller,
+                LegacyAccessController::SPACE,
+            )?
+            .ok_or(AccessControlError::AlreadyMigrated)?;
+        require_keys_eq!(
+            legacy_access_controller.admin,
+            self.admin.key(),
+            AccessControlError::UnauthorizedAdmin
+        );
+
+        let access_controller = AccessController {
+            creator: legacy_access_controller.admin,
+            admin: legacy_access_controller.admin,
+            pending_admin: None,
+            paused: false,
+            paused_subsystems: 0,
+            releases_allowed: false,
+            bump: legacy_access_controller.bump,
+        };
+        migration::store_migrated(
+            &self.access_controller,
+            &access_controller,
+            AccessController::INIT_SPACE,
+            &self.admin,
+            &self.system_program,
+        )?;
+
+        emit!(AccessControllerMigrated {
+            access_controller: self.access_controller.key(),
+            admin: self.admin.key(),
+        });
+
+        Ok(())
+    }
+}
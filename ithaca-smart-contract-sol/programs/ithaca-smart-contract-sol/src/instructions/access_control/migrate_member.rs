@@ -0,0 +1,102 @@
+use crate::error::AccessControlError;
+use crate::events::MemberMigrated;
+use crate::math;
+use crate::state::access_controller_state::{
+    AccessController, LegacyMember, Member, Role, RoleMembers,
+};
+use crate::Roles;
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+#[derive(Accounts)]
+#[instruction(role_migrated: Roles, member_pk: Pubkey, page_index: u64)]
+pub struct MigrateMember<'info> {
+    // Legacy accounts can be migrated by the main access controller admin only
+    #[account(mut,
+        constraint = admin.key() == access_controller.admin @ AccessControlError::UnauthorizedAdmin)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+    )]
+    pub access_controller: Account<'info, AccessController>,
+    // Has to be migrated with migrate_role first
+    #[account(
+        mut,
+        seeds = [b"role".as_ref(), access_controller.key().as_ref(), role_migrated.as_str().as_bytes()],
+        bump = role.bump,
+    )]
+    pub role: Account<'info, Role>,
+    /// CHECK: Member account seeded by the role account, its layout is checked by the handler
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"member".as_ref(), role.key().as_ref(), member_pk.as_ref()],
+        bump
+    )]
+    pub legacy_member: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        seeds = [b"member".as_ref(), access_controller.key().as_ref(), member_pk.as_ref()],
+        space = Member::INIT_SPACE,
+        bump
+    )]
+    pub member: Account<'info, Member>,
+    // The migrated member is listed like a newly granted one
+    #[account(
+        init_if_needed,
+        payer = admin,
+        seeds = [b"role_members".as_ref(), role.key().as_ref(), page_index.to_le_bytes().as_ref()],
+        space = RoleMembers::INIT_SPACE,
+        bump
+    )]
+    pub role_members: Account<'info, RoleMembers>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateMember<'info> {
+    // Moves the role held by a member account seeded by its role account into the member's
+    // role set, lists it in the role members and closes the legacy account, the role member
+    // count is unchanged
+    pub fn migrate_member(
+        &mut self,
+        role_migrated: Roles,
+        member_pk: Pubkey,
+        page_index: u64,
+        bumps: &MigrateMemberBumps,
+    ) -> Result<()> {
+        {
+            let data = self.legacy_member.try_borrow_data()?;
+            require!(
+                data.get(..8) == Some(&Member::DISCRIMINATOR[..]),
+                AccessControlError::AlreadyMigrated
+            );
+            LegacyMember::deserialize(&mut &data[8..])
+                .ok()
+                .filter(|legacy_member| legacy_member.member == member_pk)
+                .ok_or(AccessControlError::AlreadyMigrated)?;
+        }
+
+        self.member.member = member_pk;
+        self.member.add_role(role_migrated, None);
+        self.member.bump = bumps.member;
+        self.role_members
+            .add(&mut self.role, page_index, bumps.role_members, member_pk)?;
+
+        let legacy_member_info = self.legacy_member.to_account_info();
+        **self.admin.try_borrow_mut_lamports()? =
+            math::add(self.admin.lamports(), legacy_member_info.lamports())?;
+        **legacy_member_info.try_borrow_mut_lamports()? = 0;
+        legacy_member_info.assign(&System::id());
+        legacy_member_info.realloc(0, false)?;
+
+        emit!(MemberMigrated {
+            access_controller: self.access_controller.key(),
+            role: role_migrated,
+            member: member_pk,
+        });
+
+        Ok(())
+    }
+}
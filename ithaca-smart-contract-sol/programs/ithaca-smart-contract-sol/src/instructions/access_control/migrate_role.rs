@@ -0,0 +1,74 @@
+use crate::error::AccessControlError;
+use crate::events::RoleMigrated;
+use crate::math;
+use crate::state::access_controller_state::{AccessController, LegacyRole, Role};
+use crate::Roles;
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+#[derive(Accounts)]
+#[instruction(role_migrated: Roles)]
+pub struct MigrateRole<'info> {
+    // Legacy accounts can be migrated by the main access controller admin only
+    #[account(mut,
+        constraint = admin.key() == access_controller.admin @ AccessControlError::UnauthorizedAdmin)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+    )]
+    pub access_controller: Account<'info, AccessController>,
+    /// CHECK: Role account still storing the role name, its layout is checked by the handler
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"role".as_ref(), access_controller.key().as_ref(), role_migrated.as_str().as_bytes()],
+        bump
+    )]
+    pub role: UncheckedAccount<'info>,
+}
+
+impl<'info> MigrateRole<'info> {
+    // Rewrites the role account in place with the typed layout, the role PDA is still seeded
+    // by the role name so its address and member count are kept, the members are listed again
+    // as they are migrated
+    pub fn migrate_role(&mut self, role_migrated: Roles) -> Result<()> {
+        let legacy_role = {
+            let data = self.role.try_borrow_data()?;
+            require!(
+                data.get(..8) == Some(&Role::DISCRIMINATOR[..]),
+                AccessControlError::AlreadyMigrated
+            );
+            LegacyRole::deserialize(&mut &data[8..])
+                .ok()
+                .filter(|legacy_role| legacy_role.role == role_migrated.as_str())
+                .ok_or(AccessControlError::AlreadyMigrated)?
+        };
+
+        let role = Role {
+            role: role_migrated,
+            member_count: legacy_role.member_count,
+            page_count: 0,
+            bump: legacy_role.bump,
+        };
+        let role_info = self.role.to_account_info();
+        role_info.realloc(Role::INIT_SPACE, false)?;
+        role.try_serialize(&mut &mut role_info.try_borrow_mut_data()?[..])?;
+
+        // The typed layout is smaller, the freed rent goes back to the admin
+        let surplus = math::sub(
+            role_info.lamports(),
+            Rent::get()?.minimum_balance(Role::INIT_SPACE),
+        )?;
+        **role_info.try_borrow_mut_lamports()? = math::sub(role_info.lamports(), surplus)?;
+        **self.admin.try_borrow_mut_lamports()? = math::add(self.admin.lamports(), surplus)?;
+
+        emit!(RoleMigrated {
+            access_controller: self.access_controller.key(),
+            role: role_migrated,
+            member_count: role.member_count,
+        });
+
+        Ok(())
+    }
+}
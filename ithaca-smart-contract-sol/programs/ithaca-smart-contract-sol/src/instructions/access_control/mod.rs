@@ -10,4 +10,20 @@ pub use grant_role::*;
 pub mod renounce_role;
 pub use renounce_role::*;
 
+pub mod propose_admin;
+pub use propose_admin::*;
 
+pub mod accept_admin;
+pub use accept_admin::*;
+
+pub mod migrate_access_controller;
+pub use migrate_access_controller::*;
+
+pub mod migrate_role;
+pub use migrate_role::*;
+
+pub mod migrate_member;
+pub use migrate_member::*;
+
+pub mod set_pause;
+pub use set_pause::*;
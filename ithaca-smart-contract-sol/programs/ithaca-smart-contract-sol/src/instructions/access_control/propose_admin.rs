@@ -0,0 +1,38 @@
+use crate::error::AccessControlError;
+use crate::events::AdminProposed;
+use crate::state::access_controller_state::AccessController;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct ProposeAdmin<'info> {
+    // The admin transfer can be started by the current admin only
+    #[account(
+        constraint = admin.key() == access_controller.admin @ AccessControlError::UnauthorizedAdmin
+    )]
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+    )]
+    pub access_controller: Account<'info, AccessController>,
+}
+
+impl<'info> ProposeAdmin<'info> {
+    // Replaces any previous proposal, the current admin stays in charge until the new one accepts
+    pub fn propose_admin(&mut self, new_admin: Pubkey) -> Result<()> {
+        require!(
+            new_admin != self.access_controller.admin,
+            AccessControlError::InvalidPendingAdmin
+        );
+        self.access_controller.pending_admin = Some(new_admin);
+
+        emit!(AdminProposed {
+            access_controller: self.access_controller.key(),
+            admin: self.admin.key(),
+            pending_admin: new_admin,
+        });
+
+        Ok(())
+    }
+}
@@ -1,42 +1,81 @@
 use crate::error::AccessControlError;
-use crate::state::access_controller_state::{AccessController, Member, Role};
+use crate::events::RoleRenounced;
+use crate::math;
+use crate::state::access_controller_state::{AccessController, Member, Role, RoleMembers};
+use crate::Roles;
 use anchor_lang::prelude::*;
 
 #[derive(Accounts)]
-#[instruction(_role_renounced: String, _member_pk: Pubkey)]
+#[instruction(role_renounced: Roles, member_pk: Pubkey, page_index: u64)]
 pub struct RenounceRole<'info> {
-    // Roles can be renounced my the main access controller admin only
-    #[account(mut,
-    constraint = admin.key() == access_controller.admin @ AccessControlError::UnauthorizedAdmin)]
-    pub admin: Signer<'info>,
+    // Roles can be renounced by the main access controller admin or by the member itself, the
+    // admin role of the current admin moves with accept_admin, which needs its member account
     #[account(
-        seeds = [b"access_controller".as_ref(), access_controller.admin.as_ref()],
+        constraint = authority.key() == access_controller.admin || authority.key() == member_pk @ AccessControlError::UnauthorizedRenounce,
+        constraint = role_renounced != Roles::Admin || member_pk != access_controller.admin @ AccessControlError::AdminCannotRenounce
+    )]
+    pub authority: Signer<'info>,
+    /// CHECK: Paid for the member account, gets its rent back once it's closed
+    #[account(mut, address = access_controller.admin @ AccessControlError::UnauthorizedAdmin)]
+    pub admin: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
         bump = access_controller.bump,
     )]
     pub access_controller: Account<'info, AccessController>,
     #[account(
         mut,
-        seeds = [b"role".as_ref(), access_controller.key().as_ref(), _role_renounced.as_str().as_bytes()],
+        seeds = [b"role".as_ref(), access_controller.key().as_ref(), role_renounced.as_str().as_bytes()],
         bump = role.bump,
     )]
     pub role: Account<'info, Role>,
+    // Closed once the member has no role left
     #[account(
         mut,
-        close = admin,
-        seeds = [b"member".as_ref(), role.key().as_ref(), _member_pk.as_ref()],
+        seeds = [b"member".as_ref(), access_controller.key().as_ref(), member_pk.as_ref()],
         bump = member.bump,
+        constraint = member.has_role(role_renounced) @ AccessControlError::NoRole
     )]
     pub member: Account<'info, Member>,
+    // Page listing the member
+    #[account(
+        mut,
+        seeds = [b"role_members".as_ref(), role.key().as_ref(), page_index.to_le_bytes().as_ref()],
+        bump = role_members.bump,
+    )]
+    pub role_members: Account<'info, RoleMembers>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> RenounceRole<'info> {
-    // Will renounce the role according to the signer's pubkey
-    pub fn renounce_role(&mut self, _role_renounced: String, _member_pk: Pubkey) -> Result<()> {
+    // Will renounce the role of the member, expired members can still renounce their roles
+    pub fn renounce_role(
+        &mut self,
+        role_renounced: Roles,
+        member_pk: Pubkey,
+        page_index: u64,
+    ) -> Result<()> {
         require!(self.role.member_count != 1, AccessControlError::LastMember);
 
-        self.role.member_count -= 1;
-        
+        self.role.member_count = math::sub(self.role.member_count, 1)?;
+        require!(
+            self.role_members.remove(&member_pk),
+            AccessControlError::MemberNotListed
+        );
+        self.member.remove_role(role_renounced);
+        if self.member.roles == 0 {
+            self.member.close(self.admin.to_account_info())?;
+        }
+
+        emit!(RoleRenounced {
+            access_controller: self.access_controller.key(),
+            role: role_renounced,
+            member: member_pk,
+            member_count: self.role.member_count,
+            renounced_by: self.authority.key(),
+            page_index,
+        });
+
         Ok(())
     }
 }
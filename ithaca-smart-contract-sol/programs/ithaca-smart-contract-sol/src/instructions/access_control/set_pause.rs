@@ -0,0 +1,56 @@
+use crate::error::AccessControlError;
+use crate::events::PauseStateChanged;
+use crate::state::access_controller_state::{AccessController, Member, Role};
+use crate::{Roles, Subsystem};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetPause<'info> {
+    pub guardian: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+    )]
+    pub access_controller: Account<'info, AccessController>,
+    // The circuit breaker is held by the guardian role, so pausing doesn't need the admin keys
+    #[account(
+        seeds = [b"role".as_ref(), access_controller.key().as_ref(), Roles::Guardian.as_str().as_bytes()],
+        bump = role.bump
+    )]
+    pub role: Account<'info, Role>,
+    #[account(
+        seeds = [b"member".as_ref(), access_controller.key().as_ref(), guardian.key().as_ref()],
+        bump = member.bump,
+        constraint = member.has_role(Roles::Guardian) @ AccessControlError::NoRole,
+        constraint = !member.is_expired(Roles::Guardian)? @ AccessControlError::RoleExpired
+    )]
+    pub member: Account<'info, Member>,
+}
+
+impl<'info> SetPause<'info> {
+    // Replaces the whole pause state, unpausing is done by passing false and no subsystems
+    pub fn set_pause(
+        &mut self,
+        paused: bool,
+        paused_subsystems: Vec<Subsystem>,
+        releases_allowed: bool,
+    ) -> Result<()> {
+        let access_controller = &mut self.access_controller;
+        access_controller.paused = paused;
+        access_controller.paused_subsystems = paused_subsystems
+            .iter()
+            .fold(0, |mask, subsystem| mask | subsystem.bit());
+        access_controller.releases_allowed = releases_allowed;
+
+        emit!(PauseStateChanged {
+            access_controller: access_controller.key(),
+            guardian: self.guardian.key(),
+            paused,
+            paused_subsystems: access_controller.paused_subsystems,
+            releases_allowed,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use crate::error::{AccessControlError, FundlockError, TokenValidatorError};
+use crate::events::WithdrawalCancelled;
+use crate::math;
+use crate::state::access_controller_state::AccessController;
+use crate::state::fundlock_state::Fundlock;
+use crate::{ClientBalance, Subsystem, TokenValidator, Withdrawals};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+#[derive(Accounts)]
+// Works for token and native SOL balances alike, the balance is found through its stored key
+pub struct CancelWithdrawal<'info> {
+    pub client: Signer<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Withdrawals) @ AccessControlError::Paused,
+    )]
+    pub access_controller: Box<Account<'info, AccessController>>,
+    #[account(
+        seeds = [b"token_validator".as_ref(), access_controller.key().as_ref()],
+        bump = token_validator.bump
+    )]
+    pub token_validator: Box<Account<'info, TokenValidator>>,
+    #[account(
+        seeds = [b"fundlock".as_ref(), access_controller.key().as_ref(), token_validator.key().as_ref()],
+        bump = fundlock.bump
+    )]
+    pub fundlock: Box<Account<'info, Fundlock>>,
+    #[account(
+        constraint = token.decimals > 0 @ TokenValidatorError::NonFungibleToken
+    )]
+    pub token: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        seeds = [b"fundlock_token_vault".as_ref(), fundlock.key().as_ref(), token.key().as_ref()],
+        token::mint = token,
+        token::authority = fundlock,
+        bump,
+    )]
+    pub fundlock_token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        has_one = client,
+        seeds = [b"client_balance".as_ref(), fundlock_token_vault.key().as_ref(), client_balance.client_ata.as_ref()],
+        bump = client_balance.bump
+    )]
+    pub client_balance: Box<Account<'info, ClientBalance>>,
+    #[account(
+        mut,
+        seeds = [b"withdrawals".as_ref(), fundlock.key().as_ref(), client_balance.key().as_ref()],
+        bump = withdrawals.bump
+    )]
+    pub withdrawals: Box<Account<'info, Withdrawals>>,
+}
+
+impl<'info> CancelWithdrawal<'info> {
+    // Moves amount of a queued withdrawal back into the balance, the entry is dropped once empty
+    pub fn cancel_withdrawal(&mut self, index: u64, amount: u64) -> Result<()> {
+        require!(amount > 0, FundlockError::AmountZero);
+        require!(
+            self.withdrawals.withdrawal_queue.len() > index as usize,
+            FundlockError::InvalidIndex
+        );
+
+        let withdrawal = &mut self.withdrawals.withdrawal_queue[index as usize];
+        require!(withdrawal.amount >= amount, FundlockError::InsufficientFunds);
+        withdrawal.amount = math::sub(withdrawal.amount, amount)?;
+        if withdrawal.amount == 0 {
+            self.withdrawals.withdrawal_queue.remove(index as usize);
+        }
+
+        self.withdrawals.active_withdrawals_amount =
+            math::sub(self.withdrawals.active_withdrawals_amount, amount)?;
+        self.client_balance.amount = math::add(self.client_balance.amount, amount)?;
+
+        emit!(WithdrawalCancelled {
+            fundlock: self.fundlock.key(),
+            client: self.client.key(),
+            token: self.token.key(),
+            amount,
+            index,
+            balance: self.client_balance.amount,
+            active_withdrawals_amount: self.withdrawals.active_withdrawals_amount,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+use crate::error::{AccessControlError, FundlockError, TokenValidatorError};
+use crate::events::{KeeperTipped, MaturedWithdrawalsReleased};
+use crate::math;
+use crate::state::access_controller_state::AccessController;
+use crate::state::fundlock_state::{Fundlock, KeeperPool};
+use crate::token_transfer::transfer_checked;
+use crate::{ClientBalance, Subsystem, TokenValidator, WhitelistedToken, Withdrawals};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked};
+
+#[derive(Accounts)]
+// Anyone can crank, the tokens can only go to the destination recorded in the client balance.
+// Native SOL balances are cranked with crank_release_sol.
+pub struct CrankRelease<'info> {
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Releases) @ AccessControlError::Paused,
+    )]
+    pub access_controller: Box<Account<'info, AccessController>>,
+    #[account(
+        seeds = [b"token_validator".as_ref(), access_controller.key().as_ref()],
+        bump = token_validator.bump
+    )]
+    pub token_validator: Box<Account<'info, TokenValidator>>,
+    #[account(
+        seeds = [b"fundlock".as_ref(), access_controller.key().as_ref(), token_validator.key().as_ref()],
+        bump = fundlock.bump
+    )]
+    pub fundlock: Box<Account<'info, Fundlock>>,
+    #[account(
+        constraint = token.decimals > 0 @ TokenValidatorError::NonFungibleToken
+    )]
+    pub token: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"whitelisted_token".as_ref(), token_validator.key().as_ref(), token.key().as_ref()],
+        bump = whitelisted_token.bump
+    )]
+    pub whitelisted_token: Box<Account<'info, WhitelistedToken>>,
+    #[account(
+        mut,
+        seeds = [b"fundlock_token_vault".as_ref(), fundlock.key().as_ref(), token.key().as_ref()],
+        token::mint = token,
+        token::authority = fundlock,
+        bump,
+    )]
+    pub fundlock_token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"client_balance".as_ref(), fundlock_token_vault.key().as_ref(), client_balance.client_ata.as_ref()],
+        bump = client_balance.bump
+    )]
+    pub client_balance: Box<Account<'info, ClientBalance>>,
+    #[account(
+        mut,
+        address = client_balance.destination,
+        constraint = destination.mint == token.key()
+    )]
+    pub destination: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"withdrawals".as_ref(), fundlock.key().as_ref(), client_balance.key().as_ref()],
+        bump = withdrawals.bump
+    )]
+    pub withdrawals: Box<Account<'info, Withdrawals>>,
+    // left out when the keeper doesn't claim a tip
+    #[account(
+        mut,
+        seeds = [b"keeper_pool".as_ref(), fundlock.key().as_ref()],
+        bump = keeper_pool.bump
+    )]
+    pub keeper_pool: Option<Box<Account<'info, KeeperPool>>>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> CrankRelease<'info> {
+    pub fn crank_release(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let (amount_released, released_count) = self
+            .withdrawals
+            .take_matured(self.fundlock.release_lock, Clock::get()?.unix_timestamp)?;
+        require!(released_count > 0, FundlockError::NoMaturedWithdrawals);
+        require!(
+            self.fundlock_token_vault.amount >= amount_released,
+            FundlockError::InsufficientFundsInVault
+        );
+
+        let cpi_accounts = TransferChecked {
+            from: self.fundlock_token_vault.to_account_info(),
+            mint: self.token.to_account_info(),
+            to: self.destination.to_account_info(),
+            authority: self.fundlock.to_account_info(),
+        };
+
+        let access_validator_key = self.access_controller.key();
+        let token_validator_key = self.token_validator.key();
+
+        let fundlock_seeds: &[&[&[u8]]] = &[&[
+            b"fundlock".as_ref(),
+            access_validator_key.as_ref(),
+            token_validator_key.as_ref(),
+            &[self.fundlock.bump],
+        ]];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            fundlock_seeds,
+        )
+        .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked(cpi_ctx, amount_released, self.token.decimals)?;
+
+        // Ledger movements can leave more with a client than it deposited, so this only floors
+        self.whitelisted_token.total_deposits = self
+            .whitelisted_token
+            .total_deposits
+            .saturating_sub(amount_released);
+
+        emit!(MaturedWithdrawalsReleased {
+            fundlock: self.fundlock.key(),
+            client: self.client_balance.client,
+            token: self.token.key(),
+            amount: amount_released,
+            released_count,
+            active_withdrawals_amount: self.withdrawals.active_withdrawals_amount,
+        });
+
+        pay_keeper_tip(
+            self.keeper_pool.as_deref(),
+            &self.keeper,
+            self.client_balance.client,
+            amount_released,
+        )
+    }
+}
+
+// The tip is skipped rather than failing the release once the pool runs dry, and clients
+// cranking their own withdrawals or releases below the pool's floor don't get it
+pub fn pay_keeper_tip<'info>(
+    keeper_pool: Option<&Account<'info, KeeperPool>>,
+    keeper: &Signer<'info>,
+    client: Pubkey,
+    amount_released: u64,
+) -> Result<()> {
+    let Some(keeper_pool) = keeper_pool else {
+        return Ok(());
+    };
+    if keeper.key() == client || amount_released < keeper_pool.min_release {
+        return Ok(());
+    }
+    let pool = keeper_pool.to_account_info();
+    let tip = keeper_pool.tip;
+    let rent_exempt = Rent::get()?.minimum_balance(pool.data_len());
+    if tip == 0 || pool.lamports() < math::add(rent_exempt, tip)? {
+        return Ok(());
+    }
+
+    let keeper = keeper.to_account_info();
+    **pool.try_borrow_mut_lamports()? = math::sub(pool.lamports(), tip)?;
+    **keeper.try_borrow_mut_lamports()? = math::add(keeper.lamports(), tip)?;
+
+    emit!(KeeperTipped {
+        keeper_pool: pool.key(),
+        keeper: keeper.key(),
+        tip,
+    });
+    Ok(())
+}
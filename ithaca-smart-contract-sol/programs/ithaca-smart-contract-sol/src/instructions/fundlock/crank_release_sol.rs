@@ -0,0 +1,164 @@
+use crate::error::{AccessControlError, FundlockError};
+use crate::events::MaturedWithdrawalsReleased;
+use crate::instructions::fundlock::crank_release::pay_keeper_tip;
+use crate::state::access_controller_state::AccessController;
+use crate::state::fundlock_state::{Fundlock, KeeperPool};
+use crate::{ClientBalance, Subsystem, TokenValidator, WhitelistedToken, Withdrawals};
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer};
+use anchor_spl::token::spl_token::native_mint;
+use anchor_spl::token::Token;
+use anchor_spl::token_interface::{
+    close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TransferChecked,
+};
+
+#[derive(Accounts)]
+// crank_release for native SOL balances, the lamports can only go to the client's system account
+pub struct CrankReleaseSol<'info> {
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Releases) @ AccessControlError::Paused,
+    )]
+    pub access_controller: Box<Account<'info, AccessController>>,
+    #[account(
+        seeds = [b"token_validator".as_ref(), access_controller.key().as_ref()],
+        bump = token_validator.bump
+    )]
+    pub token_validator: Box<Account<'info, TokenValidator>>,
+    #[account(
+        seeds = [b"fundlock".as_ref(), access_controller.key().as_ref(), token_validator.key().as_ref()],
+        bump = fundlock.bump
+    )]
+    pub fundlock: Box<Account<'info, Fundlock>>,
+    #[account(address = native_mint::ID)]
+    pub token: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"whitelisted_token".as_ref(), token_validator.key().as_ref(), token.key().as_ref()],
+        bump = whitelisted_token.bump
+    )]
+    pub whitelisted_token: Box<Account<'info, WhitelistedToken>>,
+    #[account(
+        mut,
+        seeds = [b"fundlock_token_vault".as_ref(), fundlock.key().as_ref(), token.key().as_ref()],
+        token::mint = token,
+        token::authority = fundlock,
+        bump,
+    )]
+    pub fundlock_token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"client_balance".as_ref(), fundlock_token_vault.key().as_ref(), client.key().as_ref()],
+        bump = client_balance.bump,
+        has_one = client,
+    )]
+    pub client_balance: Box<Account<'info, ClientBalance>>,
+    #[account(mut)]
+    pub client: SystemAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"withdrawals".as_ref(), fundlock.key().as_ref(), client_balance.key().as_ref()],
+        bump = withdrawals.bump
+    )]
+    pub withdrawals: Box<Account<'info, Withdrawals>>,
+    // Paid for by the keeper and closed back to it, the released lamports are then passed on
+    // to the client
+    #[account(
+        init,
+        payer = keeper,
+        seeds = [b"sol_release".as_ref(), fundlock_token_vault.key().as_ref(), client.key().as_ref()],
+        token::mint = token,
+        token::authority = fundlock,
+        token::token_program = token_program,
+        bump,
+    )]
+    pub unwrap_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    // left out when the keeper doesn't claim a tip
+    #[account(
+        mut,
+        seeds = [b"keeper_pool".as_ref(), fundlock.key().as_ref()],
+        bump = keeper_pool.bump
+    )]
+    pub keeper_pool: Option<Box<Account<'info, KeeperPool>>>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> CrankReleaseSol<'info> {
+    pub fn crank_release_sol(&mut self) -> Result<()> {
+        let (amount_released, released_count) = self
+            .withdrawals
+            .take_matured(self.fundlock.release_lock, Clock::get()?.unix_timestamp)?;
+        require!(released_count > 0, FundlockError::NoMaturedWithdrawals);
+        require!(
+            self.fundlock_token_vault.amount >= amount_released,
+            FundlockError::InsufficientFundsInVault
+        );
+
+        let access_validator_key = self.access_controller.key();
+        let token_validator_key = self.token_validator.key();
+
+        let fundlock_seeds: &[&[&[u8]]] = &[&[
+            b"fundlock".as_ref(),
+            access_validator_key.as_ref(),
+            token_validator_key.as_ref(),
+            &[self.fundlock.bump],
+        ]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.fundlock_token_vault.to_account_info(),
+            mint: self.token.to_account_info(),
+            to: self.unwrap_account.to_account_info(),
+            authority: self.fundlock.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            fundlock_seeds,
+        );
+        transfer_checked(cpi_ctx, amount_released, self.token.decimals)?;
+
+        let cpi_accounts = CloseAccount {
+            account: self.unwrap_account.to_account_info(),
+            destination: self.keeper.to_account_info(),
+            authority: self.fundlock.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            fundlock_seeds,
+        );
+        close_account(cpi_ctx)?;
+
+        let cpi_accounts = Transfer {
+            from: self.keeper.to_account_info(),
+            to: self.client.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), cpi_accounts);
+        transfer(cpi_ctx, amount_released)?;
+
+        // Ledger movements can leave more with a client than it deposited, so this only floors
+        self.whitelisted_token.total_deposits = self
+            .whitelisted_token
+            .total_deposits
+            .saturating_sub(amount_released);
+
+        emit!(MaturedWithdrawalsReleased {
+            fundlock: self.fundlock.key(),
+            client: self.client.key(),
+            token: self.token.key(),
+            amount: amount_released,
+            released_count,
+            active_withdrawals_amount: self.withdrawals.active_withdrawals_amount,
+        });
+
+        pay_keeper_tip(
+            self.keeper_pool.as_deref(),
+            &self.keeper,
+            self.client.key(),
+            amount_released,
+        )
+    }
+}
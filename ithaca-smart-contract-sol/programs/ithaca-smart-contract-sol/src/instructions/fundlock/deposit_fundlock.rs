@@ -1,9 +1,12 @@
-use crate::error::{FundlockError, TokenValidatorError};
+use crate::error::{AccessControlError, FundlockError, TokenValidatorError};
+use crate::events::Deposited;
+use crate::math;
 use crate::state::access_controller_state::AccessController;
 use crate::state::fundlock_state::Fundlock;
-use crate::{ClientBalance, TokenValidator, WhitelistedToken, Withdrawals};
+use crate::token_transfer::transfer_checked;
+use crate::{ClientBalance, Subsystem, TokenValidator, WhitelistedToken, Withdrawals};
 use anchor_lang::prelude::*;
-use anchor_spl::token::{transfer, Mint, Token, TokenAccount, Transfer};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked};
 
 #[derive(Accounts)]
 // Boxing all the account to avoid stack overflow
@@ -11,8 +14,9 @@ pub struct DepositFundlock<'info> {
     #[account(mut)]
     pub client: Signer<'info>,
     #[account(
-        seeds = [b"access_controller".as_ref(), access_controller.admin.as_ref()],
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
         bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Deposits) @ AccessControlError::Paused,
     )]
     pub access_controller: Box<Account<'info, AccessController>>,
     #[account(
@@ -28,8 +32,9 @@ pub struct DepositFundlock<'info> {
     #[account(
         constraint = token.decimals > 0 @ TokenValidatorError::NonFungibleToken
     )]
-    pub token: Box<Account<'info, Mint>>,
+    pub token: Box<InterfaceAccount<'info, Mint>>,
     #[account(
+        mut,
         seeds = [b"whitelisted_token".as_ref(), token_validator.key().as_ref(), token.key().as_ref()],
         bump = whitelisted_token.bump
     )]
@@ -42,13 +47,15 @@ pub struct DepositFundlock<'info> {
         token::authority = fundlock,
         bump,
     )]
-    pub fundlock_token_vault: Box<Account<'info, TokenAccount>>,
+    pub fundlock_token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         init_if_needed,
         payer = client, 
         seeds = [b"client_balance".as_ref(), fundlock_token_vault.key().as_ref(), client_ata.key().as_ref()],
         space = ClientBalance::INIT_SPACE,
-        bump
+        bump,
+        constraint = client_balance.client == Pubkey::default() ||
+        client_balance.client == client.key() @ FundlockError::NotBalanceOwner
     )]
     pub client_balance: Box<Account<'info, ClientBalance>>,
     #[account(
@@ -56,7 +63,7 @@ pub struct DepositFundlock<'info> {
         constraint = client_ata.mint == token.key() &&
         client_ata.owner == client.key()
     )]
-    pub client_ata: Box<Account<'info, TokenAccount>>,
+    pub client_ata: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         init_if_needed,
         payer = client,
@@ -66,43 +73,69 @@ pub struct DepositFundlock<'info> {
     )]
     pub withdrawals: Box<Account<'info, Withdrawals>>,
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 impl<'info> DepositFundlock<'info> {
-    pub fn deposit_fundlock(&mut self, amount: u64, bumps: &DepositFundlockBumps) -> Result<()> {
+    pub fn deposit_fundlock(
+        &mut self,
+        amount: u64,
+        bumps: &DepositFundlockBumps,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
         require!(amount > 0, FundlockError::AmountZero);
         require!(
             self.whitelisted_token.token_mint == self.token.key(),
             TokenValidatorError::TokenNotWhitelisted
         );
 
-        let cpi_accounts = Transfer {
+        let vault_balance_before = self.fundlock_token_vault.amount;
+
+        let cpi_accounts = TransferChecked {
             from: self.client_ata.to_account_info(),
+            mint: self.token.to_account_info(),
             to: self.fundlock_token_vault.to_account_info(),
             authority: self.client.to_account_info(),
         };
-        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked(cpi_ctx, amount, self.token.decimals)?;
 
-        transfer(cpi_ctx, amount)?;
+        // Token-2022 transfer fees are withheld from what reaches the vault, so only the
+        // received amount is credited
+        self.fundlock_token_vault.reload()?;
+        let received = math::sub(self.fundlock_token_vault.amount, vault_balance_before)?;
+        require!(received > 0, FundlockError::AmountZero);
+        self.whitelisted_token
+            .check_deposit(amount, received, self.client_balance.amount)?;
+        self.whitelisted_token.total_deposits =
+            math::add(self.whitelisted_token.total_deposits, received)?;
 
         self.client_balance.set_inner(ClientBalance {
-            amount: self.client_balance.amount + amount,
+            amount: math::add(self.client_balance.amount, received)?,
             token: self.token.key(),
             client: self.client.key(),
             client_ata: self.client_ata.key(),
             collateral_amount: self.client_balance.collateral_amount,
             bump: bumps.client_balance,
+            destination: if self.client_balance.destination == Pubkey::default() {
+                self.client_ata.key()
+            } else {
+                self.client_balance.destination
+            },
         });
 
         self.withdrawals.bump = bumps.withdrawals;
         self.withdrawals.client = self.client.key();
 
-        msg!(
-            "Client: {} deposited {} tokens into the fundlock",
-            self.client.key(),
-            amount
-        );
+        emit!(Deposited {
+            fundlock: self.fundlock.key(),
+            client: self.client.key(),
+            token: self.token.key(),
+            amount: received,
+            balance: self.client_balance.amount,
+        });
 
         Ok(())
     }
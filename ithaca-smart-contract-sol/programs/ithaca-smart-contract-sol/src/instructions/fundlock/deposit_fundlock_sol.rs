@@ -0,0 +1,131 @@
+use crate::error::{AccessControlError, FundlockError, TokenValidatorError};
+use crate::events::Deposited;
+use crate::math;
+use crate::state::access_controller_state::AccessController;
+use crate::state::fundlock_state::Fundlock;
+use crate::{ClientBalance, Subsystem, TokenValidator, WhitelistedToken, Withdrawals};
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer};
+use anchor_spl::token::spl_token::native_mint;
+use anchor_spl::token::Token;
+use anchor_spl::token_interface::{sync_native, Mint, SyncNative, TokenAccount};
+
+#[derive(Accounts)]
+// Native SOL balances are keyed by the client's system account instead of a token account
+pub struct DepositFundlockSol<'info> {
+    #[account(mut)]
+    pub client: Signer<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Deposits) @ AccessControlError::Paused,
+    )]
+    pub access_controller: Box<Account<'info, AccessController>>,
+    #[account(
+        seeds = [b"token_validator".as_ref(), access_controller.key().as_ref()],
+        bump = token_validator.bump
+    )]
+    pub token_validator: Box<Account<'info, TokenValidator>>,
+    #[account(
+        seeds = [b"fundlock".as_ref(), access_controller.key().as_ref(), token_validator.key().as_ref()],
+        bump = fundlock.bump
+    )]
+    pub fundlock: Box<Account<'info, Fundlock>>,
+    #[account(address = native_mint::ID)]
+    pub token: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"whitelisted_token".as_ref(), token_validator.key().as_ref(), token.key().as_ref()],
+        bump = whitelisted_token.bump
+    )]
+    pub whitelisted_token: Box<Account<'info, WhitelistedToken>>,
+    #[account(
+        init_if_needed,
+        payer = client,
+        seeds = [b"fundlock_token_vault".as_ref(), fundlock.key().as_ref(), token.key().as_ref()],
+        token::mint = token,
+        token::authority = fundlock,
+        token::token_program = token_program,
+        bump,
+    )]
+    pub fundlock_token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = client,
+        seeds = [b"client_balance".as_ref(), fundlock_token_vault.key().as_ref(), client.key().as_ref()],
+        space = ClientBalance::INIT_SPACE,
+        bump
+    )]
+    pub client_balance: Box<Account<'info, ClientBalance>>,
+    #[account(
+        init_if_needed,
+        payer = client,
+        seeds = [b"withdrawals".as_ref(), fundlock.key().as_ref(), client_balance.key().as_ref()],
+        space = Withdrawals::INIT_SPACE,
+        bump,
+    )]
+    pub withdrawals: Box<Account<'info, Withdrawals>>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> DepositFundlockSol<'info> {
+    // Wraps the lamports straight into the wSOL vault
+    pub fn deposit_fundlock_sol(
+        &mut self,
+        amount: u64,
+        bumps: &DepositFundlockSolBumps,
+    ) -> Result<()> {
+        require!(amount > 0, FundlockError::AmountZero);
+        require!(
+            self.whitelisted_token.token_mint == self.token.key(),
+            TokenValidatorError::TokenNotWhitelisted
+        );
+        self.whitelisted_token
+            .check_deposit(amount, amount, self.client_balance.amount)?;
+
+        let cpi_accounts = Transfer {
+            from: self.client.to_account_info(),
+            to: self.fundlock_token_vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), cpi_accounts);
+        transfer(cpi_ctx, amount)?;
+
+        let cpi_accounts = SyncNative {
+            account: self.fundlock_token_vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+        sync_native(cpi_ctx)?;
+
+        self.whitelisted_token.total_deposits =
+            math::add(self.whitelisted_token.total_deposits, amount)?;
+
+        self.client_balance.set_inner(ClientBalance {
+            amount: math::add(self.client_balance.amount, amount)?,
+            token: self.token.key(),
+            client: self.client.key(),
+            client_ata: self.client.key(),
+            collateral_amount: self.client_balance.collateral_amount,
+            bump: bumps.client_balance,
+            // A wSOL account registered with set_client_destination is kept
+            destination: if self.client_balance.destination == Pubkey::default() {
+                self.client.key()
+            } else {
+                self.client_balance.destination
+            },
+        });
+
+        self.withdrawals.bump = bumps.withdrawals;
+        self.withdrawals.client = self.client.key();
+
+        emit!(Deposited {
+            fundlock: self.fundlock.key(),
+            client: self.client.key(),
+            token: self.token.key(),
+            amount,
+            balance: self.client_balance.amount,
+        });
+
+        Ok(())
+    }
+}
@@ -1,7 +1,10 @@
-use crate::error::FundlockError;
+use crate::error::AccessControlError;
+use crate::events::FundlockInitialized;
 use crate::state::access_controller_state::Role;
 use crate::state::fundlock_state::Fundlock;
-use crate::{AccessController, Member, Roles, TokenValidator};
+use crate::{
+    AccessController, Member, Roles, Subsystem, TokenValidator, DEFAULT_WITHDRAWAL_LIMIT,
+};
 use anchor_lang::prelude::*;
 
 #[derive(Accounts)]
@@ -9,8 +12,9 @@ pub struct InitFundlock<'info> {
     #[account(mut)]
     pub caller: Signer<'info>,
     #[account(
-        seeds = [b"access_controller".as_ref(), access_controller.admin.as_ref()],
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
         bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Config) @ AccessControlError::Paused,
     )]
     pub access_controller: Account<'info, AccessController>,
     // Fundlock can be initialized only by the admin role member
@@ -20,8 +24,10 @@ pub struct InitFundlock<'info> {
     )]
     pub role: Account<'info, Role>,
     #[account(
-        seeds = [b"member".as_ref(), role.key().as_ref(), caller.key().as_ref()],
-        bump = member.bump
+        seeds = [b"member".as_ref(), access_controller.key().as_ref(), caller.key().as_ref()],
+        bump = member.bump,
+        constraint = member.has_role(Roles::Admin) @ AccessControlError::NoRole,
+        constraint = !member.is_expired(Roles::Admin)? @ AccessControlError::RoleExpired
     )]
     pub member: Account<'info, Member>,
     #[account(
@@ -47,19 +53,22 @@ impl<'info> InitFundlock<'info> {
         release_lock: i64,
         bumps: &InitFundlockBumps,
     ) -> Result<()> {
-        require!(trade_lock != 0, FundlockError::InvalidTradeLock);
-        require!(release_lock < 604800, FundlockError::ReleaseLockTooLong); // 604800 seconds = 1 week
+        Fundlock::validate_trade_lock(trade_lock)?;
+        Fundlock::validate_release_lock(release_lock)?;
         self.fundlock.set_inner(Fundlock {
             access_controller: self.access_controller.key(),
             token_validator: self.token_validator.key(),
             trade_lock,
             release_lock,
             bump: bumps.fundlock,
+            withdrawal_limit: DEFAULT_WITHDRAWAL_LIMIT,
+        });
+        emit!(FundlockInitialized {
+            fundlock: self.fundlock.key(),
+            trade_lock,
+            release_lock,
+            withdrawal_limit: DEFAULT_WITHDRAWAL_LIMIT,
         });
-        msg!(
-            "Fundlock initialized successfully at {}",
-            self.fundlock.key()
-        );
         Ok(())
     }
 }
@@ -0,0 +1,53 @@
+use crate::error::FundlockError;
+use crate::events::ClientBalanceMigrated;
+use crate::migration;
+use crate::state::fundlock_state::{ClientBalance, LegacyClientBalance};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+// Anyone can migrate a client balance, as nothing but the stored fields goes into the new layout
+pub struct MigrateClientBalance<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: Client balance still in its legacy layout, told apart by its discriminator and size
+    #[account(mut, owner = crate::ID)]
+    pub client_balance: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateClientBalance<'info> {
+    // Rewrites the client balance with the current layout, paid by the signer. Cranked releases
+    // go to the token account the balance was deposited from until the client registers another.
+    pub fn migrate_client_balance(&mut self) -> Result<()> {
+        let legacy_balance: LegacyClientBalance = migration::load_legacy::<ClientBalance, _>(
+            &self.client_balance,
+            LegacyClientBalance::SPACE,
+        )?
+        .ok_or(FundlockError::AlreadyMigrated)?;
+
+        let client_balance = ClientBalance {
+            amount: legacy_balance.amount,
+            token: legacy_balance.token,
+            client: legacy_balance.client,
+            client_ata: legacy_balance.client_ata,
+            collateral_amount: legacy_balance.collateral_amount,
+            bump: legacy_balance.bump,
+            destination: legacy_balance.client_ata,
+        };
+        migration::store_migrated(
+            &self.client_balance,
+            &client_balance,
+            ClientBalance::INIT_SPACE,
+            &self.payer,
+            &self.system_program,
+        )?;
+
+        emit!(ClientBalanceMigrated {
+            client_balance: self.client_balance.key(),
+            client: client_balance.client,
+            destination: client_balance.destination,
+        });
+
+        Ok(())
+    }
+}
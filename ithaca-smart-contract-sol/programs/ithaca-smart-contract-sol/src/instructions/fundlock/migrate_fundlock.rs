@@ -0,0 +1,62 @@
+use crate::constants::DEFAULT_WITHDRAWAL_LIMIT;
+use crate::error::{AccessControlError, FundlockError};
+use crate::events::FundlockMigrated;
+use crate::migration;
+use crate::state::access_controller_state::AccessController;
+use crate::state::fundlock_state::{Fundlock, LegacyFundlock};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct MigrateFundlock<'info> {
+    // Legacy accounts can be migrated by the main access controller admin only
+    #[account(mut,
+        constraint = admin.key() == access_controller.admin @ AccessControlError::UnauthorizedAdmin)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+    )]
+    pub access_controller: Account<'info, AccessController>,
+    /// CHECK: Fundlock still in its legacy layout, checked by the handler against the access controller
+    #[account(mut, owner = crate::ID)]
+    pub fundlock: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateFundlock<'info> {
+    // Rewrites the fundlock with the current layout, paid by the admin. Clients get the default
+    // withdrawal limit, which governance can change afterwards.
+    pub fn migrate_fundlock(&mut self) -> Result<()> {
+        let legacy_fundlock: LegacyFundlock =
+            migration::load_legacy::<Fundlock, _>(&self.fundlock, LegacyFundlock::SPACE)?
+                .ok_or(FundlockError::AlreadyMigrated)?;
+        require_keys_eq!(
+            legacy_fundlock.access_controller,
+            self.access_controller.key(),
+            FundlockError::AlreadyMigrated
+        );
+
+        let fundlock = Fundlock {
+            access_controller: legacy_fundlock.access_controller,
+            token_validator: legacy_fundlock.token_validator,
+            trade_lock: legacy_fundlock.trade_lock,
+            release_lock: legacy_fundlock.release_lock,
+            bump: legacy_fundlock.bump,
+            withdrawal_limit: DEFAULT_WITHDRAWAL_LIMIT,
+        };
+        migration::store_migrated(
+            &self.fundlock,
+            &fundlock,
+            Fundlock::INIT_SPACE,
+            &self.admin,
+            &self.system_program,
+        )?;
+
+        emit!(FundlockMigrated {
+            fundlock: self.fundlock.key(),
+            withdrawal_limit: DEFAULT_WITHDRAWAL_LIMIT,
+        });
+
+        Ok(())
+    }
+}
@@ -1,6 +1,12 @@
 pub mod init_fundlock;
 pub use init_fundlock::*;
 
+pub mod migrate_fundlock;
+pub use migrate_fundlock::*;
+
+pub mod migrate_client_balance;
+pub use migrate_client_balance::*;
+
 pub mod deposit_fundlock;
 pub use deposit_fundlock::*;
 
@@ -9,3 +15,36 @@ pub use withdraw_fundlock::*;
 
 pub mod release_fundlock;
 pub use release_fundlock::*;
+
+pub mod deposit_fundlock_sol;
+pub use deposit_fundlock_sol::*;
+
+pub mod withdraw_fundlock_sol;
+pub use withdraw_fundlock_sol::*;
+
+pub mod release_fundlock_sol;
+pub use release_fundlock_sol::*;
+
+pub mod cancel_withdrawal;
+pub use cancel_withdrawal::*;
+
+pub mod release_all_matured;
+pub use release_all_matured::*;
+
+pub mod release_all_matured_sol;
+pub use release_all_matured_sol::*;
+
+pub mod set_keeper_tip;
+pub use set_keeper_tip::*;
+
+pub mod crank_release;
+pub use crank_release::*;
+
+pub mod crank_release_sol;
+pub use crank_release_sol::*;
+
+pub mod set_client_destination;
+pub use set_client_destination::*;
+
+pub mod release_fundlock_to;
+pub use release_fundlock_to::*;
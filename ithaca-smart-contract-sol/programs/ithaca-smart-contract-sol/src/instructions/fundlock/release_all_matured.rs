@@ -0,0 +1,128 @@
+use crate::error::{AccessControlError, FundlockError, TokenValidatorError};
+use crate::events::MaturedWithdrawalsReleased;
+use crate::state::access_controller_state::AccessController;
+use crate::state::fundlock_state::Fundlock;
+use crate::token_transfer::transfer_checked;
+use crate::{ClientBalance, Subsystem, TokenValidator, WhitelistedToken, Withdrawals};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked};
+
+#[derive(Accounts)]
+pub struct ReleaseAllMatured<'info> {
+    #[account(mut)]
+    pub client: Signer<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Releases) @ AccessControlError::Paused,
+    )]
+    pub access_controller: Box<Account<'info, AccessController>>,
+    #[account(
+        seeds = [b"token_validator".as_ref(), access_controller.key().as_ref()],
+        bump = token_validator.bump
+    )]
+    pub token_validator: Box<Account<'info, TokenValidator>>,
+    #[account(
+        seeds = [b"fundlock".as_ref(), access_controller.key().as_ref(), token_validator.key().as_ref()],
+        bump = fundlock.bump
+    )]
+    pub fundlock: Box<Account<'info, Fundlock>>,
+    #[account(
+        constraint = token.decimals > 0 @ TokenValidatorError::NonFungibleToken
+    )]
+    pub token: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"whitelisted_token".as_ref(), token_validator.key().as_ref(), token.key().as_ref()],
+        bump = whitelisted_token.bump
+    )]
+    pub whitelisted_token: Box<Account<'info, WhitelistedToken>>,
+    #[account(
+        mut,
+        seeds = [b"fundlock_token_vault".as_ref(), fundlock.key().as_ref(), token.key().as_ref()],
+        token::mint = token,
+        token::authority = fundlock,
+        bump,
+    )]
+    pub fundlock_token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        has_one = client,
+        seeds = [b"client_balance".as_ref(), fundlock_token_vault.key().as_ref(), client_ata.key().as_ref()],
+        bump = client_balance.bump
+    )]
+    pub client_balance: Box<Account<'info, ClientBalance>>,
+    /// CHECK: Only keys the client balance, so that releases work after it has been closed
+    pub client_ata: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        constraint = destination.mint == token.key() &&
+        destination.owner == client.key()
+    )]
+    pub destination: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"withdrawals".as_ref(), fundlock.key().as_ref(), client_balance.key().as_ref()],
+        bump = withdrawals.bump
+    )]
+    pub withdrawals: Box<Account<'info, Withdrawals>>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ReleaseAllMatured<'info> {
+    // Releases every matured withdrawal of the client with a single transfer to any token account
+    // of the client's, so that indices shifting between releases don't matter
+    pub fn release_all_matured(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let (amount_released, released_count) = self
+            .withdrawals
+            .take_matured(self.fundlock.release_lock, Clock::get()?.unix_timestamp)?;
+        require!(released_count > 0, FundlockError::NoMaturedWithdrawals);
+        require!(
+            self.fundlock_token_vault.amount >= amount_released,
+            FundlockError::InsufficientFundsInVault
+        );
+
+        let cpi_accounts = TransferChecked {
+            from: self.fundlock_token_vault.to_account_info(),
+            mint: self.token.to_account_info(),
+            to: self.destination.to_account_info(),
+            authority: self.fundlock.to_account_info(),
+        };
+
+        let access_validator_key = self.access_controller.key();
+        let token_validator_key = self.token_validator.key();
+
+        let fundlock_seeds: &[&[&[u8]]] = &[&[
+            b"fundlock".as_ref(),
+            access_validator_key.as_ref(),
+            token_validator_key.as_ref(),
+            &[self.fundlock.bump],
+        ]];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            fundlock_seeds,
+        )
+        .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked(cpi_ctx, amount_released, self.token.decimals)?;
+
+        // Ledger movements can leave more with a client than it deposited, so this only floors
+        self.whitelisted_token.total_deposits = self
+            .whitelisted_token
+            .total_deposits
+            .saturating_sub(amount_released);
+
+        emit!(MaturedWithdrawalsReleased {
+            fundlock: self.fundlock.key(),
+            client: self.client.key(),
+            token: self.token.key(),
+            amount: amount_released,
+            released_count,
+            active_withdrawals_amount: self.withdrawals.active_withdrawals_amount,
+        });
+
+        Ok(())
+    }
+}
@@ -1,17 +1,21 @@
-use crate::error::{FundlockError, TokenValidatorError};
+use crate::error::{AccessControlError, FundlockError, TokenValidatorError};
+use crate::events::WithdrawalReleased;
+use crate::math;
 use crate::state::access_controller_state::AccessController;
 use crate::state::fundlock_state::Fundlock;
-use crate::{ClientBalance, TokenValidator, WhitelistedToken, Withdrawals};
+use crate::token_transfer::transfer_checked;
+use crate::{ClientBalance, Subsystem, TokenValidator, WhitelistedToken, Withdrawals};
 use anchor_lang::prelude::*;
-use anchor_spl::token::{transfer, Mint, Token, TokenAccount, Transfer};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked};
 
 #[derive(Accounts)]
 pub struct ReleaseFundlock<'info> {
     #[account(mut)]
     pub client: Signer<'info>,
     #[account(
-        seeds = [b"access_controller".as_ref(), access_controller.admin.as_ref()],
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
         bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Releases) @ AccessControlError::Paused,
     )]
     pub access_controller: Box<Account<'info, AccessController>>,
     #[account(
@@ -27,8 +31,9 @@ pub struct ReleaseFundlock<'info> {
     #[account(
         constraint = token.decimals > 0 @ TokenValidatorError::NonFungibleToken
     )]
-    pub token: Box<Account<'info, Mint>>,
+    pub token: Box<InterfaceAccount<'info, Mint>>,
     #[account(
+        mut,
         seeds = [b"whitelisted_token".as_ref(), token_validator.key().as_ref(), token.key().as_ref()],
         bump = whitelisted_token.bump
     )]
@@ -40,7 +45,7 @@ pub struct ReleaseFundlock<'info> {
         token::authority = fundlock,
         bump,
     )]
-    pub fundlock_token_vault: Box<Account<'info, TokenAccount>>,
+    pub fundlock_token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         seeds = [b"client_balance".as_ref(), fundlock_token_vault.key().as_ref(), client_ata.key().as_ref()],
         bump = client_balance.bump
@@ -51,7 +56,7 @@ pub struct ReleaseFundlock<'info> {
         constraint = client_ata.mint == token.key() &&
         client_ata.owner == client.key()
     )]
-    pub client_ata: Box<Account<'info, TokenAccount>>,
+    pub client_ata: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         mut,
         seeds = [b"withdrawals".as_ref(), fundlock.key().as_ref(), client_balance.key().as_ref()],
@@ -59,43 +64,77 @@ pub struct ReleaseFundlock<'info> {
     )]
     pub withdrawals: Box<Account<'info, Withdrawals>>,
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 impl<'info> ReleaseFundlock<'info> {
-    pub fn release_fundlock(&mut self, index: u64) -> Result<()> {
+    pub fn release_fundlock(
+        &mut self,
+        index: u64,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        WithdrawalRelease {
+            client: self.client.key(),
+            access_controller: self.access_controller.key(),
+            token_validator: self.token_validator.key(),
+            fundlock: &self.fundlock,
+            token: &self.token,
+            whitelisted_token: &mut self.whitelisted_token,
+            fundlock_token_vault: &self.fundlock_token_vault,
+            withdrawals: &mut self.withdrawals,
+            token_program: &self.token_program,
+            to: self.client_ata.to_account_info(),
+        }
+        .release(index, remaining_accounts)
+    }
+}
+
+// Releases a single matured withdrawal, shared by release_fundlock and release_fundlock_to which
+// only differ in the token account paid out to
+pub struct WithdrawalRelease<'a, 'info> {
+    pub client: Pubkey,
+    pub access_controller: Pubkey,
+    pub token_validator: Pubkey,
+    pub fundlock: &'a Account<'info, Fundlock>,
+    pub token: &'a InterfaceAccount<'info, Mint>,
+    pub whitelisted_token: &'a mut Account<'info, WhitelistedToken>,
+    pub fundlock_token_vault: &'a InterfaceAccount<'info, TokenAccount>,
+    pub withdrawals: &'a mut Account<'info, Withdrawals>,
+    pub token_program: &'a Interface<'info, TokenInterface>,
+    pub to: AccountInfo<'info>,
+}
+
+impl<'a, 'info> WithdrawalRelease<'a, 'info> {
+    pub fn release(self, index: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
         require!(
             self.withdrawals.withdrawal_queue.len() > index as usize,
             FundlockError::InvalidIndex
         );
         require!(
-            self.withdrawals.withdrawal_queue[index as usize].timestamp
-                + self.fundlock.release_lock
-                < Clock::get()?.unix_timestamp,
+            math::add_signed(
+                self.withdrawals.withdrawal_queue[index as usize].timestamp,
+                self.fundlock.release_lock
+            )? < Clock::get()?.unix_timestamp,
             FundlockError::ReleaseLockActive
         );
 
+        let amount_released = self.withdrawals.withdrawal_queue[index as usize].amount;
         require!(
-            self.fundlock_token_vault.amount
-                >= self.withdrawals.withdrawal_queue[index as usize].amount,
+            self.fundlock_token_vault.amount >= amount_released,
             FundlockError::InsufficientFundsInVault
         );
 
-        let amount_released = self.withdrawals.withdrawal_queue[index as usize].amount;
-        
-        let cpi_accounts = Transfer {
+        let cpi_accounts = TransferChecked {
             from: self.fundlock_token_vault.to_account_info(),
-            to: self.client_ata.to_account_info(),
+            mint: self.token.to_account_info(),
+            to: self.to,
             authority: self.fundlock.to_account_info(),
         };
 
-        let access_validator_key = self.access_controller.key();
-        let token_validator_key = self.token_validator.key();
-
         let fundlock_seeds: &[&[&[u8]]] = &[&[
             b"fundlock".as_ref(),
-            access_validator_key.as_ref(),
-            token_validator_key.as_ref(),
+            self.access_controller.as_ref(),
+            self.token_validator.as_ref(),
             &[self.fundlock.bump],
         ]];
 
@@ -103,34 +142,29 @@ impl<'info> ReleaseFundlock<'info> {
             self.token_program.to_account_info(),
             cpi_accounts,
             fundlock_seeds,
-        );
+        )
+        .with_remaining_accounts(remaining_accounts.to_vec());
 
-        transfer(cpi_ctx, amount_released).expect("Error transferring tokens");
+        transfer_checked(cpi_ctx, amount_released, self.token.decimals)?;
 
         self.withdrawals.withdrawal_queue.remove(index as usize);
-        self.withdrawals.active_withdrawals_amount -= amount_released;
+        self.withdrawals.active_withdrawals_amount =
+            math::sub(self.withdrawals.active_withdrawals_amount, amount_released)?;
+        // Ledger movements can leave more with a client than it deposited, so this only floors
+        self.whitelisted_token.total_deposits = self
+            .whitelisted_token
+            .total_deposits
+            .saturating_sub(amount_released);
 
-        msg!(
-            "Client: {} released {} tokens from the fundlock",
-            self.client.key(),
-            amount_released
-        );
-
-        // Log the current active withdrawals amount
-        msg!(
-            "Current client's active withdrawals amount: {}",
-            self.withdrawals.active_withdrawals_amount
-        );
+        emit!(WithdrawalReleased {
+            fundlock: self.fundlock.key(),
+            client: self.client,
+            token: self.token.key(),
+            amount: amount_released,
+            index,
+            active_withdrawals_amount: self.withdrawals.active_withdrawals_amount,
+        });
 
-        msg!("Current withdrawals for the client:");
-        for (i, withdrawal) in self.withdrawals.withdrawal_queue.iter().enumerate() {
-            msg!(
-                "Index: {}, Amount: {}, Timestamp: {}",
-                i,
-                withdrawal.amount,
-                withdrawal.timestamp
-            );
-        }
         Ok(())
     }
 }
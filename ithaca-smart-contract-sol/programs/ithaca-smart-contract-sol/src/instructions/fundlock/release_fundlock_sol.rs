@@ -0,0 +1,152 @@
+use crate::error::{AccessControlError, FundlockError};
+use crate::events::WithdrawalReleased;
+use crate::math;
+use crate::state::access_controller_state::AccessController;
+use crate::state::fundlock_state::Fundlock;
+use crate::{ClientBalance, Subsystem, TokenValidator, WhitelistedToken, Withdrawals};
+use anchor_lang::prelude::*;
+use anchor_spl::token::spl_token::native_mint;
+use anchor_spl::token::Token;
+use anchor_spl::token_interface::{
+    close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TransferChecked,
+};
+
+#[derive(Accounts)]
+pub struct ReleaseFundlockSol<'info> {
+    #[account(mut)]
+    pub client: Signer<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Releases) @ AccessControlError::Paused,
+    )]
+    pub access_controller: Box<Account<'info, AccessController>>,
+    #[account(
+        seeds = [b"token_validator".as_ref(), access_controller.key().as_ref()],
+        bump = token_validator.bump
+    )]
+    pub token_validator: Box<Account<'info, TokenValidator>>,
+    #[account(
+        seeds = [b"fundlock".as_ref(), access_controller.key().as_ref(), token_validator.key().as_ref()],
+        bump = fundlock.bump
+    )]
+    pub fundlock: Box<Account<'info, Fundlock>>,
+    #[account(address = native_mint::ID)]
+    pub token: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"whitelisted_token".as_ref(), token_validator.key().as_ref(), token.key().as_ref()],
+        bump = whitelisted_token.bump
+    )]
+    pub whitelisted_token: Box<Account<'info, WhitelistedToken>>,
+    #[account(
+        mut,
+        seeds = [b"fundlock_token_vault".as_ref(), fundlock.key().as_ref(), token.key().as_ref()],
+        token::mint = token,
+        token::authority = fundlock,
+        bump,
+    )]
+    pub fundlock_token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"client_balance".as_ref(), fundlock_token_vault.key().as_ref(), client.key().as_ref()],
+        bump = client_balance.bump
+    )]
+    pub client_balance: Box<Account<'info, ClientBalance>>,
+    #[account(
+        mut,
+        seeds = [b"withdrawals".as_ref(), fundlock.key().as_ref(), client_balance.key().as_ref()],
+        bump = withdrawals.bump
+    )]
+    pub withdrawals: Box<Account<'info, Withdrawals>>,
+    // Receives the released wSOL and is closed to the client within the same instruction,
+    // which unwraps it together with the rent the client paid for it
+    #[account(
+        init,
+        payer = client,
+        seeds = [b"sol_release".as_ref(), fundlock_token_vault.key().as_ref(), client.key().as_ref()],
+        token::mint = token,
+        token::authority = fundlock,
+        token::token_program = token_program,
+        bump,
+    )]
+    pub unwrap_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> ReleaseFundlockSol<'info> {
+    pub fn release_fundlock_sol(&mut self, index: u64) -> Result<()> {
+        require!(
+            self.withdrawals.withdrawal_queue.len() > index as usize,
+            FundlockError::InvalidIndex
+        );
+        require!(
+            math::add_signed(
+                self.withdrawals.withdrawal_queue[index as usize].timestamp,
+                self.fundlock.release_lock
+            )? < Clock::get()?.unix_timestamp,
+            FundlockError::ReleaseLockActive
+        );
+
+        let amount_released = self.withdrawals.withdrawal_queue[index as usize].amount;
+        require!(
+            self.fundlock_token_vault.amount >= amount_released,
+            FundlockError::InsufficientFundsInVault
+        );
+
+        let access_validator_key = self.access_controller.key();
+        let token_validator_key = self.token_validator.key();
+
+        let fundlock_seeds: &[&[&[u8]]] = &[&[
+            b"fundlock".as_ref(),
+            access_validator_key.as_ref(),
+            token_validator_key.as_ref(),
+            &[self.fundlock.bump],
+        ]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.fundlock_token_vault.to_account_info(),
+            mint: self.token.to_account_info(),
+            to: self.unwrap_account.to_account_info(),
+            authority: self.fundlock.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            fundlock_seeds,
+        );
+        transfer_checked(cpi_ctx, amount_released, self.token.decimals)?;
+
+        let cpi_accounts = CloseAccount {
+            account: self.unwrap_account.to_account_info(),
+            destination: self.client.to_account_info(),
+            authority: self.fundlock.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            fundlock_seeds,
+        );
+        close_account(cpi_ctx)?;
+
+        self.withdrawals.withdrawal_queue.remove(index as usize);
+        self.withdrawals.active_withdrawals_amount =
+            math::sub(self.withdrawals.active_withdrawals_amount, amount_released)?;
+        // Ledger movements can leave more with a client than it deposited, so this only floors
+        self.whitelisted_token.total_deposits = self
+            .whitelisted_token
+            .total_deposits
+            .saturating_sub(amount_released);
+
+        emit!(WithdrawalReleased {
+            fundlock: self.fundlock.key(),
+            client: self.client.key(),
+            token: self.token.key(),
+            amount: amount_released,
+            index,
+            active_withdrawals_amount: self.withdrawals.active_withdrawals_amount,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+use crate::error::{AccessControlError, TokenValidatorError};
+use crate::instructions::fundlock::release_fundlock::WithdrawalRelease;
+use crate::state::access_controller_state::AccessController;
+use crate::state::fundlock_state::Fundlock;
+use crate::{ClientBalance, Subsystem, TokenValidator, WhitelistedToken, Withdrawals};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct ReleaseFundlockTo<'info> {
+    #[account(mut)]
+    pub client: Signer<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Releases) @ AccessControlError::Paused,
+    )]
+    pub access_controller: Box<Account<'info, AccessController>>,
+    #[account(
+        seeds = [b"token_validator".as_ref(), access_controller.key().as_ref()],
+        bump = token_validator.bump
+    )]
+    pub token_validator: Box<Account<'info, TokenValidator>>,
+    #[account(
+        seeds = [b"fundlock".as_ref(), access_controller.key().as_ref(), token_validator.key().as_ref()],
+        bump = fundlock.bump
+    )]
+    pub fundlock: Box<Account<'info, Fundlock>>,
+    #[account(
+        constraint = token.decimals > 0 @ TokenValidatorError::NonFungibleToken
+    )]
+    pub token: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"whitelisted_token".as_ref(), token_validator.key().as_ref(), token.key().as_ref()],
+        bump = whitelisted_token.bump
+    )]
+    pub whitelisted_token: Box<Account<'info, WhitelistedToken>>,
+    #[account(
+        mut,
+        seeds = [b"fundlock_token_vault".as_ref(), fundlock.key().as_ref(), token.key().as_ref()],
+        token::mint = token,
+        token::authority = fundlock,
+        bump,
+    )]
+    pub fundlock_token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        has_one = client,
+        seeds = [b"client_balance".as_ref(), fundlock_token_vault.key().as_ref(), client_balance.client_ata.as_ref()],
+        bump = client_balance.bump
+    )]
+    pub client_balance: Box<Account<'info, ClientBalance>>,
+    #[account(
+        mut,
+        constraint = destination.mint == token.key() &&
+        destination.owner == client.key()
+    )]
+    pub destination: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"withdrawals".as_ref(), fundlock.key().as_ref(), client_balance.key().as_ref()],
+        bump = withdrawals.bump
+    )]
+    pub withdrawals: Box<Account<'info, Withdrawals>>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ReleaseFundlockTo<'info> {
+    // Same as release_fundlock, to any token account of the client's
+    pub fn release_fundlock_to(
+        &mut self,
+        index: u64,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        WithdrawalRelease {
+            client: self.client.key(),
+            access_controller: self.access_controller.key(),
+            token_validator: self.token_validator.key(),
+            fundlock: &self.fundlock,
+            token: &self.token,
+            whitelisted_token: &mut self.whitelisted_token,
+            fundlock_token_vault: &self.fundlock_token_vault,
+            withdrawals: &mut self.withdrawals,
+            token_program: &self.token_program,
+            to: self.destination.to_account_info(),
+        }
+        .release(index, remaining_accounts)
+    }
+}
@@ -0,0 +1,68 @@
+use crate::error::{AccessControlError, TokenValidatorError};
+use crate::events::ClientDestinationSet;
+use crate::state::access_controller_state::AccessController;
+use crate::state::fundlock_state::Fundlock;
+use crate::{ClientBalance, Subsystem, TokenValidator};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+#[derive(Accounts)]
+pub struct SetClientDestination<'info> {
+    pub client: Signer<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Releases) @ AccessControlError::Paused,
+    )]
+    pub access_controller: Box<Account<'info, AccessController>>,
+    #[account(
+        seeds = [b"token_validator".as_ref(), access_controller.key().as_ref()],
+        bump = token_validator.bump
+    )]
+    pub token_validator: Box<Account<'info, TokenValidator>>,
+    #[account(
+        seeds = [b"fundlock".as_ref(), access_controller.key().as_ref(), token_validator.key().as_ref()],
+        bump = fundlock.bump
+    )]
+    pub fundlock: Box<Account<'info, Fundlock>>,
+    #[account(
+        constraint = token.decimals > 0 @ TokenValidatorError::NonFungibleToken
+    )]
+    pub token: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        seeds = [b"fundlock_token_vault".as_ref(), fundlock.key().as_ref(), token.key().as_ref()],
+        token::mint = token,
+        token::authority = fundlock,
+        bump,
+    )]
+    pub fundlock_token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        has_one = client,
+        seeds = [b"client_balance".as_ref(), fundlock_token_vault.key().as_ref(), client_balance.client_ata.as_ref()],
+        bump = client_balance.bump
+    )]
+    pub client_balance: Box<Account<'info, ClientBalance>>,
+    #[account(
+        constraint = destination.mint == token.key() &&
+        destination.owner == client.key()
+    )]
+    pub destination: Box<InterfaceAccount<'info, TokenAccount>>,
+}
+
+impl<'info> SetClientDestination<'info> {
+    // The balance stays keyed by the token account it was deposited from, only where cranked
+    // releases go changes
+    pub fn set_client_destination(&mut self) -> Result<()> {
+        self.client_balance.destination = self.destination.key();
+
+        emit!(ClientDestinationSet {
+            fundlock: self.fundlock.key(),
+            client: self.client.key(),
+            token: self.token.key(),
+            client_balance: self.client_balance.key(),
+            destination: self.destination.key(),
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+use crate::error::AccessControlError;
+use crate::events::KeeperTipSet;
+use crate::state::access_controller_state::Role;
+use crate::state::fundlock_state::{Fundlock, KeeperPool};
+use crate::{AccessController, Member, Roles, Subsystem, TokenValidator};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetKeeperTip<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Config) @ AccessControlError::Paused,
+    )]
+    pub access_controller: Account<'info, AccessController>,
+    #[account(
+        seeds = [b"role".as_ref(), access_controller.key().as_ref(), Roles::Admin.as_str().as_bytes()],
+        bump = role.bump
+    )]
+    pub role: Account<'info, Role>,
+    #[account(
+        seeds = [b"member".as_ref(), access_controller.key().as_ref(), admin.key().as_ref()],
+        bump = member.bump,
+        constraint = member.has_role(Roles::Admin) @ AccessControlError::NoRole,
+        constraint = !member.is_expired(Roles::Admin)? @ AccessControlError::RoleExpired
+    )]
+    pub member: Account<'info, Member>,
+    #[account(
+        seeds = [b"token_validator".as_ref(), access_controller.key().as_ref()],
+        bump = token_validator.bump
+    )]
+    pub token_validator: Account<'info, TokenValidator>,
+    #[account(
+        seeds = [b"fundlock".as_ref(), access_controller.key().as_ref(), token_validator.key().as_ref()],
+        bump = fundlock.bump
+    )]
+    pub fundlock: Account<'info, Fundlock>,
+    // created on first use, the pool is then topped up with plain lamport transfers
+    #[account(
+        init_if_needed,
+        payer = admin,
+        seeds = [b"keeper_pool".as_ref(), fundlock.key().as_ref()],
+        space = KeeperPool::INIT_SPACE,
+        bump,
+    )]
+    pub keeper_pool: Account<'info, KeeperPool>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SetKeeperTip<'info> {
+    pub fn set_keeper_tip(
+        &mut self,
+        tip: u64,
+        min_release: u64,
+        bumps: &SetKeeperTipBumps,
+    ) -> Result<()> {
+        self.keeper_pool.set_inner(KeeperPool {
+            fundlock: self.fundlock.key(),
+            tip,
+            bump: bumps.keeper_pool,
+            min_release,
+        });
+
+        emit!(KeeperTipSet {
+            fundlock: self.fundlock.key(),
+            keeper_pool: self.keeper_pool.key(),
+            tip,
+            min_release,
+        });
+        Ok(())
+    }
+}
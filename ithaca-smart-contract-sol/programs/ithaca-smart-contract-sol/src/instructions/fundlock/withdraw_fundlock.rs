@@ -1,20 +1,22 @@
-use crate::error::{FundlockError, TokenValidatorError};
+use crate::error::{AccessControlError, FundlockError, TokenValidatorError};
+use crate::events::WithdrawalQueued;
+use crate::math;
 use crate::state::access_controller_state::AccessController;
 use crate::state::fundlock_state::Fundlock;
 use crate::{
-    ClientBalance, TokenValidator, WhitelistedToken, WithdrawalState, Withdrawals,
-    ALLOWED_WITHDRAWAL_LIMIT,
+    ClientBalance, Subsystem, TokenValidator, WhitelistedToken, WithdrawalState, Withdrawals,
 };
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 
 #[derive(Accounts)]
 pub struct WithdrawFundlock<'info> {
     #[account(mut)]
     pub client: Signer<'info>,
     #[account(
-        seeds = [b"access_controller".as_ref(), access_controller.admin.as_ref()],
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
         bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Withdrawals) @ AccessControlError::Paused,
     )]
     pub access_controller: Box<Account<'info, AccessController>>,
     #[account(
@@ -30,7 +32,7 @@ pub struct WithdrawFundlock<'info> {
     #[account(
         constraint = token.decimals > 0 @ TokenValidatorError::NonFungibleToken
     )]
-    pub token: Box<Account<'info, Mint>>,
+    pub token: Box<InterfaceAccount<'info, Mint>>,
     #[account(
         seeds = [b"whitelisted_token".as_ref(), token_validator.key().as_ref(), token.key().as_ref()],
         bump = whitelisted_token.bump
@@ -43,26 +45,29 @@ pub struct WithdrawFundlock<'info> {
         token::authority = fundlock,
         bump,
     )]
-    pub fundlock_token_vault: Box<Account<'info, TokenAccount>>,
+    pub fundlock_token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account( 
         mut,
+        has_one = client,
         seeds = [b"client_balance".as_ref(), fundlock_token_vault.key().as_ref(), client_ata.key().as_ref()],
         bump = client_balance.bump
     )]
     pub client_balance: Box<Account<'info, ClientBalance>>,
-    #[account(
-        constraint = client_ata.mint == token.key() &&
-        client_ata.owner == client.key()
-    )]
-    pub client_ata: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Only keys the client balance, so that withdrawals work after it has been closed
+    pub client_ata: UncheckedAccount<'info>,
+    // grown by an entry whenever the queue outgrows the account, up to the fundlock's limit
     #[account(
         mut,
         seeds = [b"withdrawals".as_ref(), fundlock.key().as_ref(), client_balance.key().as_ref()],
-        bump = withdrawals.bump
+        bump = withdrawals.bump,
+        realloc = Withdrawals::space(withdrawals.withdrawal_queue.len() + 1)
+            .max(withdrawals.to_account_info().data_len()),
+        realloc::payer = client,
+        realloc::zero = false,
     )]
     pub withdrawals: Box<Account<'info, Withdrawals>>,
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 impl<'info> WithdrawFundlock<'info> {
@@ -73,49 +78,41 @@ impl<'info> WithdrawFundlock<'info> {
             FundlockError::InsufficientFunds
         );
         require!(
-            self.withdrawals.withdrawal_queue.len() < ALLOWED_WITHDRAWAL_LIMIT,
+            (self.withdrawals.withdrawal_queue.len() as u64) < self.fundlock.withdrawal_limit,
             FundlockError::WithdrawalLimitReached
         );
 
-        let withdrawal = WithdrawalState {
-            amount,
-            timestamp: Clock::get()?.unix_timestamp,
-        };
+        let timestamp = Clock::get()?.unix_timestamp;
+        let withdrawal = WithdrawalState { amount, timestamp };
 
         self.withdrawals.withdrawal_queue.push(withdrawal);
 
         self.client_balance.set_inner(ClientBalance {
-            amount: self.client_balance.amount - amount,
+            amount: math::sub(self.client_balance.amount, amount)?,
             token: self.token.key(),
             client: self.client.key(),
             client_ata: self.client_ata.key(),
             collateral_amount: self.client_balance.collateral_amount,
             bump: self.client_balance.bump,
+            destination: self.client_balance.destination,
         });
 
-        self.withdrawals.active_withdrawals_amount += amount;
+        self.withdrawals.active_withdrawals_amount =
+            math::add(self.withdrawals.active_withdrawals_amount, amount)?;
         self.withdrawals.client = self.client.key();
         // self.withdrawals.bump = bumps.withdrawals;
 
         let index = self.withdrawals.withdrawal_queue.len() - 1;
 
-        // Log the index of the new element
-        msg!("New withdrawal of {} added at index: {}", amount, index);
-
-        //log the active withdrawal amoun
-        msg!(
-            "Active withdrawal amount: {}",
-            self.withdrawals.active_withdrawals_amount.to_string()
-        );
-
-        // Log the withdrawal event
-        msg!(
-            "Withdraw queued: client={}, token={}, amount={}, slot={}",
-            self.client.key(),
-            self.token.key(),
+        emit!(WithdrawalQueued {
+            fundlock: self.fundlock.key(),
+            client: self.client.key(),
+            token: self.token.key(),
             amount,
-            index
-        );
+            index: index as u64,
+            timestamp,
+            active_withdrawals_amount: self.withdrawals.active_withdrawals_amount,
+        });
         Ok(())
     }
 }
@@ -0,0 +1,101 @@
+use crate::error::{AccessControlError, FundlockError};
+use crate::events::WithdrawalQueued;
+use crate::math;
+use crate::state::access_controller_state::AccessController;
+use crate::state::fundlock_state::Fundlock;
+use crate::{
+    ClientBalance, Subsystem, TokenValidator, WhitelistedToken, WithdrawalState, Withdrawals,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token::spl_token::native_mint;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+#[derive(Accounts)]
+pub struct WithdrawFundlockSol<'info> {
+    #[account(mut)]
+    pub client: Signer<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Withdrawals) @ AccessControlError::Paused,
+    )]
+    pub access_controller: Box<Account<'info, AccessController>>,
+    #[account(
+        seeds = [b"token_validator".as_ref(), access_controller.key().as_ref()],
+        bump = token_validator.bump
+    )]
+    pub token_validator: Box<Account<'info, TokenValidator>>,
+    #[account(
+        seeds = [b"fundlock".as_ref(), access_controller.key().as_ref(), token_validator.key().as_ref()],
+        bump = fundlock.bump
+    )]
+    pub fundlock: Box<Account<'info, Fundlock>>,
+    #[account(address = native_mint::ID)]
+    pub token: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        seeds = [b"whitelisted_token".as_ref(), token_validator.key().as_ref(), token.key().as_ref()],
+        bump = whitelisted_token.bump
+    )]
+    pub whitelisted_token: Box<Account<'info, WhitelistedToken>>,
+    #[account(
+        seeds = [b"fundlock_token_vault".as_ref(), fundlock.key().as_ref(), token.key().as_ref()],
+        token::mint = token,
+        token::authority = fundlock,
+        bump,
+    )]
+    pub fundlock_token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"client_balance".as_ref(), fundlock_token_vault.key().as_ref(), client.key().as_ref()],
+        bump = client_balance.bump
+    )]
+    pub client_balance: Box<Account<'info, ClientBalance>>,
+    // grown by an entry whenever the queue outgrows the account, up to the fundlock's limit
+    #[account(
+        mut,
+        seeds = [b"withdrawals".as_ref(), fundlock.key().as_ref(), client_balance.key().as_ref()],
+        bump = withdrawals.bump,
+        realloc = Withdrawals::space(withdrawals.withdrawal_queue.len() + 1)
+            .max(withdrawals.to_account_info().data_len()),
+        realloc::payer = client,
+        realloc::zero = false,
+    )]
+    pub withdrawals: Box<Account<'info, Withdrawals>>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> WithdrawFundlockSol<'info> {
+    pub fn withdraw_fundlock_sol(&mut self, amount: u64) -> Result<()> {
+        require!(amount > 0, FundlockError::AmountZero);
+        require!(
+            self.client_balance.amount >= amount,
+            FundlockError::InsufficientFunds
+        );
+        require!(
+            (self.withdrawals.withdrawal_queue.len() as u64) < self.fundlock.withdrawal_limit,
+            FundlockError::WithdrawalLimitReached
+        );
+
+        let timestamp = Clock::get()?.unix_timestamp;
+        self.withdrawals
+            .withdrawal_queue
+            .push(WithdrawalState { amount, timestamp });
+
+        self.client_balance.amount = math::sub(self.client_balance.amount, amount)?;
+        self.withdrawals.active_withdrawals_amount =
+            math::add(self.withdrawals.active_withdrawals_amount, amount)?;
+
+        let index = self.withdrawals.withdrawal_queue.len() - 1;
+
+        emit!(WithdrawalQueued {
+            fundlock: self.fundlock.key(),
+            client: self.client.key(),
+            token: self.token.key(),
+            amount,
+            index: index as u64,
+            timestamp,
+            active_withdrawals_amount: self.withdrawals.active_withdrawals_amount,
+        });
+        Ok(())
+    }
+}
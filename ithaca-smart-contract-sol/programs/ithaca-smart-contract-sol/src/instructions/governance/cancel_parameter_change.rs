@@ -0,0 +1,44 @@
+use crate::error::AccessControlError;
+use crate::events::ParameterChangeCancelled;
+use crate::state::{AccessController, Governance, ParameterChangeRequest};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct CancelParameterChange<'info> {
+    // Changes can be cancelled by the main access controller admin only
+    #[account(
+        constraint = admin.key() == access_controller.admin @ AccessControlError::UnauthorizedAdmin)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+    )]
+    pub access_controller: Account<'info, AccessController>,
+    #[account(
+        seeds = [b"governance".as_ref(), access_controller.key().as_ref()],
+        bump = governance.bump
+    )]
+    pub governance: Account<'info, Governance>,
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [b"parameter_change".as_ref(), governance.key().as_ref(), parameter_change.change_id.to_le_bytes().as_ref()],
+        bump = parameter_change.bump
+    )]
+    pub parameter_change: Account<'info, ParameterChangeRequest>,
+    /// CHECK: Only receives the rent of the request, must be the admin that scheduled it
+    #[account(mut, address = parameter_change.proposer)]
+    pub proposer: UncheckedAccount<'info>,
+}
+
+impl<'info> CancelParameterChange<'info> {
+    // Changes can be cancelled up to their execution, including after their ETA and during a pause
+    pub fn cancel_parameter_change(&mut self) -> Result<()> {
+        emit!(ParameterChangeCancelled {
+            governance: self.governance.key(),
+            change_id: self.parameter_change.change_id,
+        });
+
+        Ok(())
+    }
+}
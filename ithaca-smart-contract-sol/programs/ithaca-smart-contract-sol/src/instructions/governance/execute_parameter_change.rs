@@ -0,0 +1,54 @@
+use crate::error::{AccessControlError, GovernanceError};
+use crate::events::ParameterChangeExecuted;
+use crate::state::{AccessController, Governance, ParameterChangeRequest};
+use crate::Subsystem;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct ExecuteParameterChange<'info> {
+    // Anyone can execute a change once its ETA has passed
+    pub executor: Signer<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Config) @ AccessControlError::Paused,
+    )]
+    pub access_controller: Account<'info, AccessController>,
+    #[account(
+        seeds = [b"governance".as_ref(), access_controller.key().as_ref()],
+        bump = governance.bump
+    )]
+    pub governance: Account<'info, Governance>,
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [b"parameter_change".as_ref(), governance.key().as_ref(), parameter_change.change_id.to_le_bytes().as_ref()],
+        bump = parameter_change.bump
+    )]
+    pub parameter_change: Account<'info, ParameterChangeRequest>,
+    /// CHECK: Account the change applies to, checked when the change was scheduled
+    #[account(mut, address = parameter_change.target @ GovernanceError::InvalidTarget)]
+    pub target: UncheckedAccount<'info>,
+    /// CHECK: Only receives the rent of the request, must be the admin that scheduled it
+    #[account(mut, address = parameter_change.proposer)]
+    pub proposer: UncheckedAccount<'info>,
+}
+
+impl<'info> ExecuteParameterChange<'info> {
+    pub fn execute_parameter_change(&mut self) -> Result<()> {
+        require!(
+            Clock::get()?.unix_timestamp >= self.parameter_change.eta,
+            GovernanceError::ChangeNotReady
+        );
+        self.parameter_change.change.apply(&self.target)?;
+
+        emit!(ParameterChangeExecuted {
+            governance: self.governance.key(),
+            change_id: self.parameter_change.change_id,
+            target: self.target.key(),
+            change: self.parameter_change.change,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+use crate::error::{AccessControlError, GovernanceError};
+use crate::events::GovernanceInitialized;
+use crate::state::{AccessController, Governance};
+use crate::Subsystem;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct InitGovernance<'info> {
+    #[account(mut,
+        constraint = admin.key() == access_controller.admin @ AccessControlError::UnauthorizedAdmin)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Config) @ AccessControlError::Paused,
+    )]
+    pub access_controller: Account<'info, AccessController>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"governance".as_ref(), access_controller.key().as_ref()],
+        space = Governance::INIT_SPACE,
+        bump
+    )]
+    pub governance: Account<'info, Governance>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitGovernance<'info> {
+    pub fn init_governance(&mut self, min_delay: i64, bumps: &InitGovernanceBumps) -> Result<()> {
+        require!(min_delay >= 0, GovernanceError::InvalidParameterValue);
+        self.governance.set_inner(Governance {
+            access_controller: self.access_controller.key(),
+            min_delay,
+            change_count: 0,
+            bump: bumps.governance,
+        });
+
+        emit!(GovernanceInitialized {
+            access_controller: self.access_controller.key(),
+            governance: self.governance.key(),
+            min_delay,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,11 @@
+pub mod init_governance;
+pub use init_governance::*;
+
+pub mod schedule_parameter_change;
+pub use schedule_parameter_change::*;
+
+pub mod cancel_parameter_change;
+pub use cancel_parameter_change::*;
+
+pub mod execute_parameter_change;
+pub use execute_parameter_change::*;
@@ -0,0 +1,76 @@
+use crate::error::{AccessControlError, GovernanceError};
+use crate::events::ParameterChangeScheduled;
+use crate::math;
+use crate::state::{AccessController, Governance, ParameterChange, ParameterChangeRequest};
+use crate::Subsystem;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct ScheduleParameterChange<'info> {
+    // Changes can be scheduled by the main access controller admin only
+    #[account(mut,
+        constraint = admin.key() == access_controller.admin @ AccessControlError::UnauthorizedAdmin)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Config) @ AccessControlError::Paused,
+    )]
+    pub access_controller: Account<'info, AccessController>,
+    #[account(
+        mut,
+        seeds = [b"governance".as_ref(), access_controller.key().as_ref()],
+        bump = governance.bump
+    )]
+    pub governance: Account<'info, Governance>,
+    /// CHECK: Fundlock, whitelisted token, ledger or governance account, checked against the change
+    pub target: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"parameter_change".as_ref(), governance.key().as_ref(), governance.change_count.to_le_bytes().as_ref()],
+        space = ParameterChangeRequest::INIT_SPACE,
+        bump
+    )]
+    pub parameter_change: Account<'info, ParameterChangeRequest>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ScheduleParameterChange<'info> {
+    // Queues the change so that clients get notice of it, it can be executed from its ETA
+    pub fn schedule_parameter_change(
+        &mut self,
+        change: ParameterChange,
+        eta: i64,
+        bumps: &ScheduleParameterChangeBumps,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            eta >= now.saturating_add(self.governance.min_delay),
+            GovernanceError::EtaTooEarly
+        );
+        change.validate(&self.target, &self.access_controller.key())?;
+
+        let change_id = self.governance.change_count;
+        self.parameter_change.set_inner(ParameterChangeRequest {
+            governance: self.governance.key(),
+            change_id,
+            target: self.target.key(),
+            change,
+            eta,
+            proposer: self.admin.key(),
+            bump: bumps.parameter_change,
+        });
+        self.governance.change_count = math::add(change_id, 1)?;
+
+        emit!(ParameterChangeScheduled {
+            governance: self.governance.key(),
+            change_id,
+            target: self.target.key(),
+            change,
+            eta,
+        });
+
+        Ok(())
+    }
+}
@@ -1,10 +1,13 @@
-use crate::error::{FundlockError, TokenValidatorError};
+use crate::error::{AccessControlError, FundlockError, TokenValidatorError};
+use crate::events::KaminoRedeemed;
+use crate::math;
 use crate::state::access_controller_state::AccessController;
 use crate::state::fundlock_state::Fundlock;
-use crate::{ClientBalance, KLend, TokenValidator, WhitelistedToken};
+use crate::{ClientBalance, KLend, Subsystem, TokenValidator, WhitelistedToken};
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::sysvar::instructions;
-use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_spl::token::Token;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 use kamino_lending_interface::*;
 
 #[derive(Accounts)]
@@ -12,8 +15,9 @@ pub struct ReedemKamino<'info> {
     #[account(mut)]
     pub client: Signer<'info>,
     #[account(
-        seeds = [b"access_controller".as_ref(), access_controller.admin.as_ref()],
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
         bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Kamino) @ AccessControlError::Paused,
     )]
     pub access_controller: Box<Account<'info, AccessController>>,
     #[account(
@@ -31,7 +35,7 @@ pub struct ReedemKamino<'info> {
         mut,
         constraint = token.decimals > 0 @ TokenValidatorError::NonFungibleToken
     )]
-    pub token: Box<Account<'info, Mint>>,
+    pub token: Box<InterfaceAccount<'info, Mint>>,
     #[account(
         seeds = [b"whitelisted_token".as_ref(), token_validator.key().as_ref(), token.key().as_ref()],
         bump = whitelisted_token.bump
@@ -44,13 +48,13 @@ pub struct ReedemKamino<'info> {
         token::authority = fundlock,
         bump,
     )]
-    pub fundlock_token_vault: Box<Account<'info, TokenAccount>>,
+    pub fundlock_token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         mut,
         constraint = client_ata.mint == token.key() &&
         client_ata.owner == client.key()
     )]
-    pub client_ata: Box<Account<'info, TokenAccount>>,
+    pub client_ata: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account( 
         mut,
         seeds = [b"client_balance".as_ref(), fundlock_token_vault.key().as_ref(), client_ata.key().as_ref()],
@@ -58,7 +62,9 @@ pub struct ReedemKamino<'info> {
     )]
     pub client_balance: Box<Account<'info, ClientBalance>>,
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
+    // Kamino collateral mints are always SPL Token mints, whatever the program of the liquidity
+    pub collateral_token_program: Program<'info, Token>,
 
     // Accounts associated with the Kamino program
     // All unchecked accounts are safe to use due to them getting validated inside the Kamino Program
@@ -77,15 +83,16 @@ pub struct ReedemKamino<'info> {
         mut,
         constraint = token.decimals > 0 @ TokenValidatorError::NonFungibleToken
     )]
-    pub reserve_collateral_token: Box<Account<'info, Mint>>,
+    pub reserve_collateral_token: Box<InterfaceAccount<'info, Mint>>,
     #[account(
         mut,
         seeds = [b"fundlock_collateral_vault".as_ref(), fundlock_token_vault.key().as_ref(), reserve_collateral_token.key().as_ref()],
         token::mint = reserve_collateral_token,
         token::authority = fundlock,
+        token::token_program = collateral_token_program,
         bump
     )]
-    pub fundlock_collateral_vault:  Box<Account<'info, TokenAccount>>,
+    pub fundlock_collateral_vault:  Box<InterfaceAccount<'info, TokenAccount>>,
     pub kamino_program: Program<'info, KLend>,
     #[account(address = instructions::ID)]
     /// CHECK: InstructionsSysvar account
@@ -118,7 +125,7 @@ impl<'info> ReedemKamino<'info> {
             reserve_collateral_mint: &self.reserve_collateral_token.to_account_info(),
             user_destination_liquidity: &self.fundlock_token_vault.to_account_info(),
             user_source_collateral: &self.fundlock_collateral_vault.to_account_info(),
-            collateral_token_program: &self.token_program.to_account_info(),
+            collateral_token_program: &self.collateral_token_program.to_account_info(),
             reserve_liquidity_mint: &self.token.to_account_info(),
             liquidity_token_program: &self.token_program.to_account_info(),
             instruction_sysvar_account: &self.instructions.to_account_info(),
@@ -128,18 +135,22 @@ impl<'info> ReedemKamino<'info> {
             collateral_amount: amount,
         };
 
-        redeem_reserve_collateral_invoke_signed(redeem_accounts, redeem_ix_args, fundlock_seeds)
-            .expect("Failed to deposit Kamino into the reserve liquidity supply");
+        redeem_reserve_collateral_invoke_signed(redeem_accounts, redeem_ix_args, fundlock_seeds)?;
 
-        self.fundlock_token_vault.reload().expect("Failed to reload data for fundlock token vault");
+        self.fundlock_token_vault.reload()?;
 
-        let withdraw_liquidity_amount: u64 = self.fundlock_token_vault.amount - fundlock_token_vault_balance_before;
+        let withdraw_liquidity_amount: u64 = math::sub(self.fundlock_token_vault.amount, fundlock_token_vault_balance_before)?;
 
-        self.client_balance.collateral_amount -= amount;
-        self.client_balance.amount += withdraw_liquidity_amount;
+        self.client_balance.collateral_amount = math::sub(self.client_balance.collateral_amount, amount)?;
+        self.client_balance.amount = math::add(self.client_balance.amount, withdraw_liquidity_amount)?;
 
-        msg!("User {} Successfully redeemed {} of {} and received {} of {} into his Fundlock Balance",
-        self.client.key().to_string(), amount, self.reserve_collateral_token.key().to_string(), withdraw_liquidity_amount, self.token.key().to_string());
+        emit!(KaminoRedeemed {
+            fundlock: self.fundlock.key(),
+            client: self.client.key(),
+            token: self.token.key(),
+            collateral_amount: amount,
+            liquidity_amount: withdraw_liquidity_amount,
+        });
         
         Ok(())
     }
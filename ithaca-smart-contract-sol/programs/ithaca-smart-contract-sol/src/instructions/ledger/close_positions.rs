@@ -0,0 +1,156 @@
+use crate::error::{AccessControlError, LedgerError};
+use crate::events::{ContractClosed, PositionClosed};
+use crate::math;
+use crate::state::{AccessController, ClosedContract, Contract, Ledger, Member, Position, Role};
+use crate::{Roles, Subsystem};
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+#[derive(Accounts)]
+pub struct ClosePositions<'info> {
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Ledger) @ AccessControlError::Paused,
+    )]
+    pub access_controller: Account<'info, AccessController>,
+    #[account(
+        seeds = [b"role".as_ref(), access_controller.key().as_ref(), Roles::UtilityAccount.as_str().as_bytes()],
+        bump = role.bump
+    )]
+    pub role: Account<'info, Role>,
+    #[account(
+        seeds = [b"member".as_ref(), access_controller.key().as_ref(), caller.key().as_ref()],
+        bump = member.bump,
+        constraint = member.has_role(Roles::UtilityAccount) @ AccessControlError::NoRole,
+        constraint = !member.is_expired(Roles::UtilityAccount)? @ AccessControlError::RoleExpired
+    )]
+    pub member: Account<'info, Member>,
+    #[account(
+        seeds = [
+            b"ledger".as_ref(),
+            access_controller.key().as_ref(),
+            ledger.token_validator.as_ref(),
+            ledger.underlying_token.as_ref(),
+            ledger.strike_token.as_ref()
+        ],
+        bump = ledger.bump
+    )]
+    pub ledger: Account<'info, Ledger>,
+    /// CHECK: Only receives the rent of the closed accounts, must be the ledger's rent payer
+    #[account(mut, address = ledger.rent_payer)]
+    pub rent_payer: UncheckedAccount<'info>,
+}
+
+impl<'info> ClosePositions<'info> {
+    // Remaining accounts hold every contract followed by the positions to close under it.
+    // Only flat positions can be closed, and a settled or expired contract is closed along
+    // with its last position.
+    pub fn close_positions(
+        &mut self,
+        remaining_accounts: &'info [AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(
+            !remaining_accounts.is_empty(),
+            LedgerError::EmptyPoistionsArray
+        );
+        let ledger_key = self.ledger.key();
+        let now = Clock::get()?.unix_timestamp;
+        let mut current_contract: Option<Account<'info, Contract>> = None;
+
+        for account_info in remaining_accounts {
+            if is_contract(account_info)? {
+                if let Some(contract) = current_contract.take() {
+                    self.finish_contract(contract, now)?;
+                }
+                let contract = Account::<Contract>::try_from(account_info)?;
+                let (expected_contract_pda, _contract_bump) = Pubkey::find_program_address(
+                    &[
+                        b"contract",
+                        ledger_key.as_ref(),
+                        contract.contract_id.to_le_bytes().as_ref(),
+                    ],
+                    &crate::ID,
+                );
+                require!(
+                    contract.key() == expected_contract_pda,
+                    LedgerError::AccountOrderViolated
+                );
+                current_contract = Some(contract);
+                continue;
+            }
+
+            // Positions must follow the contract they belong to
+            let contract = current_contract
+                .as_mut()
+                .ok_or(LedgerError::AccountOrderViolated)?;
+            let position = Account::<Position>::try_from(account_info)?;
+            let (expected_position_pda, _position_bump) = Pubkey::find_program_address(
+                &[
+                    b"position",
+                    contract.key().as_ref(),
+                    position.client.as_ref(),
+                ],
+                &crate::ID,
+            );
+            require!(
+                position.key() == expected_position_pda,
+                LedgerError::AccountOrderViolated
+            );
+            require!(position.size == 0, LedgerError::PositionNotClosable);
+
+            position.close(self.rent_payer.to_account_info())?;
+            contract.position_count = math::sub(contract.position_count, 1)?;
+
+            emit!(PositionClosed {
+                ledger: ledger_key,
+                contract_id: contract.contract_id,
+                client: position.client,
+                rent_payer: self.rent_payer.key(),
+            });
+        }
+
+        if let Some(contract) = current_contract {
+            self.finish_contract(contract, now)?;
+        }
+        Ok(())
+    }
+
+    // Closes a contract without open positions once it is settled or expired, otherwise
+    // persists its position count. The account shrinks to a tombstone rather than being
+    // closed, so that its id can't be used again for a contract with other metadata.
+    fn finish_contract(&self, contract: Account<'info, Contract>, now: i64) -> Result<()> {
+        if contract.position_count > 0 || !(contract.is_settled() || now >= contract.expiry) {
+            return contract.exit(&crate::ID);
+        }
+
+        let contract_id = contract.contract_id;
+        let contract_info = contract.to_account_info();
+        contract_info.realloc(ClosedContract::INIT_SPACE, false)?;
+        ClosedContract { contract_id }
+            .try_serialize(&mut &mut contract_info.try_borrow_mut_data()?[..])?;
+
+        // The freed rent goes to the rent payer
+        let surplus = math::sub(
+            contract_info.lamports(),
+            Rent::get()?.minimum_balance(ClosedContract::INIT_SPACE),
+        )?;
+        **contract_info.try_borrow_mut_lamports()? = math::sub(contract_info.lamports(), surplus)?;
+        **self.rent_payer.try_borrow_mut_lamports()? =
+            math::add(self.rent_payer.lamports(), surplus)?;
+
+        emit!(ContractClosed {
+            ledger: self.ledger.key(),
+            contract_id,
+            rent_payer: self.rent_payer.key(),
+        });
+        Ok(())
+    }
+}
+
+fn is_contract(account_info: &AccountInfo) -> Result<bool> {
+    let data = account_info.try_borrow_data()?;
+    Ok(data.get(..8) == Some(&Contract::DISCRIMINATOR[..]))
+}
@@ -1,9 +1,11 @@
-use crate::error::{AccessControlError, TokenValidatorError};
+use crate::error::{AccessControlError, MathError, TokenValidatorError};
+use crate::events::LedgerInitialized;
+use crate::math;
 use crate::state::access_controller_state::{AccessController, Role};
 use crate::state::fundlock_state::Fundlock;
-use crate::{Ledger, Member, Roles, TokenValidator, WhitelistedToken};
+use crate::{Ledger, Member, Roles, Subsystem, TokenValidator, WhitelistedToken};
 use anchor_lang::prelude::*;
-use anchor_spl::token::Mint;
+use anchor_spl::token_interface::Mint;
 
 #[derive(Accounts)]
 pub struct InitLedger<'info> {
@@ -11,8 +13,9 @@ pub struct InitLedger<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
     #[account(
-        seeds = [b"access_controller".as_ref(), access_controller.admin.as_ref()],
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
         bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Config) @ AccessControlError::Paused,
     )]
     pub access_controller: Account<'info, AccessController>,
     #[account(
@@ -21,8 +24,10 @@ pub struct InitLedger<'info> {
     )]
     pub role: Account<'info, Role>,
     #[account(
-        seeds = [b"member".as_ref(), role.key().as_ref(), admin.key().as_ref()],
-        bump = member.bump
+        seeds = [b"member".as_ref(), access_controller.key().as_ref(), admin.key().as_ref()],
+        bump = member.bump,
+        constraint = member.has_role(Roles::Admin) @ AccessControlError::NoRole,
+        constraint = !member.is_expired(Roles::Admin)? @ AccessControlError::RoleExpired
     )]
     pub member: Account<'info, Member>,
     #[account(
@@ -38,8 +43,10 @@ pub struct InitLedger<'info> {
     #[account(
         constraint = underlying_token.decimals > 0 @ TokenValidatorError::NonFungibleToken
     )]
-    pub underlying_token: Account<'info, Mint>,
+    pub underlying_token: InterfaceAccount<'info, Mint>,
+    // The tokens count their ledgers, as their precisions are fixed once ledgers exist
     #[account(
+        mut,
         seeds = [b"whitelisted_token".as_ref(), token_validator.key().as_ref(), underlying_token.key().as_ref()],
         bump = whitelisted_underlying_token.bump
     )]
@@ -47,8 +54,9 @@ pub struct InitLedger<'info> {
     #[account(
         constraint = strike_token.decimals > 0 @ TokenValidatorError::NonFungibleToken
     )]
-    pub strike_token: Account<'info, Mint>,
+    pub strike_token: InterfaceAccount<'info, Mint>,
     #[account(
+        mut,
         seeds = [b"whitelisted_token".as_ref(), token_validator.key().as_ref(), strike_token.key().as_ref()],
         bump = whitelisted_strike_token.bump
     )]
@@ -69,18 +77,38 @@ pub struct InitLedger<'info> {
 }
 
 impl<'info> InitLedger<'info> {
-    pub fn init_ledger(&mut self, bumps: &InitLedgerBumps) -> Result<()> {
+    pub fn init_ledger(
+        &mut self,
+        rent_payer: Pubkey,
+        insurance_fund: Pubkey,
+        bumps: &InitLedgerBumps,
+    ) -> Result<()> {
         require!(
-            self.role.role == Roles::Admin.as_str(),
+            self.role.role == Roles::Admin,
             AccessControlError::UnauthorizedAdmin
         );
-        let strike_token_diff: u8 =
-            self.strike_token.decimals - self.whitelisted_strike_token.token_precision;
-        let underlying_token_diff: u8 =
-            self.underlying_token.decimals - self.whitelisted_underlying_token.token_precision;
+        let strike_token_diff: u8 = self
+            .strike_token
+            .decimals
+            .checked_sub(self.whitelisted_strike_token.token_precision)
+            .ok_or(MathError::MathOverflow)?;
+        let underlying_token_diff: u8 = self
+            .underlying_token
+            .decimals
+            .checked_sub(self.whitelisted_underlying_token.token_precision)
+            .ok_or(MathError::MathOverflow)?;
+
+        let underlying_multiplier = 10_i64
+            .checked_pow(underlying_token_diff as u32)
+            .ok_or(MathError::MathOverflow)?;
+        let strike_multiplier = 10_i64
+            .checked_pow(strike_token_diff as u32)
+            .ok_or(MathError::MathOverflow)?;
+        self.whitelisted_underlying_token.ledger_count =
+            math::add(self.whitelisted_underlying_token.ledger_count, 1)?;
+        self.whitelisted_strike_token.ledger_count =
+            math::add(self.whitelisted_strike_token.ledger_count, 1)?;
 
-        let underlying_multiplier = 10_i64.pow(underlying_token_diff as u32);
-        let strike_multiplier = 10_i64.pow(strike_token_diff as u32);
         self.ledger.set_inner(Ledger {
             access_controller: self.access_controller.key(),
             token_validator: self.token_validator.key(),
@@ -89,11 +117,22 @@ impl<'info> InitLedger<'info> {
             strike_token: self.strike_token.key(),
             underlying_multiplier,
             strike_multiplier,
+            last_backend_id: 0,
+            recent_backend_ids: Vec::new(),
+            rent_payer,
+            insurance_fund,
             bump: bumps.ledger,
         });
 
-        msg!("Ledger initialized successfully at {}", self.ledger.key());
-        msg!("Underlying Token: {}, Strike token: {}", self.underlying_token.key(), self.strike_token.key());
+        emit!(LedgerInitialized {
+            ledger: self.ledger.key(),
+            underlying_token: self.underlying_token.key(),
+            strike_token: self.strike_token.key(),
+            underlying_multiplier,
+            strike_multiplier,
+            rent_payer,
+            insurance_fund,
+        });
 
         Ok(())
     }
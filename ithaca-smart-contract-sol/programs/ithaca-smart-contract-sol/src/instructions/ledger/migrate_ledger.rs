@@ -0,0 +1,68 @@
+use crate::error::{AccessControlError, LedgerError};
+use crate::events::LedgerMigrated;
+use crate::migration;
+use crate::state::access_controller_state::AccessController;
+use crate::state::ledger_state::{LegacyLedger, Ledger};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct MigrateLedger<'info> {
+    // Legacy accounts can be migrated by the main access controller admin only
+    #[account(mut,
+        constraint = admin.key() == access_controller.admin @ AccessControlError::UnauthorizedAdmin)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+    )]
+    pub access_controller: Account<'info, AccessController>,
+    /// CHECK: Ledger still in its legacy layout, checked by the handler against the access controller
+    #[account(mut, owner = crate::ID)]
+    pub ledger: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateLedger<'info> {
+    // Rewrites the ledger with the current layout, paid by the admin. No backend id is
+    // remembered yet, replays are rejected from the first batch processed after the migration.
+    pub fn migrate_ledger(&mut self, rent_payer: Pubkey, insurance_fund: Pubkey) -> Result<()> {
+        let legacy_ledger: LegacyLedger =
+            migration::load_legacy::<Ledger, _>(&self.ledger, LegacyLedger::SPACE)?
+                .ok_or(LedgerError::AlreadyMigrated)?;
+        require_keys_eq!(
+            legacy_ledger.access_controller,
+            self.access_controller.key(),
+            LedgerError::AlreadyMigrated
+        );
+
+        let ledger = Ledger {
+            access_controller: legacy_ledger.access_controller,
+            token_validator: legacy_ledger.token_validator,
+            fundlock: legacy_ledger.fundlock,
+            underlying_token: legacy_ledger.underlying_token,
+            strike_token: legacy_ledger.strike_token,
+            underlying_multiplier: legacy_ledger.underlying_multiplier,
+            strike_multiplier: legacy_ledger.strike_multiplier,
+            last_backend_id: 0,
+            recent_backend_ids: Vec::new(),
+            rent_payer,
+            insurance_fund,
+            bump: legacy_ledger.bump,
+        };
+        migration::store_migrated(
+            &self.ledger,
+            &ledger,
+            Ledger::INIT_SPACE,
+            &self.admin,
+            &self.system_program,
+        )?;
+
+        emit!(LedgerMigrated {
+            ledger: self.ledger.key(),
+            rent_payer,
+            insurance_fund,
+        });
+
+        Ok(())
+    }
+}
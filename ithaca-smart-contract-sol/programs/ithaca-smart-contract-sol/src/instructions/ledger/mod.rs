@@ -2,4 +2,13 @@ pub mod init_ledger;
 pub use init_ledger::*;
 
 pub mod update_fund_movements;
-pub use update_fund_movements::*;
\ No newline at end of file
+pub use update_fund_movements::*;
+
+pub mod settle_contract;
+pub use settle_contract::*;
+
+pub mod close_positions;
+pub use close_positions::*;
+
+pub mod migrate_ledger;
+pub use migrate_ledger::*;
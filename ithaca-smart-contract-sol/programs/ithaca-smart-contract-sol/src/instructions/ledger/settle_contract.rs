@@ -0,0 +1,73 @@
+use crate::error::{AccessControlError, LedgerError};
+use crate::events::ContractSettled;
+use crate::state::{AccessController, Contract, ContractStatus, Ledger, Member, Role};
+use crate::{Roles, Subsystem};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+#[instruction(contract_id: u64)]
+pub struct SettleContract<'info> {
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Ledger) @ AccessControlError::Paused,
+    )]
+    pub access_controller: Account<'info, AccessController>,
+    // Settlement prices are submitted by the backend through the utility account role
+    #[account(
+        seeds = [b"role".as_ref(), access_controller.key().as_ref(), Roles::UtilityAccount.as_str().as_bytes()],
+        bump = role.bump
+    )]
+    pub role: Account<'info, Role>,
+    #[account(
+        seeds = [b"member".as_ref(), access_controller.key().as_ref(), caller.key().as_ref()],
+        bump = member.bump,
+        constraint = member.has_role(Roles::UtilityAccount) @ AccessControlError::NoRole,
+        constraint = !member.is_expired(Roles::UtilityAccount)? @ AccessControlError::RoleExpired
+    )]
+    pub member: Account<'info, Member>,
+    #[account(
+        seeds = [
+            b"ledger".as_ref(),
+            access_controller.key().as_ref(),
+            ledger.token_validator.as_ref(),
+            ledger.underlying_token.as_ref(),
+            ledger.strike_token.as_ref()
+        ],
+        bump = ledger.bump
+    )]
+    pub ledger: Account<'info, Ledger>,
+    #[account(
+        mut,
+        seeds = [b"contract".as_ref(), ledger.key().as_ref(), contract_id.to_le_bytes().as_ref()],
+        bump = contract.bump
+    )]
+    pub contract: Account<'info, Contract>,
+}
+
+impl<'info> SettleContract<'info> {
+    // Records the settlement price of an expired contract and moves it to its terminal state
+    pub fn settle_contract(&mut self, contract_id: u64, settlement_price: u64) -> Result<()> {
+        require!(
+            self.contract.status == ContractStatus::Active,
+            LedgerError::ContractSettled
+        );
+        require!(
+            Clock::get()?.unix_timestamp >= self.contract.expiry,
+            LedgerError::ContractNotExpired
+        );
+
+        self.contract.status = ContractStatus::Settled;
+        self.contract.settlement_price = settlement_price;
+
+        emit!(ContractSettled {
+            ledger: self.ledger.key(),
+            contract_id,
+            settlement_price,
+        });
+
+        Ok(())
+    }
+}
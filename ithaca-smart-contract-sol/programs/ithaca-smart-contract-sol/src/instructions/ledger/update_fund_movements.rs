@@ -1,17 +1,20 @@
 use std::cell::RefMut;
 use anchor_lang::prelude::*;
-use crate::error::{FundlockError, LedgerError, TokenValidatorError};
+use crate::error::{AccessControlError, FundlockError, LedgerError, TokenValidatorError};
+use crate::events::FundMovementApplied;
+use crate::math;
 use crate::state::{AccessController, Fundlock, Ledger, Member, FundMovementParam, TokenValidator, Role};
-use crate::{ClientBalance, Roles, WhitelistedToken, Withdrawals};
-use anchor_spl::token::Mint;
+use crate::{ClientBalance, Roles, Subsystem, WhitelistedToken, Withdrawals};
+use anchor_spl::token_interface::Mint;
 
 #[derive(Accounts)]
 pub struct UpdateFundMovements<'info> {
     #[account(mut)]
     pub caller: Signer<'info>,
     #[account(
-        seeds = [b"access_controller".as_ref(), access_controller.admin.as_ref()],
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
         bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Ledger) @ AccessControlError::Paused,
     )]
     pub access_controller: Account<'info, AccessController>,
     #[account(
@@ -20,8 +23,10 @@ pub struct UpdateFundMovements<'info> {
     )]
     pub role: Account<'info, Role>,
     #[account(
-        seeds = [b"member".as_ref(), role.key().as_ref(), caller.key().as_ref()], 
-        bump = member.bump
+        seeds = [b"member".as_ref(), access_controller.key().as_ref(), caller.key().as_ref()],
+        bump = member.bump,
+        constraint = member.has_role(Roles::UtilityAccount) @ AccessControlError::NoRole,
+        constraint = !member.is_expired(Roles::UtilityAccount)? @ AccessControlError::RoleExpired
     )]
     pub member: Account<'info, Member>,
     #[account(
@@ -37,22 +42,25 @@ pub struct UpdateFundMovements<'info> {
     #[account(
         constraint = underlying_token.decimals > 0 @ TokenValidatorError::NonFungibleToken
     )]
-    pub underlying_token: Account<'info, Mint>,
+    pub underlying_token: InterfaceAccount<'info, Mint>,
     #[account(
         seeds = [b"whitelisted_token".as_ref(), token_validator.key().as_ref(), underlying_token.key().as_ref()],
-        bump = whitelisted_underlying_token.bump
+        bump = whitelisted_underlying_token.bump,
+        constraint = whitelisted_underlying_token.is_tradable() @ TokenValidatorError::TokenWithdrawOnly
     )]
     pub whitelisted_underlying_token: Account<'info, WhitelistedToken>,
     #[account(
         constraint = strike_token.decimals > 0 @ TokenValidatorError::NonFungibleToken
     )]
-    pub strike_token: Account<'info, Mint>,
+    pub strike_token: InterfaceAccount<'info, Mint>,
     #[account(
         seeds = [b"whitelisted_token".as_ref(), token_validator.key().as_ref(), strike_token.key().as_ref()],
-        bump = whitelisted_strike_token.bump
+        bump = whitelisted_strike_token.bump,
+        constraint = whitelisted_strike_token.is_tradable() @ TokenValidatorError::TokenWithdrawOnly
     )]
     pub whitelisted_strike_token: Account<'info, WhitelistedToken>,
     #[account(
+        mut,
         seeds = [b"ledger".as_ref(), 
         access_controller.key().as_ref(), 
         token_validator.key().as_ref(), 
@@ -75,6 +83,7 @@ impl<'info> UpdateFundMovements<'info> {
         backend_id: u64,
     ) -> Result<()> {
         require!(!fund_movements.is_empty(), LedgerError::EmptyFundMovementArray);
+        self.ledger.record_backend_id(backend_id)?;
         let transfer_count = self.validate_and_count_amounts(&fund_movements);
         self.process_fund_movement(
             fund_movements, 
@@ -85,7 +94,6 @@ impl<'info> UpdateFundMovements<'info> {
             client_balance_strike_datas,
             client_strike_withdrawal_datas
         )?;
-        msg!("Fund movements updated successfully, backend id {}", backend_id);
         Ok(())
     }
 
@@ -117,9 +125,6 @@ impl<'info> UpdateFundMovements<'info> {
         client_strike_withdrawal_datas: Vec<RefMut<'_, &mut [u8]>>,
     ) -> Result<()> {
         let (clients, tokens, amounts) = self.initialize_data(fund_movements, transfer_count)?;
-        msg!("Clients: {:?}", clients);
-        msg!("Tokens: {:?}", tokens);
-        msg!("Amounts: {:?}", amounts);
         self.update_balances_fundlock(
             amounts, 
             clients, 
@@ -129,7 +134,7 @@ impl<'info> UpdateFundMovements<'info> {
             client_balance_strike_datas,
             client_strike_withdrawal_datas, 
             backend_id
-        ).expect("Failed to update balances");
+        )?;
         Ok(())
     }
 
@@ -149,12 +154,18 @@ impl<'info> UpdateFundMovements<'info> {
             if fund_movement.underlying_amount != 0 {
                 clients.push(fund_movement.client);
                 tokens.push(self.ledger.underlying_token);
-                amounts.push(-fund_movement.underlying_amount * underlying_multiplier);
+                amounts.push(math::mul_signed(
+                    math::neg(fund_movement.underlying_amount)?,
+                    underlying_multiplier,
+                )?);
             }
             if fund_movement.strike_amount != 0 {
                 clients.push(fund_movement.client);
                 tokens.push(self.ledger.strike_token);
-                amounts.push(-fund_movement.strike_amount * strike_multiplier);
+                amounts.push(math::mul_signed(
+                    math::neg(fund_movement.strike_amount)?,
+                    strike_multiplier,
+                )?);
             }
         }
         Ok((clients, tokens, amounts))
@@ -196,31 +207,40 @@ impl<'info> UpdateFundMovements<'info> {
                 );
 
                 let _change_in_underlying_balance: i64;
-                if amounts[i] > 0 || client_balance_underlying.amount >= amounts[i * 2].abs() as u64 {
+                let mut underlying_funded_from_withdrawals: u64 = 0;
+                let amount_to_deduct = amounts[i * 2].unsigned_abs();
+                if amounts[i * 2] > 0 || client_balance_underlying.amount >= amount_to_deduct {
                     _change_in_underlying_balance = amounts[i * 2];
-                    msg!("Funding from client balance: client={}, amount={}, token={}", clients[i * 2], amounts[i * 2], tokens[i * 2]);
-
                 } else {
-                    let amount_to_deduct = amounts[i * 2].abs() as u64;
-                    _change_in_underlying_balance = -(client_balance_underlying.amount as i64);
-                    let shortage = amount_to_deduct - client_balance_underlying.amount;
+                    _change_in_underlying_balance = math::neg(math::to_signed(client_balance_underlying.amount)?)?;
+                    let shortage = math::sub(amount_to_deduct, client_balance_underlying.amount)?;
                     require!(
                         self.fund_from_withdrawal(
                             clients[i * 2],
                             tokens[i * 2],
                             shortage,
                             withdrawals_data
-                        ),
+                        )?,
                         FundlockError::InsufficientFunds
                     );
-                    msg!("Client {} underlying withdrawal active amount after: {}", clients[i * 2], withdrawals_underlying_account_info.active_withdrawals_amount);
+                    underlying_funded_from_withdrawals = shortage;
                 }
-                client_balance_underlying.amount = (client_balance_underlying.amount as i64 + _change_in_underlying_balance) as u64;
+                client_balance_underlying.amount = math::apply_change(client_balance_underlying.amount, _change_in_underlying_balance)?;
+                // Serializing through a fresh slice, writing into the borrowed one would advance
+                // the account's data reference past the written bytes
                 client_balance_underlying
-                    .try_serialize(&mut **client_balance_underlying_data)
+                    .try_serialize(&mut &mut client_balance_underlying_data[..])
                     .expect("Error Serializing Client Balance");
-                msg!("Client {} underlying balance: {}", clients[i * 2], client_balance_underlying.amount);
 
+                emit!(FundMovementApplied {
+                    ledger: self.ledger.key(),
+                    client: clients[i * 2],
+                    token: tokens[i * 2],
+                    amount: _change_in_underlying_balance,
+                    balance: client_balance_underlying.amount,
+                    funded_from_withdrawals: underlying_funded_from_withdrawals,
+                    backend_id,
+                });
             }
 
             let client_balance_strike_data = &mut client_balance_strike_datas[i];
@@ -246,31 +266,39 @@ impl<'info> UpdateFundMovements<'info> {
             );
 
             let _change_in_strike_balance: i64;
-            if amounts[i *2 +1] > 0 || client_balance_strike.amount >= amounts[i * 2 +1].abs() as u64 {
+            let mut strike_funded_from_withdrawals: u64 = 0;
+            let amount_to_deduct = amounts[i * 2 + 1].unsigned_abs();
+            if amounts[i *2 +1] > 0 || client_balance_strike.amount >= amount_to_deduct {
                 _change_in_strike_balance = amounts[i * 2 +1];
-                msg!("Funding from client balance: client={}, amount={}, token={}", clients[i * 2 + 1], amounts[i* 2 + 1], tokens[i * 2 + 1]);
             } else {
-                let amount_to_deduct = amounts[i * 2 + 1].abs() as u64;
-                _change_in_strike_balance = -(client_balance_strike.amount as i64);
-                let shortage = amount_to_deduct - client_balance_strike.amount;
+                _change_in_strike_balance = math::neg(math::to_signed(client_balance_strike.amount)?)?;
+                let shortage = math::sub(amount_to_deduct, client_balance_strike.amount)?;
                 require!(
                     self.fund_from_withdrawal(
                         clients[i * 2+1],
                         tokens[i*2+1],
                         shortage,
                         withdrawals_data
-                    ),
+                    )?,
                     FundlockError::InsufficientFunds
                 );
-                msg!("Client {} strike withdrawal active amount after: {}", clients[i * 2 + 1], withdrawals_strike_account_info.active_withdrawals_amount);
+                strike_funded_from_withdrawals = shortage;
             }
-            client_balance_strike.amount = (client_balance_strike.amount as i64 + _change_in_strike_balance) as u64;
+            client_balance_strike.amount = math::apply_change(client_balance_strike.amount, _change_in_strike_balance)?;
             client_balance_strike
-                .try_serialize(&mut **client_balance_strike_data)
+                .try_serialize(&mut &mut client_balance_strike_data[..])
                 .expect("Error Serializing Client Balance");
-            msg!("Client {} new strike balance: {}", clients[i * 2 + 1], client_balance_strike.amount);
+
+            emit!(FundMovementApplied {
+                ledger: self.ledger.key(),
+                client: clients[i * 2 + 1],
+                token: tokens[i * 2 + 1],
+                amount: _change_in_strike_balance,
+                balance: client_balance_strike.amount,
+                funded_from_withdrawals: strike_funded_from_withdrawals,
+                backend_id,
+            });
         }
-        msg!("Balances updated successfully! Backend ID: {}", backend_id);
         Ok(())
     }
 
@@ -280,7 +308,7 @@ impl<'info> UpdateFundMovements<'info> {
         token: Pubkey,
         amount: u64,
         withdrawal_data: &mut RefMut<'_, &mut [u8]>,
-    ) -> bool {
+    ) -> Result<bool> {
         let mut funded_sum: u64 = 0;
         let trade_lock = self.fundlock.trade_lock;
         let mut withdrawal_account_info =
@@ -294,15 +322,18 @@ impl<'info> UpdateFundMovements<'info> {
 
         for index in 0..withdrawals.len() {
             if let Some(withdrawal) = withdrawals.get_mut(index) {
-                if withdrawal.timestamp + trade_lock > Clock::get().unwrap().unix_timestamp {
-                    let left_to_fund = amount - funded_sum;
+                if math::add_signed(withdrawal.timestamp, trade_lock)? > Clock::get()?.unix_timestamp {
+                    let left_to_fund = math::sub(amount, funded_sum)?;
                     let available_amount = withdrawal.amount;
 
                     if available_amount <= left_to_fund {
-                        funded_sum += available_amount;
+                        funded_sum = math::add(funded_sum, available_amount)?;
                         withdrawal.amount = 0;
                         withdrawal.timestamp = 0;
-                        withdrawal_account_info.active_withdrawals_amount -= available_amount;
+                        withdrawal_account_info.active_withdrawals_amount = math::sub(
+                            withdrawal_account_info.active_withdrawals_amount,
+                            available_amount,
+                        )?;
                         withdrawals.remove(index);
                         msg!(
                             "Funded from withdrawal: client={}, token={}, amount={}, index={}",
@@ -312,9 +343,12 @@ impl<'info> UpdateFundMovements<'info> {
                             index
                         );
                     } else {
-                        funded_sum += left_to_fund;
-                        withdrawal.amount -= left_to_fund;
-                        withdrawal_account_info.active_withdrawals_amount -= left_to_fund;
+                        funded_sum = math::add(funded_sum, left_to_fund)?;
+                        withdrawal.amount = math::sub(withdrawal.amount, left_to_fund)?;
+                        withdrawal_account_info.active_withdrawals_amount = math::sub(
+                            withdrawal_account_info.active_withdrawals_amount,
+                            left_to_fund,
+                        )?;
                         msg!(
                             "Funded from withdrawal: client={}, token={}, amount={}, index={}",
                             client,
@@ -326,9 +360,9 @@ impl<'info> UpdateFundMovements<'info> {
 
                     if funded_sum == amount {
                         withdrawal_account_info
-                            .try_serialize(&mut **withdrawal_data)
+                            .try_serialize(&mut &mut withdrawal_data[..])
                             .expect("Error Serializing Withdrawals");
-                        return true;
+                        return Ok(true);
                     }
                 }
             }
@@ -338,9 +372,9 @@ impl<'info> UpdateFundMovements<'info> {
         *withdrawals = original_withdrawals;
         withdrawal_account_info.active_withdrawals_amount = original_active_withdrawals_amount;
         withdrawal_account_info
-            .try_serialize(&mut **withdrawal_data)
+            .try_serialize(&mut &mut withdrawal_data[..])
             .expect("Error Serializing Withdrawals");
 
-        false
+        Ok(false)
     }
 }
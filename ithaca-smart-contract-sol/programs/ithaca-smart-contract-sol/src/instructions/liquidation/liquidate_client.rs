@@ -0,0 +1,317 @@
+use crate::error::{
+    AccessControlError, FundlockError, LedgerError, LiquidationError, TokenValidatorError,
+};
+use crate::events::{ClientLiquidated, PositionUpdated};
+use crate::math;
+use crate::state::{
+    AccessController, ClientBalance, Contract, Fundlock, Ledger, Member, Position, Role,
+    TokenValidator, WhitelistedToken,
+};
+use crate::{Roles, Subsystem};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+#[derive(Accounts)]
+#[instruction(client: Pubkey)]
+// Boxing all the account to avoid stack overflow
+pub struct LiquidateClient<'info> {
+    #[account(mut)]
+    pub liquidator: Signer<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Ledger) @ AccessControlError::Paused,
+    )]
+    pub access_controller: Box<Account<'info, AccessController>>,
+    // Liquidations can be performed by the liquidator role members only
+    #[account(
+        seeds = [b"role".as_ref(), access_controller.key().as_ref(), Roles::Liquidator.as_str().as_bytes()],
+        bump = role_liquidator.bump
+    )]
+    pub role_liquidator: Box<Account<'info, Role>>,
+    #[account(
+        seeds = [b"member".as_ref(), access_controller.key().as_ref(), liquidator.key().as_ref()],
+        bump = member_liquidator.bump,
+        constraint = member_liquidator.has_role(Roles::Liquidator) @ AccessControlError::NoRole,
+        constraint = !member_liquidator.is_expired(Roles::Liquidator)? @ AccessControlError::RoleExpired
+    )]
+    pub member_liquidator: Box<Account<'info, Member>>,
+    #[account(
+        seeds = [b"token_validator".as_ref(), access_controller.key().as_ref()],
+        bump = token_validator.bump
+    )]
+    pub token_validator: Box<Account<'info, TokenValidator>>,
+    #[account(
+        seeds = [b"fundlock".as_ref(), access_controller.key().as_ref(), token_validator.key().as_ref()],
+        bump = fundlock.bump
+    )]
+    pub fundlock: Box<Account<'info, Fundlock>>,
+    #[account(
+        constraint = underlying_token.decimals > 0 @ TokenValidatorError::NonFungibleToken
+    )]
+    pub underlying_token: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        seeds = [b"whitelisted_token".as_ref(), token_validator.key().as_ref(), underlying_token.key().as_ref()],
+        bump = whitelisted_underlying_token.bump
+    )]
+    pub whitelisted_underlying_token: Box<Account<'info, WhitelistedToken>>,
+    #[account(
+        constraint = strike_token.decimals > 0 @ TokenValidatorError::NonFungibleToken
+    )]
+    pub strike_token: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        seeds = [b"whitelisted_token".as_ref(), token_validator.key().as_ref(), strike_token.key().as_ref()],
+        bump = whitelisted_strike_token.bump
+    )]
+    pub whitelisted_strike_token: Box<Account<'info, WhitelistedToken>>,
+    #[account(
+        mut,
+        seeds = [
+            b"ledger".as_ref(),
+            access_controller.key().as_ref(),
+            token_validator.key().as_ref(),
+            underlying_token.key().as_ref(),
+            strike_token.key().as_ref()
+        ],
+        bump = ledger.bump
+    )]
+    pub ledger: Box<Account<'info, Ledger>>,
+    #[account(
+        seeds = [b"fundlock_token_vault".as_ref(), fundlock.key().as_ref(), underlying_token.key().as_ref()],
+        token::mint = underlying_token,
+        token::authority = fundlock,
+        bump,
+    )]
+    pub fundlock_underlying_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"fundlock_token_vault".as_ref(), fundlock.key().as_ref(), strike_token.key().as_ref()],
+        token::mint = strike_token,
+        token::authority = fundlock,
+        bump,
+    )]
+    pub fundlock_strike_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    // Balances of the liquidated client, funds are seized from these accounts
+    #[account(
+        mut,
+        seeds = [b"client_balance".as_ref(), fundlock_underlying_vault.key().as_ref(), client_underlying_balance.client_ata.as_ref()],
+        bump = client_underlying_balance.bump,
+        constraint = client_underlying_balance.client == client @ LiquidationError::ClientMismatch
+    )]
+    pub client_underlying_balance: Box<Account<'info, ClientBalance>>,
+    #[account(
+        mut,
+        seeds = [b"client_balance".as_ref(), fundlock_strike_vault.key().as_ref(), client_strike_balance.client_ata.as_ref()],
+        bump = client_strike_balance.bump,
+        constraint = client_strike_balance.client == client @ LiquidationError::ClientMismatch
+    )]
+    pub client_strike_balance: Box<Account<'info, ClientBalance>>,
+    // Balances of the ledger's insurance fund, seized funds are credited to these accounts so
+    // that the liquidator has nothing to gain from the amounts it picks
+    #[account(
+        mut,
+        seeds = [b"client_balance".as_ref(), fundlock_underlying_vault.key().as_ref(), insurance_underlying_balance.client_ata.as_ref()],
+        bump = insurance_underlying_balance.bump,
+        constraint = insurance_underlying_balance.client == ledger.insurance_fund @ LiquidationError::InsuranceFundMismatch
+    )]
+    pub insurance_underlying_balance: Box<Account<'info, ClientBalance>>,
+    #[account(
+        mut,
+        seeds = [b"client_balance".as_ref(), fundlock_strike_vault.key().as_ref(), insurance_strike_balance.client_ata.as_ref()],
+        bump = insurance_strike_balance.bump,
+        constraint = insurance_strike_balance.client == ledger.insurance_fund @ LiquidationError::InsuranceFundMismatch
+    )]
+    pub insurance_strike_balance: Box<Account<'info, ClientBalance>>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> LiquidateClient<'info> {
+    // Closes out the client's positions passed as remaining accounts in (contract, position,
+    // insurance fund position) triples and moves the seized underlying and strike amounts to the
+    // insurance fund's balances. Nothing here checks that the client is under-collateralised:
+    // margin is computed off-chain, and the liquidator, a backend key, attests it with the
+    // backend id recorded by the ledger and emitted with the liquidation.
+    pub fn liquidate_client(
+        &mut self,
+        remaining_accounts: &'info [AccountInfo<'info>],
+        client: Pubkey,
+        underlying_amount: u64,
+        strike_amount: u64,
+        backend_id: u64,
+    ) -> Result<()> {
+        require!(
+            client != self.liquidator.key(),
+            LiquidationError::SelfLiquidation
+        );
+        require!(
+            client != self.ledger.insurance_fund,
+            LiquidationError::InsuranceFundLiquidation
+        );
+        require!(
+            remaining_accounts.chunks_exact(3).remainder().is_empty(),
+            LedgerError::AccountOrderViolated
+        );
+        require!(
+            !remaining_accounts.is_empty() || underlying_amount > 0 || strike_amount > 0,
+            LiquidationError::NothingToLiquidate
+        );
+        self.ledger.record_backend_id(backend_id)?;
+
+        self.close_out_positions(remaining_accounts, client, backend_id)?;
+
+        require!(
+            self.client_underlying_balance.amount >= underlying_amount
+                && self.client_strike_balance.amount >= strike_amount,
+            FundlockError::InsufficientFunds
+        );
+
+        self.client_underlying_balance.amount =
+            math::sub(self.client_underlying_balance.amount, underlying_amount)?;
+        self.insurance_underlying_balance.amount =
+            math::add(self.insurance_underlying_balance.amount, underlying_amount)?;
+        self.client_strike_balance.amount =
+            math::sub(self.client_strike_balance.amount, strike_amount)?;
+        self.insurance_strike_balance.amount =
+            math::add(self.insurance_strike_balance.amount, strike_amount)?;
+
+        emit!(ClientLiquidated {
+            ledger: self.ledger.key(),
+            client,
+            liquidator: self.liquidator.key(),
+            insurance_fund: self.ledger.insurance_fund,
+            underlying_amount,
+            strike_amount,
+            backend_id,
+        });
+
+        Ok(())
+    }
+
+    // The closed out sizes are taken over by the insurance fund, so the sizes on every contract
+    // still net to zero. Its positions are created at the liquidator's expense when missing.
+    fn close_out_positions(
+        &self,
+        remaining_accounts: &'info [AccountInfo<'info>],
+        client: Pubkey,
+        backend_id: u64,
+    ) -> Result<()> {
+        let ledger_key = self.ledger.key();
+        let insurance_fund = self.ledger.insurance_fund;
+
+        for accounts in remaining_accounts.chunks_exact(3) {
+            let mut contract = Account::<Contract>::try_from(&accounts[0])?;
+
+            let (expected_contract_pda, _contract_bump) = Pubkey::find_program_address(
+                &[
+                    b"contract",
+                    ledger_key.as_ref(),
+                    contract.contract_id.to_le_bytes().as_ref(),
+                ],
+                &crate::ID,
+            );
+            let (expected_position_pda, _position_bump) = Pubkey::find_program_address(
+                &[b"position", contract.key().as_ref(), client.as_ref()],
+                &crate::ID,
+            );
+            let (expected_insurance_pda, insurance_bump) = Pubkey::find_program_address(
+                &[
+                    b"position",
+                    contract.key().as_ref(),
+                    insurance_fund.as_ref(),
+                ],
+                &crate::ID,
+            );
+
+            // Ensure the passed accounts match the expected PDAs
+            require!(
+                contract.key() == expected_contract_pda,
+                LedgerError::AccountOrderViolated
+            );
+            require!(
+                accounts[1].key() == expected_position_pda,
+                LedgerError::AccountOrderViolated
+            );
+            require!(
+                accounts[2].key() == expected_insurance_pda,
+                LedgerError::AccountOrderViolated
+            );
+
+            let mut position = Account::<Position>::try_from(&accounts[1])?;
+            require!(
+                position.client == client && position.contract_id == contract.contract_id,
+                LiquidationError::ClientMismatch
+            );
+
+            let mut insurance_position = if accounts[2].data_is_empty() {
+                self.create_position(&accounts[2], &contract.key(), insurance_bump)?;
+                contract.position_count = math::add(contract.position_count, 1)?;
+                Position {
+                    contract_id: contract.contract_id,
+                    client: insurance_fund,
+                    size: 0,
+                    bump: insurance_bump,
+                }
+            } else {
+                Position::try_deserialize(&mut &accounts[2].try_borrow_data()?[..])?
+            };
+
+            let size_change = math::neg(position.size)?;
+            let insurance_size = math::add_signed(insurance_position.size, position.size)?;
+            contract.update_open_interest(position.size, 0)?;
+            contract.update_open_interest(insurance_position.size, insurance_size)?;
+            contract.exit(&crate::ID)?;
+            position.size = 0;
+            position.exit(&crate::ID)?;
+            insurance_position.size = insurance_size;
+            insurance_position.try_serialize(&mut &mut accounts[2].try_borrow_mut_data()?[..])?;
+
+            emit!(PositionUpdated {
+                ledger: ledger_key,
+                contract_id: position.contract_id,
+                client,
+                size: position.size,
+                size_change,
+                backend_id,
+            });
+            emit!(PositionUpdated {
+                ledger: ledger_key,
+                contract_id: position.contract_id,
+                client: insurance_fund,
+                size: insurance_size,
+                size_change: math::neg(size_change)?,
+                backend_id,
+            });
+        }
+        Ok(())
+    }
+
+    fn create_position(
+        &self,
+        position: &AccountInfo<'info>,
+        contract: &Pubkey,
+        bump: u8,
+    ) -> Result<()> {
+        let init_space = Position::INIT_SPACE;
+        let lamports = Rent::get()?.minimum_balance(init_space);
+        let insurance_fund = self.ledger.insurance_fund;
+        let signer = [
+            b"position".as_ref(),
+            contract.as_ref(),
+            insurance_fund.as_ref(),
+            &[bump],
+        ];
+
+        solana_program::program::invoke_signed(
+            &solana_program::system_instruction::create_account(
+                &self.liquidator.key(),
+                position.key,
+                lamports,
+                init_space as u64,
+                &crate::ID,
+            ),
+            &[self.liquidator.to_account_info(), position.clone()],
+            &[&signer],
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,2 @@
+pub mod liquidate_client;
+pub use liquidate_client::*;
@@ -12,3 +12,12 @@ pub use ledger::*;
 
 pub mod kamino_lend_staking;
 pub use kamino_lend_staking::*;
+
+pub mod liquidation;
+pub use liquidation::*;
+
+pub mod multisig;
+pub use multisig::*;
+
+pub mod governance;
+pub use governance::*;
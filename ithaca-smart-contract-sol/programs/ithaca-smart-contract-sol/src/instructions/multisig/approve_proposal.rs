@@ -0,0 +1,43 @@
+use crate::error::MultisigError;
+use crate::events::ProposalApproved;
+use crate::state::{Multisig, Proposal};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct ApproveProposal<'info> {
+    pub signer: Signer<'info>,
+    #[account(
+        seeds = [b"multisig".as_ref(), multisig.access_controller.as_ref()],
+        bump = multisig.bump,
+        constraint = multisig.is_signer(&signer.key()) @ MultisigError::NotASigner
+    )]
+    pub multisig: Account<'info, Multisig>,
+    #[account(
+        mut,
+        seeds = [b"proposal".as_ref(), multisig.key().as_ref(), proposal.proposal_id.to_le_bytes().as_ref()],
+        bump = proposal.bump,
+        constraint = !proposal.executed @ MultisigError::ProposalExecuted,
+        constraint = proposal.signers_version == multisig.signers_version @ MultisigError::StaleProposal
+    )]
+    pub proposal: Account<'info, Proposal>,
+}
+
+impl<'info> ApproveProposal<'info> {
+    pub fn approve_proposal(&mut self) -> Result<()> {
+        let signer = self.signer.key();
+        require!(
+            !self.proposal.approvals.contains(&signer),
+            MultisigError::AlreadyApproved
+        );
+        self.proposal.approvals.push(signer);
+
+        emit!(ProposalApproved {
+            multisig: self.multisig.key(),
+            proposal_id: self.proposal.proposal_id,
+            signer,
+            approvals: self.proposal.approvals.len() as u8,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use crate::error::MultisigError;
+use crate::events::ProposalCreated;
+use crate::math;
+use crate::state::{Multisig, Proposal, ProposalAccount};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+#[instruction(accounts: Vec<ProposalAccount>, data: Vec<u8>)]
+pub struct CreateProposal<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"multisig".as_ref(), multisig.access_controller.as_ref()],
+        bump = multisig.bump,
+        constraint = multisig.is_signer(&proposer.key()) @ MultisigError::NotASigner
+    )]
+    pub multisig: Account<'info, Multisig>,
+    #[account(
+        init,
+        payer = proposer,
+        seeds = [b"proposal".as_ref(), multisig.key().as_ref(), multisig.proposal_count.to_le_bytes().as_ref()],
+        space = Proposal::space(&accounts, &data),
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateProposal<'info> {
+    // Proposes an instruction of this program to be signed by the multisig authority,
+    // the proposer's approval is recorded right away
+    pub fn create_proposal(
+        &mut self,
+        accounts: Vec<ProposalAccount>,
+        data: Vec<u8>,
+        bumps: &CreateProposalBumps,
+    ) -> Result<()> {
+        let proposal_id = self.multisig.proposal_count;
+        self.proposal.set_inner(Proposal {
+            multisig: self.multisig.key(),
+            proposal_id,
+            proposer: self.proposer.key(),
+            signers_version: self.multisig.signers_version,
+            accounts,
+            data,
+            approvals: vec![self.proposer.key()],
+            executed: false,
+            bump: bumps.proposal,
+        });
+        self.multisig.proposal_count = math::add(proposal_id, 1)?;
+
+        emit!(ProposalCreated {
+            multisig: self.multisig.key(),
+            proposal: self.proposal.key(),
+            proposal_id,
+            proposer: self.proposer.key(),
+        });
+
+        Ok(())
+    }
+}
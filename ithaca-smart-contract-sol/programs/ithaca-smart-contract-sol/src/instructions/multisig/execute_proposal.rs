@@ -0,0 +1,88 @@
+use crate::error::MultisigError;
+use crate::events::ProposalExecuted;
+use crate::program::IthacaSmartContractSol;
+use crate::state::{Multisig, Proposal};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    // Anyone can execute a proposal once it reached the threshold
+    pub executor: Signer<'info>,
+    #[account(
+        seeds = [b"multisig".as_ref(), multisig.access_controller.as_ref()],
+        bump = multisig.bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+    #[account(
+        mut,
+        seeds = [b"proposal".as_ref(), multisig.key().as_ref(), proposal.proposal_id.to_le_bytes().as_ref()],
+        bump = proposal.bump,
+        constraint = !proposal.executed @ MultisigError::ProposalExecuted,
+        constraint = proposal.signers_version == multisig.signers_version @ MultisigError::StaleProposal
+    )]
+    pub proposal: Account<'info, Proposal>,
+    /// CHECK: Data-less PDA signing the proposed instruction, also pays for the accounts it creates
+    #[account(
+        seeds = [b"multisig_authority".as_ref(), multisig.key().as_ref()],
+        bump = multisig.authority_bump
+    )]
+    pub authority: UncheckedAccount<'info>,
+    pub ithaca_program: Program<'info, IthacaSmartContractSol>,
+}
+
+impl<'info> ExecuteProposal<'info> {
+    // Remaining accounts are the accounts of the proposed instruction, in the proposed order
+    pub fn execute_proposal(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(
+            self.proposal.approvals.len() >= self.multisig.threshold as usize,
+            MultisigError::ThresholdNotReached
+        );
+        require!(
+            remaining_accounts.len() == self.proposal.accounts.len()
+                && remaining_accounts
+                    .iter()
+                    .zip(&self.proposal.accounts)
+                    .all(|(account_info, account)| account_info.key() == account.pubkey),
+            MultisigError::ProposalAccountMismatch
+        );
+
+        // Persisted before the call so that the proposal can't be executed again from within it
+        self.proposal.executed = true;
+        self.proposal.exit(&crate::ID)?;
+
+        let instruction = Instruction {
+            program_id: crate::ID,
+            accounts: self
+                .proposal
+                .accounts
+                .iter()
+                .map(|account| AccountMeta {
+                    pubkey: account.pubkey,
+                    is_signer: account.is_signer,
+                    is_writable: account.is_writable,
+                })
+                .collect(),
+            data: self.proposal.data.clone(),
+        };
+        let mut account_infos = remaining_accounts.to_vec();
+        account_infos.push(self.ithaca_program.to_account_info());
+
+        let multisig_key = self.multisig.key();
+        let authority_seeds = &[
+            b"multisig_authority".as_ref(),
+            multisig_key.as_ref(),
+            &[self.multisig.authority_bump],
+        ];
+        invoke_signed(&instruction, &account_infos, &[authority_seeds])?;
+
+        emit!(ProposalExecuted {
+            multisig: multisig_key,
+            proposal_id: self.proposal.proposal_id,
+            executor: self.executor.key(),
+        });
+
+        Ok(())
+    }
+}
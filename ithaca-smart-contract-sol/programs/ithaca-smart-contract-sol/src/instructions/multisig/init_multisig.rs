@@ -0,0 +1,64 @@
+use crate::error::AccessControlError;
+use crate::events::MultisigInitialized;
+use crate::state::{AccessController, Multisig};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct InitMultisig<'info> {
+    #[account(mut,
+        constraint = admin.key() == access_controller.admin @ AccessControlError::UnauthorizedAdmin)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+    )]
+    pub access_controller: Account<'info, AccessController>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"multisig".as_ref(), access_controller.key().as_ref()],
+        space = Multisig::INIT_SPACE,
+        bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitMultisig<'info> {
+    // Creates the multisig of the access controller. The multisig authority only takes over
+    // the admin-gated instructions once the admin is transferred to it through propose_admin
+    // and an executed accept_admin proposal.
+    pub fn init_multisig(
+        &mut self,
+        signers: Vec<Pubkey>,
+        threshold: u8,
+        bumps: &InitMultisigBumps,
+    ) -> Result<()> {
+        Multisig::validate_signers(&signers, threshold)?;
+
+        let multisig_key = self.multisig.key();
+        let (authority, authority_bump) = Pubkey::find_program_address(
+            &[b"multisig_authority", multisig_key.as_ref()],
+            &crate::ID,
+        );
+        self.multisig.set_inner(Multisig {
+            access_controller: self.access_controller.key(),
+            signers: signers.clone(),
+            threshold,
+            proposal_count: 0,
+            signers_version: 0,
+            bump: bumps.multisig,
+            authority_bump,
+        });
+
+        emit!(MultisigInitialized {
+            access_controller: self.access_controller.key(),
+            multisig: multisig_key,
+            authority,
+            signers,
+            threshold,
+        });
+
+        Ok(())
+    }
+}
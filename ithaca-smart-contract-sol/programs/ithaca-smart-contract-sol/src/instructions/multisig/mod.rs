@@ -0,0 +1,14 @@
+pub mod init_multisig;
+pub use init_multisig::*;
+
+pub mod set_multisig_signers;
+pub use set_multisig_signers::*;
+
+pub mod create_proposal;
+pub use create_proposal::*;
+
+pub mod approve_proposal;
+pub use approve_proposal::*;
+
+pub mod execute_proposal;
+pub use execute_proposal::*;
@@ -0,0 +1,40 @@
+use crate::events::MultisigSignersChanged;
+use crate::math;
+use crate::state::Multisig;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetMultisigSigners<'info> {
+    // Only signs through an executed proposal
+    #[account(
+        seeds = [b"multisig_authority".as_ref(), multisig.key().as_ref()],
+        bump = multisig.authority_bump
+    )]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"multisig".as_ref(), multisig.access_controller.as_ref()],
+        bump = multisig.bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+}
+
+impl<'info> SetMultisigSigners<'info> {
+    // Replaces the signers and the threshold, proposals created before are no longer executable
+    pub fn set_multisig_signers(&mut self, signers: Vec<Pubkey>, threshold: u8) -> Result<()> {
+        Multisig::validate_signers(&signers, threshold)?;
+
+        self.multisig.signers = signers.clone();
+        self.multisig.threshold = threshold;
+        self.multisig.signers_version = math::add(self.multisig.signers_version, 1)?;
+
+        emit!(MultisigSignersChanged {
+            multisig: self.multisig.key(),
+            signers,
+            threshold,
+            signers_version: self.multisig.signers_version,
+        });
+
+        Ok(())
+    }
+}
@@ -1,16 +1,32 @@
 use crate::error::{AccessControlError, TokenValidatorError};
+use crate::events::TokenWhitelisted;
 use crate::state::access_controller_state::{AccessController, Member, Role};
-use crate::{Roles, TokenValidator, WhitelistedToken};
+use crate::{Roles, Subsystem, TokenStatus, TokenValidator, WhitelistedToken};
 use anchor_lang::prelude::*;
-use anchor_spl::token::Mint;
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+};
+use anchor_spl::token_2022::spl_token_2022::{self, state::Mint as MintState};
+use anchor_spl::token_interface::Mint;
+
+// Token-2022 extensions that let a third party take, freeze or trap the tokens held in the
+// vaults, or run arbitrary code on every transfer
+const DANGEROUS_EXTENSIONS: [ExtensionType; 5] = [
+    ExtensionType::PermanentDelegate,
+    ExtensionType::TransferHook,
+    ExtensionType::MintCloseAuthority,
+    ExtensionType::DefaultAccountState,
+    ExtensionType::NonTransferable,
+];
 
 #[derive(Accounts)]
 pub struct AddTokenToWhitelist<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
     #[account(
-        seeds = [b"access_controller".as_ref(), access_controller.admin.as_ref()],
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
         bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Config) @ AccessControlError::Paused,
     )]
     pub access_controller: Account<'info, AccessController>,
     // using admin role to ensure this method is called by an admin member
@@ -20,8 +36,10 @@ pub struct AddTokenToWhitelist<'info> {
     )]
     pub role: Account<'info, Role>,
     #[account(
-        seeds = [b"member".as_ref(), role.key().as_ref(), admin.key().as_ref()],
-        bump = member.bump
+        seeds = [b"member".as_ref(), access_controller.key().as_ref(), admin.key().as_ref()],
+        bump = member.bump,
+        constraint = member.has_role(Roles::Admin) @ AccessControlError::NoRole,
+        constraint = !member.is_expired(Roles::Admin)? @ AccessControlError::RoleExpired
     )]
     pub member: Account<'info, Member>,
     #[account(
@@ -32,7 +50,7 @@ pub struct AddTokenToWhitelist<'info> {
     #[account(
         constraint = new_token_to_whitelist.decimals > 0 @ TokenValidatorError::NonFungibleToken
     )]
-    pub new_token_to_whitelist: Account<'info, Mint>,
+    pub new_token_to_whitelist: InterfaceAccount<'info, Mint>,
     #[account(
         init,
         payer = admin,
@@ -51,23 +69,48 @@ impl<'info> AddTokenToWhitelist<'info> {
         &mut self,
         bumps: &AddTokenToWhitelistBumps,
         token_precision: u8,
+        allow_dangerous_extensions: bool,
     ) -> Result<()> {
         require!(
-            self.role.role == Roles::Admin.as_str(),
+            self.role.role == Roles::Admin,
             AccessControlError::UnauthorizedAdmin
         );
+        require!(
+            allow_dangerous_extensions || !self.has_dangerous_extensions()?,
+            TokenValidatorError::DangerousMintExtension
+        );
         self.whitelisted_token.set_inner(WhitelistedToken {
             token_mint: self.new_token_to_whitelist.key(),
             token_decimals: self.new_token_to_whitelist.decimals,
             token_precision,
             bump: bumps.whitelisted_token,
+            status: TokenStatus::Active,
+            deposit_cap: None,
+            client_deposit_cap: None,
+            min_deposit: 0,
+            total_deposits: 0,
+            ledger_count: 0,
         });
 
-        msg!(
-            "Token {} added to whitelist successfully",
-            self.new_token_to_whitelist.key().to_string()
-        );
+        emit!(TokenWhitelisted {
+            token_validator: self.token_validator.key(),
+            token_mint: self.whitelisted_token.token_mint,
+            token_decimals: self.whitelisted_token.token_decimals,
+            token_precision,
+        });
 
         Ok(())
     }
+
+    fn has_dangerous_extensions(&self) -> Result<bool> {
+        let mint = self.new_token_to_whitelist.to_account_info();
+        if mint.owner != &spl_token_2022::ID {
+            return Ok(false);
+        }
+        let data = mint.try_borrow_data()?;
+        let extensions = StateWithExtensions::<MintState>::unpack(&data)?.get_extension_types()?;
+        Ok(extensions
+            .iter()
+            .any(|extension| DANGEROUS_EXTENSIONS.contains(extension)))
+    }
 }
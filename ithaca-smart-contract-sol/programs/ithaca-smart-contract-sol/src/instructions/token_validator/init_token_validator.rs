@@ -1,6 +1,7 @@
 use crate::error::AccessControlError;
+use crate::events::TokenValidatorInitialized;
 use crate::state::access_controller_state::{AccessController, Member, Role};
-use crate::{Roles, TokenValidator};
+use crate::{Roles, Subsystem, TokenValidator};
 use anchor_lang::prelude::*;
 
 #[derive(Accounts)]
@@ -8,8 +9,9 @@ pub struct InitTokenValidator<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
     #[account(
-        seeds = [b"access_controller".as_ref(), access_controller.admin.as_ref()],
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
         bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Config) @ AccessControlError::Paused,
     )]
     pub access_controller: Account<'info, AccessController>,
     // using admin role to ensure this method is called by an admin member
@@ -19,8 +21,10 @@ pub struct InitTokenValidator<'info> {
     )]
     pub role: Account<'info, Role>,
     #[account(
-        seeds = [b"member".as_ref(), role.key().as_ref(), admin.key().as_ref()],
-        bump = member.bump
+        seeds = [b"member".as_ref(), access_controller.key().as_ref(), admin.key().as_ref()],
+        bump = member.bump,
+        constraint = member.has_role(Roles::Admin) @ AccessControlError::NoRole,
+        constraint = !member.is_expired(Roles::Admin)? @ AccessControlError::RoleExpired
     )]
     pub member: Account<'info, Member>,
     #[account(
@@ -38,7 +42,7 @@ impl<'info> InitTokenValidator<'info> {
     //will init the token validator account which we will use to whitelist tokens
     pub fn init_token_validator(&mut self, bumps: &InitTokenValidatorBumps) -> Result<()> {
         require!(
-            self.role.role == Roles::Admin.as_str(),
+            self.role.role == Roles::Admin,
             AccessControlError::UnauthorizedAdmin
         );
         self.token_validator.set_inner(TokenValidator {
@@ -46,7 +50,10 @@ impl<'info> InitTokenValidator<'info> {
             bump: bumps.token_validator,
         });
 
-        msg!("Token validator initialized successfully");
+        emit!(TokenValidatorInitialized {
+            access_controller: self.access_controller.key(),
+            token_validator: self.token_validator.key(),
+        });
 
         Ok(())
     }
@@ -0,0 +1,91 @@
+use crate::error::{AccessControlError, TokenValidatorError};
+use crate::events::WhitelistedTokenMigrated;
+use crate::migration;
+use crate::state::access_controller_state::AccessController;
+use crate::state::token_validator_state::{
+    LegacyWhitelistedToken, TokenValidator, WhitelistedToken,
+};
+use crate::TokenStatus;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+#[derive(Accounts)]
+pub struct MigrateWhitelistedToken<'info> {
+    // Legacy accounts can be migrated by the main access controller admin only
+    #[account(mut,
+        constraint = admin.key() == access_controller.admin @ AccessControlError::UnauthorizedAdmin)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+    )]
+    pub access_controller: Account<'info, AccessController>,
+    #[account(
+        seeds = [b"token_validator".as_ref(), access_controller.key().as_ref()],
+        bump = token_validator.bump
+    )]
+    pub token_validator: Account<'info, TokenValidator>,
+    pub token: InterfaceAccount<'info, Mint>,
+    /// CHECK: Whitelisted token still in its legacy layout, checked by the handler against the mint
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"whitelisted_token".as_ref(), token_validator.key().as_ref(), token.key().as_ref()],
+        bump
+    )]
+    pub whitelisted_token: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateWhitelistedToken<'info> {
+    // Rewrites the whitelisted token with the current layout, paid by the admin. The token
+    // stays active and uncapped, `total_deposits` is the sum of the client balances of the
+    // token as computed off chain, which the deposit cap will be checked against, and
+    // `ledger_count` the number of its ledgers, which keep its precision from changing.
+    pub fn migrate_whitelisted_token(
+        &mut self,
+        total_deposits: u64,
+        ledger_count: u64,
+    ) -> Result<()> {
+        let legacy_whitelisted_token: LegacyWhitelistedToken =
+            migration::load_legacy::<WhitelistedToken, _>(
+                &self.whitelisted_token,
+                LegacyWhitelistedToken::SPACE,
+            )?
+            .ok_or(TokenValidatorError::AlreadyMigrated)?;
+        require_keys_eq!(
+            legacy_whitelisted_token.token_mint,
+            self.token.key(),
+            TokenValidatorError::AlreadyMigrated
+        );
+
+        let whitelisted_token = WhitelistedToken {
+            token_mint: legacy_whitelisted_token.token_mint,
+            token_decimals: legacy_whitelisted_token.token_decimals,
+            token_precision: legacy_whitelisted_token.token_precision,
+            bump: legacy_whitelisted_token.bump,
+            status: TokenStatus::Active,
+            deposit_cap: None,
+            client_deposit_cap: None,
+            min_deposit: 0,
+            total_deposits,
+            ledger_count,
+        };
+        migration::store_migrated(
+            &self.whitelisted_token,
+            &whitelisted_token,
+            WhitelistedToken::INIT_SPACE,
+            &self.admin,
+            &self.system_program,
+        )?;
+
+        emit!(WhitelistedTokenMigrated {
+            token_validator: self.token_validator.key(),
+            token_mint: self.token.key(),
+            total_deposits,
+            ledger_count,
+        });
+
+        Ok(())
+    }
+}
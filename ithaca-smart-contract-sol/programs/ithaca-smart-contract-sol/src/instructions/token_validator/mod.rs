@@ -6,3 +6,9 @@ pub use add_token_to_whitelist::*;
 
 pub mod remove_token_from_whitelist;
 pub use remove_token_from_whitelist::*;
+
+pub mod set_token_config;
+pub use set_token_config::*;
+
+pub mod migrate_whitelisted_token;
+pub use migrate_whitelisted_token::*;
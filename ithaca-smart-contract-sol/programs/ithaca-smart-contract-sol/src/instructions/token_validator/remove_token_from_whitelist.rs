@@ -1,16 +1,18 @@
-use crate::error::TokenValidatorError;
+use crate::error::{AccessControlError, TokenValidatorError};
+use crate::events::TokenRemovedFromWhitelist;
 use crate::state::access_controller_state::{AccessController, Member, Role};
-use crate::{Roles, TokenValidator, WhitelistedToken};
+use crate::{Roles, Subsystem, TokenStatus, TokenValidator, WhitelistedToken};
 use anchor_lang::prelude::*;
-use anchor_spl::token::Mint;
+use anchor_spl::token_interface::Mint;
 
 #[derive(Accounts)]
 pub struct RemoveTokenFromWhitelist<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
     #[account(
-        seeds = [b"access_controller".as_ref(), access_controller.admin.as_ref()],
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
         bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Config) @ AccessControlError::Paused,
     )]
     pub access_controller: Account<'info, AccessController>,
     // using admin role to ensure this method is called by an admin member
@@ -20,8 +22,10 @@ pub struct RemoveTokenFromWhitelist<'info> {
     )]
     pub role: Account<'info, Role>,
     #[account(
-        seeds = [b"member".as_ref(), role.key().as_ref(), admin.key().as_ref()],
-        bump = member.bump
+        seeds = [b"member".as_ref(), access_controller.key().as_ref(), admin.key().as_ref()],
+        bump = member.bump,
+        constraint = member.has_role(Roles::Admin) @ AccessControlError::NoRole,
+        constraint = !member.is_expired(Roles::Admin)? @ AccessControlError::RoleExpired
     )]
     pub member: Account<'info, Member>,
     #[account(
@@ -32,11 +36,10 @@ pub struct RemoveTokenFromWhitelist<'info> {
     #[account(
         constraint = token_to_remove.decimals > 0 @ TokenValidatorError::NonFungibleToken
     )]
-    pub token_to_remove: Account<'info, Mint>,
-    // closing the whitelisted token account to remove the token from the whitelist
+    pub token_to_remove: InterfaceAccount<'info, Mint>,
+    // the whitelisted token account is kept so that the client balances can still be withdrawn
     #[account(
         mut,
-        close = admin,
         seeds = [b"whitelisted_token".as_ref(), token_validator.key().as_ref(), token_to_remove.key().as_ref()],
         bump = whitelisted_token.bump
     )]
@@ -45,11 +48,14 @@ pub struct RemoveTokenFromWhitelist<'info> {
 }
 
 impl<'info> RemoveTokenFromWhitelist<'info> {
+    // Delists the token by making it withdraw only, it can be listed again through governance
     pub fn remove_token_from_whitelist(&mut self) -> Result<()> {
-        msg!(
-            "Token {} removed from whitelist",
-            self.token_to_remove.key()
-        );
+        self.whitelisted_token.status = TokenStatus::WithdrawOnly;
+
+        emit!(TokenRemovedFromWhitelist {
+            token_validator: self.token_validator.key(),
+            token_mint: self.token_to_remove.key(),
+        });
         Ok(())
     }
 }
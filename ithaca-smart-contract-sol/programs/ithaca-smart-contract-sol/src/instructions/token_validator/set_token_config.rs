@@ -0,0 +1,83 @@
+use crate::error::{AccessControlError, TokenValidatorError};
+use crate::events::TokenConfigUpdated;
+use crate::state::access_controller_state::{AccessController, Member, Role};
+use crate::{Roles, Subsystem, TokenStatus, TokenValidator, WhitelistedToken};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+#[derive(Accounts)]
+pub struct SetTokenConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [b"access_controller".as_ref(), access_controller.creator.as_ref()],
+        bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Config) @ AccessControlError::Paused,
+    )]
+    pub access_controller: Account<'info, AccessController>,
+    // using admin role to ensure this method is called by an admin member
+    #[account(
+        seeds = [b"role".as_ref(), access_controller.key().as_ref(), Roles::Admin.as_str().as_bytes()],
+        bump = role.bump
+    )]
+    pub role: Account<'info, Role>,
+    #[account(
+        seeds = [b"member".as_ref(), access_controller.key().as_ref(), admin.key().as_ref()],
+        bump = member.bump,
+        constraint = member.has_role(Roles::Admin) @ AccessControlError::NoRole,
+        constraint = !member.is_expired(Roles::Admin)? @ AccessControlError::RoleExpired
+    )]
+    pub member: Account<'info, Member>,
+    #[account(
+        seeds = [b"token_validator".as_ref(), access_controller.key().as_ref()],
+        bump = token_validator.bump
+    )]
+    pub token_validator: Account<'info, TokenValidator>,
+    #[account(
+        constraint = token.decimals > 0 @ TokenValidatorError::NonFungibleToken
+    )]
+    pub token: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"whitelisted_token".as_ref(), token_validator.key().as_ref(), token.key().as_ref()],
+        bump = whitelisted_token.bump
+    )]
+    pub whitelisted_token: Account<'info, WhitelistedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SetTokenConfig<'info> {
+    // Instant path to pause deposits, delist the token or lower its caps. Reopening the token
+    // or loosening its limits goes through governance.
+    pub fn set_token_config(
+        &mut self,
+        status: TokenStatus,
+        deposit_cap: Option<u64>,
+        client_deposit_cap: Option<u64>,
+        min_deposit: u64,
+    ) -> Result<()> {
+        require!(
+            self.whitelisted_token.is_tightened_by(
+                status,
+                deposit_cap,
+                client_deposit_cap,
+                min_deposit
+            ),
+            TokenValidatorError::ConfigNotTightened
+        );
+        self.whitelisted_token.status = status;
+        self.whitelisted_token.deposit_cap = deposit_cap;
+        self.whitelisted_token.client_deposit_cap = client_deposit_cap;
+        self.whitelisted_token.min_deposit = min_deposit;
+
+        emit!(TokenConfigUpdated {
+            token_validator: self.token_validator.key(),
+            token_mint: self.token.key(),
+            status,
+            deposit_cap,
+            client_deposit_cap,
+            min_deposit,
+        });
+        Ok(())
+    }
+}
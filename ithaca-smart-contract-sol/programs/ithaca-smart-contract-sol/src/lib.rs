@@ -1,13 +1,19 @@
 pub mod constants;
 pub mod error;
+pub mod events;
 pub mod instructions;
+pub mod math;
+pub mod migration;
+pub mod role_gate;
 pub mod state;
+pub mod token_transfer;
 
 use anchor_lang::prelude::*;
 
-use anchor_spl::token::Mint;
+use anchor_spl::token_interface::Mint;
 pub use constants::*;
-use error::{LedgerError, TokenValidatorError};
+use error::{AccessControlError, LedgerError, TokenValidatorError};
+pub use events::*;
 pub use instructions::*;
 pub use state::*;
 
@@ -27,29 +33,129 @@ pub mod ithaca_smart_contract_sol {
 
     pub fn grant_role(
         ctx: Context<GrantRole>,
-        role_granted: String,
+        role_granted: Roles,
         new_member: Pubkey,
+        page_index: u64,
+        expires_at: Option<i64>,
     ) -> Result<()> {
-        ctx.accounts
-            .grant_role(role_granted, new_member, &ctx.bumps)
+        ctx.accounts.grant_role(
+            role_granted,
+            new_member,
+            page_index,
+            expires_at,
+            &ctx.bumps,
+        )
     }
 
     pub fn renounce_role(
         ctx: Context<RenounceRole>,
-        role_renounced: String,
+        role_renounced: Roles,
         member_pk: Pubkey,
+        page_index: u64,
     ) -> Result<()> {
-        ctx.accounts.renounce_role(role_renounced, member_pk)
+        ctx.accounts
+            .renounce_role(role_renounced, member_pk, page_index)
     }
 
     pub fn check_role(
         ctx: Context<CheckRole>,
-        role_checked: String,
+        role_checked: Roles,
         member_pk: Pubkey,
-    ) -> Result<()> {
+    ) -> Result<bool> {
         ctx.accounts.check_role(role_checked, member_pk)
     }
 
+    pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+        ctx.accounts.propose_admin(new_admin)
+    }
+
+    pub fn accept_admin(ctx: Context<AcceptAdmin>, page_index: u64) -> Result<()> {
+        ctx.accounts.accept_admin(page_index, &ctx.bumps)
+    }
+
+    pub fn migrate_access_controller(ctx: Context<MigrateAccessController>) -> Result<()> {
+        ctx.accounts.migrate_access_controller()
+    }
+
+    pub fn migrate_role(ctx: Context<MigrateRole>, role_migrated: Roles) -> Result<()> {
+        ctx.accounts.migrate_role(role_migrated)
+    }
+
+    pub fn migrate_member(
+        ctx: Context<MigrateMember>,
+        role_migrated: Roles,
+        member_pk: Pubkey,
+        page_index: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .migrate_member(role_migrated, member_pk, page_index, &ctx.bumps)
+    }
+
+    pub fn set_pause(
+        ctx: Context<SetPause>,
+        paused: bool,
+        paused_subsystems: Vec<Subsystem>,
+        releases_allowed: bool,
+    ) -> Result<()> {
+        ctx.accounts
+            .set_pause(paused, paused_subsystems, releases_allowed)
+    }
+
+    pub fn init_multisig(
+        ctx: Context<InitMultisig>,
+        signers: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        ctx.accounts.init_multisig(signers, threshold, &ctx.bumps)
+    }
+
+    pub fn set_multisig_signers(
+        ctx: Context<SetMultisigSigners>,
+        signers: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        ctx.accounts.set_multisig_signers(signers, threshold)
+    }
+
+    pub fn create_proposal(
+        ctx: Context<CreateProposal>,
+        accounts: Vec<ProposalAccount>,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        ctx.accounts.create_proposal(accounts, data, &ctx.bumps)
+    }
+
+    pub fn approve_proposal(ctx: Context<ApproveProposal>) -> Result<()> {
+        ctx.accounts.approve_proposal()
+    }
+
+    pub fn execute_proposal<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecuteProposal<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.execute_proposal(ctx.remaining_accounts)
+    }
+
+    pub fn init_governance(ctx: Context<InitGovernance>, min_delay: i64) -> Result<()> {
+        ctx.accounts.init_governance(min_delay, &ctx.bumps)
+    }
+
+    pub fn schedule_parameter_change(
+        ctx: Context<ScheduleParameterChange>,
+        change: ParameterChange,
+        eta: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .schedule_parameter_change(change, eta, &ctx.bumps)
+    }
+
+    pub fn cancel_parameter_change(ctx: Context<CancelParameterChange>) -> Result<()> {
+        ctx.accounts.cancel_parameter_change()
+    }
+
+    pub fn execute_parameter_change(ctx: Context<ExecuteParameterChange>) -> Result<()> {
+        ctx.accounts.execute_parameter_change()
+    }
+
     pub fn init_token_validator(ctx: Context<InitTokenValidator>) -> Result<()> {
         ctx.accounts.init_token_validator(&ctx.bumps)
     }
@@ -57,15 +163,38 @@ pub mod ithaca_smart_contract_sol {
     pub fn add_token_to_whitelist(
         ctx: Context<AddTokenToWhitelist>,
         token_precision: u8,
+        allow_dangerous_extensions: bool,
     ) -> Result<()> {
-        ctx.accounts
-            .add_token_to_whitelist(&ctx.bumps, token_precision)
+        ctx.accounts.add_token_to_whitelist(
+            &ctx.bumps,
+            token_precision,
+            allow_dangerous_extensions,
+        )
     }
 
     pub fn remove_token_from_whitelist(ctx: Context<RemoveTokenFromWhitelist>) -> Result<()> {
         ctx.accounts.remove_token_from_whitelist()
     }
 
+    pub fn set_token_config(
+        ctx: Context<SetTokenConfig>,
+        status: TokenStatus,
+        deposit_cap: Option<u64>,
+        client_deposit_cap: Option<u64>,
+        min_deposit: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .set_token_config(status, deposit_cap, client_deposit_cap, min_deposit)
+    }
+
+    pub fn migrate_whitelisted_token(
+        ctx: Context<MigrateWhitelistedToken>,
+        total_deposits: u64,
+        ledger_count: u64,
+    ) -> Result<()> {
+        ctx.accounts.migrate_whitelisted_token(total_deposits, ledger_count)
+    }
+
     pub fn init_fundlock(
         ctx: Context<InitFundlock>,
         trade_lock: i64,
@@ -75,28 +204,107 @@ pub mod ithaca_smart_contract_sol {
             .init_fundlock(trade_lock, release_lock, &ctx.bumps)
     }
 
-    pub fn deposit_fundlock(ctx: Context<DepositFundlock>, amount: u64) -> Result<()> {
-        ctx.accounts.deposit_fundlock(amount, &ctx.bumps)
+    pub fn migrate_fundlock(ctx: Context<MigrateFundlock>) -> Result<()> {
+        ctx.accounts.migrate_fundlock()
+    }
+
+    pub fn migrate_client_balance(ctx: Context<MigrateClientBalance>) -> Result<()> {
+        ctx.accounts.migrate_client_balance()
+    }
+
+    pub fn deposit_fundlock<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DepositFundlock<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .deposit_fundlock(amount, &ctx.bumps, ctx.remaining_accounts)
     }
 
     pub fn withdraw_fundlock(ctx: Context<WithdrawFundlock>, amount: u64) -> Result<()> {
         ctx.accounts.withdraw_fundlock(amount)
     }
 
-    pub fn release_fundlock(ctx: Context<ReleaseFundlock>, index: u64) -> Result<()> {
-        ctx.accounts.release_fundlock(index)
+    pub fn release_fundlock<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReleaseFundlock<'info>>,
+        index: u64,
+    ) -> Result<()> {
+        ctx.accounts.release_fundlock(index, ctx.remaining_accounts)
+    }
+
+    pub fn deposit_fundlock_sol(ctx: Context<DepositFundlockSol>, amount: u64) -> Result<()> {
+        ctx.accounts.deposit_fundlock_sol(amount, &ctx.bumps)
+    }
+
+    pub fn withdraw_fundlock_sol(ctx: Context<WithdrawFundlockSol>, amount: u64) -> Result<()> {
+        ctx.accounts.withdraw_fundlock_sol(amount)
+    }
+
+    pub fn release_fundlock_sol(ctx: Context<ReleaseFundlockSol>, index: u64) -> Result<()> {
+        ctx.accounts.release_fundlock_sol(index)
+    }
+
+    pub fn release_all_matured<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReleaseAllMatured<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.release_all_matured(ctx.remaining_accounts)
+    }
+
+    pub fn release_all_matured_sol(ctx: Context<ReleaseAllMaturedSol>) -> Result<()> {
+        ctx.accounts.release_all_matured_sol()
+    }
+
+    pub fn set_client_destination(ctx: Context<SetClientDestination>) -> Result<()> {
+        ctx.accounts.set_client_destination()
     }
 
-    pub fn init_ledger(ctx: Context<InitLedger>) -> Result<()> {
-        ctx.accounts.init_ledger(&ctx.bumps)
+    pub fn release_fundlock_to<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReleaseFundlockTo<'info>>,
+        index: u64,
+    ) -> Result<()> {
+        ctx.accounts.release_fundlock_to(index, ctx.remaining_accounts)
+    }
+
+    pub fn set_keeper_tip(ctx: Context<SetKeeperTip>, tip: u64, min_release: u64) -> Result<()> {
+        ctx.accounts.set_keeper_tip(tip, min_release, &ctx.bumps)
+    }
+
+    pub fn crank_release<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CrankRelease<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.crank_release(ctx.remaining_accounts)
+    }
+
+    pub fn crank_release_sol(ctx: Context<CrankReleaseSol>) -> Result<()> {
+        ctx.accounts.crank_release_sol()
+    }
+
+    pub fn cancel_withdrawal(
+        ctx: Context<CancelWithdrawal>,
+        index: u64,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.cancel_withdrawal(index, amount)
+    }
+
+    pub fn init_ledger(
+        ctx: Context<InitLedger>,
+        rent_payer: Pubkey,
+        insurance_fund: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts
+            .init_ledger(rent_payer, insurance_fund, &ctx.bumps)
     }
 
     pub fn create_contracts_and_positions<'info>(
         ctx: Context<'_, '_, '_, 'info, CreateOrUpdatePositions<'info>>,
         position_params: Vec<PositionsParam>,
+        contract_params: Vec<ContractParams>,
         backend_id: u64,
     ) -> Result<()> {
+        ctx.accounts.ledger.record_backend_id(backend_id)?;
+
         let remaining_accounts = &ctx.remaining_accounts;
+        let now = Clock::get()?.unix_timestamp;
 
         for (i, position) in position_params.iter().enumerate() {
             let contract_account_info = &remaining_accounts[i * 2]; // Even indices for contracts
@@ -130,8 +338,15 @@ pub mod ithaca_smart_contract_sol {
                 LedgerError::AccountOrderViolated
             );
 
-            // Handle contract account creation
+            // Handle contract account creation, the metadata of new contracts comes from the
+            // contract params while existing contracts keep theirs and must still be tradable
             if contract_account_info.data_is_empty() {
+                let params = contract_params
+                    .iter()
+                    .find(|params| params.contract_id == position.contract_id)
+                    .ok_or(LedgerError::MissingContractParams)?;
+                params.validate(now)?;
+
                 let init_space = Contract::INIT_SPACE;
                 let lamports = Rent::get()?.minimum_balance(init_space);
                 let ledger = ctx.accounts.ledger.key();
@@ -159,27 +374,43 @@ pub mod ithaca_smart_contract_sol {
                     ],
                     &[&signer],
                 )?;
-            }
-
-            let contract_account = Contract {
-                contract_id: position.contract_id,
-                bump: _contract_bump,
-            };
-
-            let data = contract_account.try_to_vec()?;
-
-            let mut final_data = vec![0u8; 8 + data.len()]; // Account discriminator (8 bytes) + contract data
-            final_data[8..].copy_from_slice(&data);
-            final_data[0..8].copy_from_slice(&Contract::DISCRIMINATOR);
-            let mut contract_data = contract_account_info
-                .try_borrow_mut_data()
-                .expect("Error borrowing contract data");
 
-            // Copy the data to the account
-            contract_data[0..final_data.len()].copy_from_slice(&final_data);
+                let contract_account = Contract::new(params, _contract_bump);
+
+                let data = contract_account.try_to_vec()?;
+
+                let mut final_data = vec![0u8; 8 + data.len()]; // Account discriminator (8 bytes) + contract data
+                final_data[8..].copy_from_slice(&data);
+                final_data[0..8].copy_from_slice(&Contract::DISCRIMINATOR);
+                let mut contract_data = contract_account_info.try_borrow_mut_data()?;
+
+                // Copy the data to the account
+                contract_data[0..final_data.len()].copy_from_slice(&final_data);
+
+                emit!(ContractCreated {
+                    ledger: ctx.accounts.ledger.key(),
+                    contract_id: params.contract_id,
+                    expiry: params.expiry,
+                    strike: params.strike,
+                    payoff_type: params.payoff_type,
+                });
+            } else {
+                let contract_data = contract_account_info.try_borrow_data()?;
+                require!(
+                    !contract_data.starts_with(&ClosedContract::DISCRIMINATOR),
+                    LedgerError::ContractClosed
+                );
+                let contract = Contract::try_deserialize(&mut &contract_data[..])?;
+                require!(
+                    contract.contract_id == position.contract_id,
+                    LedgerError::InvalidContractId
+                );
+                contract.require_tradable(now)?;
+            }
 
             // Handle position account creation
-            if position_account_info.data_is_empty() {
+            let position_data_is_new = position_account_info.data_is_empty();
+            if position_data_is_new {
                 let init_space = Position::INIT_SPACE;
                 let lamports = Rent::get()?.minimum_balance(init_space);
                 let seeds = [
@@ -211,10 +442,28 @@ pub mod ithaca_smart_contract_sol {
                 )?;
             }
 
+            // The change is netted into the existing position, so longs and shorts of the same
+            // client collapse into a single signed size
+            let previous_size = if position_data_is_new {
+                0
+            } else {
+                Position::try_deserialize(&mut &position_account_info.try_borrow_data()?[..])?.size
+            };
+            let size = math::add_signed(previous_size, position.size)?;
+
+            let mut contract_data = contract_account_info.try_borrow_mut_data()?;
+            let mut contract = Contract::try_deserialize(&mut &contract_data[..])?;
+            // Track the open positions so the contract can only be closed once they are gone
+            if position_data_is_new {
+                contract.position_count = math::add(contract.position_count, 1)?;
+            }
+            contract.update_open_interest(previous_size, size)?;
+            contract.try_serialize(&mut &mut contract_data[..])?;
+
             let position_account = Position {
                 contract_id: position.contract_id,
                 client: position.client,
-                size: position.size,
+                size,
                 bump: _position_bump,
             };
 
@@ -229,11 +478,27 @@ pub mod ithaca_smart_contract_sol {
 
             // Copy the data to the account
             position_data[0..final_data.len()].copy_from_slice(&final_data);
+
+            emit!(PositionUpdated {
+                ledger: ctx.accounts.ledger.key(),
+                contract_id: position.contract_id,
+                client: position.client,
+                size,
+                size_change: position.size,
+                backend_id,
+            });
         }
-        msg!("Backend ID: {}", backend_id);
         Ok(())
     }
 
+    pub fn migrate_ledger(
+        ctx: Context<MigrateLedger>,
+        rent_payer: Pubkey,
+        insurance_fund: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts.migrate_ledger(rent_payer, insurance_fund)
+    }
+
     pub fn update_fund_movements(
         ctx: Context<UpdateFundMovements>,
         fund_movements: Vec<FundMovementParamOptimized>,
@@ -308,6 +573,20 @@ pub mod ithaca_smart_contract_sol {
         )
     }
 
+    pub fn close_positions<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClosePositions<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.close_positions(ctx.remaining_accounts)
+    }
+
+    pub fn settle_contract(
+        ctx: Context<SettleContract>,
+        contract_id: u64,
+        settlement_price: u64,
+    ) -> Result<()> {
+        ctx.accounts.settle_contract(contract_id, settlement_price)
+    }
+
     pub fn deposit_kamino(ctx: Context<DepositKamino>, amount: u64) -> Result<()> {
         ctx.accounts.deposit_kamino(amount)
     }
@@ -316,6 +595,22 @@ pub mod ithaca_smart_contract_sol {
         ctx.accounts.redeem_kamino(amount)
     }
 
+    pub fn liquidate_client<'info>(
+        ctx: Context<'_, '_, 'info, 'info, LiquidateClient<'info>>,
+        client: Pubkey,
+        underlying_amount: u64,
+        strike_amount: u64,
+        backend_id: u64,
+    ) -> Result<()> {
+        ctx.accounts.liquidate_client(
+            ctx.remaining_accounts,
+            client,
+            underlying_amount,
+            strike_amount,
+            backend_id,
+        )
+    }
+
     //This is a dummy function to make sure we can fetch account data and have them in IDL
     pub fn dummy_for_idl(_ctx: Context<DummyContextForIdl>) -> Result<()> {
         Ok(())
@@ -328,8 +623,9 @@ pub struct CreateOrUpdatePositions<'info> {
     #[account(mut)]
     pub caller: Signer<'info>,
     #[account(
-        seeds = [b"access_controller", access_controller.admin.as_ref()],
+        seeds = [b"access_controller", access_controller.creator.as_ref()],
         bump = access_controller.bump,
+        constraint = !access_controller.is_paused(Subsystem::Ledger) @ AccessControlError::Paused,
     )]
     pub access_controller: Box<Account<'info, AccessController>>,
     #[account(
@@ -338,8 +634,10 @@ pub struct CreateOrUpdatePositions<'info> {
     )]
     pub role_util: Box<Account<'info, Role>>,
     #[account(
-        seeds = [b"member", role_util.key().as_ref(), caller.key().as_ref()],
-        bump = member_util.bump
+        seeds = [b"member", access_controller.key().as_ref(), caller.key().as_ref()],
+        bump = member_util.bump,
+        constraint = member_util.has_role(Roles::UtilityAccount) @ AccessControlError::NoRole,
+        constraint = !member_util.is_expired(Roles::UtilityAccount)? @ AccessControlError::RoleExpired
     )]
     pub member_util: Box<Account<'info, Member>>,
     #[account(
@@ -350,22 +648,25 @@ pub struct CreateOrUpdatePositions<'info> {
     #[account(
         constraint = underlying_token.decimals > 0 @ TokenValidatorError::NonFungibleToken
     )]
-    pub underlying_token: Box<Account<'info, Mint>>,
+    pub underlying_token: Box<InterfaceAccount<'info, Mint>>,
     #[account(
         seeds = [b"whitelisted_token", token_validator.key().as_ref(), underlying_token.key().as_ref()],
-        bump = whitelisted_underlying_token.bump
+        bump = whitelisted_underlying_token.bump,
+        constraint = whitelisted_underlying_token.is_tradable() @ TokenValidatorError::TokenWithdrawOnly
     )]
     pub whitelisted_underlying_token: Box<Account<'info, WhitelistedToken>>,
     #[account(
         constraint = strike_token.decimals > 0 @ TokenValidatorError::NonFungibleToken
     )]
-    pub strike_token: Box<Account<'info, Mint>>,
+    pub strike_token: Box<InterfaceAccount<'info, Mint>>,
     #[account(
         seeds = [b"whitelisted_token", token_validator.key().as_ref(), strike_token.key().as_ref()],
-        bump = whitelisted_strike_token.bump
+        bump = whitelisted_strike_token.bump,
+        constraint = whitelisted_strike_token.is_tradable() @ TokenValidatorError::TokenWithdrawOnly
     )]
     pub whitelisted_strike_token: Box<Account<'info, WhitelistedToken>>,
     #[account(
+        mut,
         seeds = [
             b"ledger",
             access_controller.key().as_ref(),
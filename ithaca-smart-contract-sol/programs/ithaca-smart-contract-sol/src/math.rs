@@ -0,0 +1,99 @@
+use crate::error::MathError;
+use anchor_lang::prelude::*;
+
+// Checked arithmetic used by every balance mutation, any overflow, underflow or
+// negative balance aborts the instruction instead of silently wrapping
+
+pub fn add(a: u64, b: u64) -> Result<u64> {
+    a.checked_add(b).ok_or(error!(MathError::MathOverflow))
+}
+
+pub fn sub(a: u64, b: u64) -> Result<u64> {
+    a.checked_sub(b).ok_or(error!(MathError::MathOverflow))
+}
+
+pub fn add_signed(a: i64, b: i64) -> Result<i64> {
+    a.checked_add(b).ok_or(error!(MathError::MathOverflow))
+}
+
+pub fn mul_signed(a: i64, b: i64) -> Result<i64> {
+    a.checked_mul(b).ok_or(error!(MathError::MathOverflow))
+}
+
+pub fn neg(a: i64) -> Result<i64> {
+    a.checked_neg().ok_or(error!(MathError::MathOverflow))
+}
+
+pub fn to_signed(a: u64) -> Result<i64> {
+    i64::try_from(a).map_err(|_| error!(MathError::MathOverflow))
+}
+
+// Applies a signed change to an unsigned balance, the resulting balance can't be negative
+pub fn apply_change(balance: u64, change: i64) -> Result<u64> {
+    if change >= 0 {
+        add(balance, change.unsigned_abs())
+    } else {
+        balance
+            .checked_sub(change.unsigned_abs())
+            .ok_or(error!(MathError::NegativeBalance))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn add_never_wraps(a: u64, b: u64) {
+            match add(a, b) {
+                Ok(sum) => prop_assert_eq!(sum as u128, a as u128 + b as u128),
+                Err(_) => prop_assert!(a as u128 + b as u128 > u64::MAX as u128),
+            }
+        }
+
+        #[test]
+        fn sub_never_goes_below_zero(a: u64, b: u64) {
+            match sub(a, b) {
+                Ok(difference) => prop_assert_eq!(difference, a - b),
+                Err(_) => prop_assert!(b > a),
+            }
+        }
+
+        #[test]
+        fn mul_signed_never_wraps(a: i64, b: i64) {
+            let expected = a as i128 * b as i128;
+            match mul_signed(a, b) {
+                Ok(product) => prop_assert_eq!(product as i128, expected),
+                Err(_) => prop_assert!(expected > i64::MAX as i128 || expected < i64::MIN as i128),
+            }
+        }
+
+        #[test]
+        fn neg_rejects_min(a: i64) {
+            match neg(a) {
+                Ok(negated) => prop_assert_eq!(negated as i128, -(a as i128)),
+                Err(_) => prop_assert_eq!(a, i64::MIN),
+            }
+        }
+
+        #[test]
+        fn apply_change_never_wraps_or_goes_negative(balance: u64, change: i64) {
+            let expected = balance as i128 + change as i128;
+            match apply_change(balance, change) {
+                Ok(new_balance) => prop_assert_eq!(new_balance as i128, expected),
+                Err(_) => prop_assert!(expected < 0 || expected > u64::MAX as i128),
+            }
+        }
+    }
+
+    #[test]
+    fn apply_change_handles_extremes() {
+        assert_eq!(apply_change(0, i64::MAX).unwrap(), i64::MAX as u64);
+        assert_eq!(apply_change(1 << 63, i64::MIN).unwrap(), 0);
+        assert!(apply_change((1 << 63) - 1, i64::MIN).is_err());
+        assert!(apply_change(u64::MAX, 1).is_err());
+        assert!(apply_change(0, -1).is_err());
+    }
+}
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer};
+use anchor_lang::Discriminator;
+
+// Accounts created before a layout change can't be deserialized with the new layout, so the
+// migration instructions read them with their legacy layout and rewrite them in place. A legacy
+// account is told apart by its discriminator and its size, which every layout change has grown.
+
+// Reads the account with the legacy layout L of T, none if it isn't a legacy account
+pub fn load_legacy<T: Discriminator, L: AnchorDeserialize>(
+    account: &AccountInfo,
+    legacy_space: usize,
+) -> Result<Option<L>> {
+    let data = account.try_borrow_data()?;
+    if data.len() != legacy_space || data[..8] != T::DISCRIMINATOR[..] {
+        return Ok(None);
+    }
+    Ok(L::deserialize(&mut &data[8..]).ok())
+}
+
+// Grows the account to space and writes the migrated account, the payer covers the extra rent
+pub fn store_migrated<'info, T: AccountSerialize>(
+    account: &AccountInfo<'info>,
+    migrated: &T,
+    space: usize,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let top_up = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(account.lamports());
+    if top_up > 0 {
+        transfer(
+            CpiContext::new(
+                system_program.clone(),
+                Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            top_up,
+        )?;
+    }
+    account.realloc(space, false)?;
+    migrated.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])
+}
@@ -0,0 +1,55 @@
+use crate::error::AccessControlError;
+use crate::Roles;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::{get_return_data, invoke};
+use anchor_lang::{InstructionData, ToAccountMetas};
+
+// Lets partner programs gate their own instructions on Ithaca roles by calling check_role
+// over CPI. The access controller isn't pinned here, callers must check it's the one they trust.
+// The member account can be left out when it doesn't exist, which simply answers false.
+
+pub fn has_role<'info>(
+    ithaca_program: AccountInfo<'info>,
+    access_controller: AccountInfo<'info>,
+    member: Option<AccountInfo<'info>>,
+    role: Roles,
+    member_pk: Pubkey,
+) -> Result<bool> {
+    require_keys_eq!(ithaca_program.key(), crate::ID, ErrorCode::InvalidProgramId);
+    let ix = anchor_lang::solana_program::instruction::Instruction {
+        program_id: crate::ID,
+        accounts: crate::accounts::CheckRole {
+            access_controller: access_controller.key(),
+            member: member.as_ref().map(|member| member.key()),
+        }
+        .to_account_metas(None),
+        data: crate::instruction::CheckRole {
+            role_checked: role,
+            member_pk,
+        }
+        .data(),
+    };
+    let mut account_infos = vec![access_controller];
+    account_infos.extend(member);
+    account_infos.push(ithaca_program);
+    invoke(&ix, &account_infos)?;
+
+    // Only trust return data written by Ithaca itself
+    let (program_id, data) = get_return_data().ok_or(ErrorCode::InvalidProgramId)?;
+    require_keys_eq!(program_id, crate::ID, ErrorCode::InvalidProgramId);
+    Ok(bool::try_from_slice(&data)?)
+}
+
+pub fn require_role<'info>(
+    ithaca_program: AccountInfo<'info>,
+    access_controller: AccountInfo<'info>,
+    member: Option<AccountInfo<'info>>,
+    role: Roles,
+    member_pk: Pubkey,
+) -> Result<()> {
+    require!(
+        has_role(ithaca_program, access_controller, member, role, member_pk)?,
+        AccessControlError::NoRole
+    );
+    Ok(())
+}
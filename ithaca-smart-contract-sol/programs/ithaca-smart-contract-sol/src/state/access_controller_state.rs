@@ -1,42 +1,189 @@
+use crate::constants::{Roles, Subsystem, ROLE_COUNT, ROLE_MEMBERS_PAGE_SIZE};
+use crate::error::AccessControlError;
+use crate::math;
 use anchor_lang::prelude::*;
 
 // expect to derive from the account init signer/payer
 #[account]
 pub struct AccessController {
+    // Signer of init_access_controller, seeds the access controller PDA so that every
+    // derived PDA stays valid when the admin changes
+    pub creator: Pubkey,
     pub admin: Pubkey,
+    // Admin proposed by the current admin, takes over once it accepts
+    pub pending_admin: Option<Pubkey>,
+    // Emergency pause set by the guardians, a global pause stops every subsystem
+    pub paused: bool,
+    // Bitmask of the subsystems paused on their own, see Subsystem::bit
+    pub paused_subsystems: u8,
+    // Lets clients release their queued withdrawals during a global pause
+    pub releases_allowed: bool,
     pub bump: u8,
 }
 
+impl AccessController {
+    pub fn is_paused(&self, subsystem: Subsystem) -> bool {
+        if self.paused_subsystems & subsystem.bit() != 0 {
+            return true;
+        }
+        self.paused && !(subsystem == Subsystem::Releases && self.releases_allowed)
+    }
+}
+
 // expect to derive from AccessController account
 #[account]
 pub struct Role {
-    pub role: String,
+    pub role: Roles,
     pub member_count: u64,
+    // Amount of RoleMembers pages created for the role, pages are never closed
+    pub page_count: u64,
     pub bump: u8,
 }
 
-// expect to derive from Role account
+// expect to derive from Role account and the page index, lists the role members so that
+// they can be enumerated without knowing their pubkeys
+#[account]
+pub struct RoleMembers {
+    pub role: Pubkey,
+    pub page_index: u64,
+    pub members: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl RoleMembers {
+    // Appends the member, a page created by init_if_needed is only accepted as the role's
+    // next page so that the pages stay contiguous
+    pub fn add(
+        &mut self,
+        role: &mut Account<Role>,
+        page_index: u64,
+        bump: u8,
+        member: Pubkey,
+    ) -> Result<()> {
+        if self.role == Pubkey::default() {
+            require!(
+                page_index == role.page_count,
+                AccessControlError::InvalidRoleMembersPage
+            );
+            self.role = role.key();
+            self.page_index = page_index;
+            self.bump = bump;
+            role.page_count = math::add(role.page_count, 1)?;
+        }
+        require!(!self.is_full(), AccessControlError::RoleMembersPageFull);
+        self.members.push(member);
+        Ok(())
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.members.len() >= ROLE_MEMBERS_PAGE_SIZE
+    }
+
+    // Unordered removal, returns false if the member isn't listed in this page
+    pub fn remove(&mut self, member: &Pubkey) -> bool {
+        match self.members.iter().position(|listed| listed == member) {
+            Some(position) => {
+                self.members.swap_remove(position);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// expect to derive from AccessController account, one per member for all of its roles
 #[account]
 pub struct Member {
+    pub member: Pubkey,
+    // Bitmask of the granted roles, see Roles::bit
+    pub roles: u8,
+    // Expiry of each granted role set by grant_role for time-bounded roles, see Roles::index
+    pub expires_at: [Option<i64>; ROLE_COUNT],
+    pub bump: u8,
+}
+
+impl Member {
+    pub fn is_expired(&self, role: Roles) -> Result<bool> {
+        match self.expires_at[role.index()] {
+            Some(expires_at) => Ok(Clock::get()?.unix_timestamp >= expires_at),
+            None => Ok(false),
+        }
+    }
+
+    pub fn has_role(&self, role: Roles) -> bool {
+        self.roles & role.bit() != 0
+    }
+
+    pub fn add_role(&mut self, role: Roles, expires_at: Option<i64>) {
+        self.roles |= role.bit();
+        self.expires_at[role.index()] = expires_at;
+    }
+
+    pub fn remove_role(&mut self, role: Roles) {
+        self.roles &= !role.bit();
+        self.expires_at[role.index()] = None;
+    }
+}
+
+// Layouts of the accounts created before roles were typed, the access controller only stored
+// its admin, role accounts stored the role name and a member account was seeded by the role
+// account for every granted role. Only read by the migration instructions.
+#[derive(AnchorDeserialize)]
+pub struct LegacyAccessController {
+    pub admin: Pubkey,
+    pub bump: u8,
+}
+
+impl LegacyAccessController {
+    pub const SPACE: usize = 8 + // account discriminator
+    32 + // admin pubkey
+    1; // bump
+}
+
+#[derive(AnchorDeserialize)]
+pub struct LegacyRole {
+    pub role: String,
+    pub member_count: u64,
+    pub bump: u8,
+}
+
+#[derive(AnchorDeserialize)]
+pub struct LegacyMember {
     pub member: Pubkey,
     pub bump: u8,
 }
 
 impl Space for AccessController {
     const INIT_SPACE: usize = 8 + // account discriminator
+    32 + // creator pubkey
     32 + // admin pubkey
+    1 + 32 + // pending admin pubkey
+    1 + // paused
+    1 + // paused subsystems bitmask
+    1 + // releases allowed
     1; // bump
 }
 
 impl Space for Role {
     const INIT_SPACE: usize = 8 + // account discriminator
-    32 + // role string (expected max size of 32 bytes)
+    1 + // role enum
     8 + // member count
+    8 + // page count
+    1; // bump
+}
+
+impl Space for RoleMembers {
+    const INIT_SPACE: usize = 8 + // account discriminator
+    32 + // role pubkey
+    8 + // page index
+    4 + 32 * ROLE_MEMBERS_PAGE_SIZE + // member pubkeys
     1; // bump
 }
 
 impl Space for Member {
     const INIT_SPACE: usize = 8 + // account discriminator
     32 + // role member pubkey
+    1 + // roles bitmask
+    ROLE_COUNT * (1 + 8) + // expires at of each role
     1; // bump
 }
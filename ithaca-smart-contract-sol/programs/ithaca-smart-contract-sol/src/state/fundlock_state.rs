@@ -1,4 +1,6 @@
 use crate::constants::*;
+use crate::error::FundlockError;
+use crate::math;
 use anchor_lang::prelude::*;
 
 // expect to derive from access_controller account
@@ -9,6 +11,20 @@ pub struct Fundlock {
     pub trade_lock: i64,
     pub release_lock: i64,
     pub bump: u8,
+    // Withdrawals each client can have queued at once
+    pub withdrawal_limit: u64,
+}
+
+// expect to derive from fundlock account, holds the lamports tipped to keepers cranking releases
+#[account]
+pub struct KeeperPool {
+    pub fundlock: Pubkey,
+    // Lamports paid for each crank, while the pool can afford it
+    pub tip: u64,
+    pub bump: u8,
+    // Smallest amount a crank has to release to be tipped, in base units of the released
+    // token, so that splitting withdrawals into dust can't drain the pool
+    pub min_release: u64,
 }
 
 #[account]
@@ -16,9 +32,12 @@ pub struct ClientBalance {
     pub amount: u64,
     pub token: Pubkey,
     pub client: Pubkey,
+    // Keys the balance account, the token account deposited from
     pub client_ata: Pubkey,
     pub collateral_amount: u64,
     pub bump: u8,
+    // Token account cranked releases go to, the client_ata until the client registers another
+    pub destination: Pubkey,
 }
 
 #[account]
@@ -27,6 +46,8 @@ pub struct WithdrawalState {
     pub timestamp: i64,
 }
 
+// The queue is a plain vector as it's borsh (de)serialized as a whole on every access anyway,
+// which the bounded limit keeps cheap next to the shifting done by removals
 #[account]
 pub struct Withdrawals {
     pub withdrawal_queue: Vec<WithdrawalState>,
@@ -35,13 +56,82 @@ pub struct Withdrawals {
     pub bump: u8,
 }
 
+impl Fundlock {
+    // Bounds of the locks, checked when the fundlock is initialized and when governance
+    // changes them
+    pub fn validate_trade_lock(trade_lock: i64) -> Result<()> {
+        require!(trade_lock > 0, FundlockError::InvalidTradeLock);
+        Ok(())
+    }
+
+    pub fn validate_release_lock(release_lock: i64) -> Result<()> {
+        require!(release_lock >= 0, FundlockError::InvalidReleaseLock);
+        require!(
+            release_lock < MAX_RELEASE_LOCK,
+            FundlockError::ReleaseLockTooLong
+        );
+        Ok(())
+    }
+}
+
+// Layout of the fundlocks created before the withdrawal limit was stored, only read by
+// migrate_fundlock
+#[derive(AnchorDeserialize)]
+pub struct LegacyFundlock {
+    pub access_controller: Pubkey,
+    pub token_validator: Pubkey,
+    pub trade_lock: i64,
+    pub release_lock: i64,
+    pub bump: u8,
+}
+
+impl LegacyFundlock {
+    pub const SPACE: usize = 8 + // account discriminator
+    32 + // access controller pubkey
+    32 + // token validator pubkey
+    8 + // trade lock
+    8 + // release lock
+    1; // bump
+}
+
+// Layout of the client balances created before the release destination was stored, only read by
+// migrate_client_balance
+#[derive(AnchorDeserialize)]
+pub struct LegacyClientBalance {
+    pub amount: u64,
+    pub token: Pubkey,
+    pub client: Pubkey,
+    pub client_ata: Pubkey,
+    pub collateral_amount: u64,
+    pub bump: u8,
+}
+
+impl LegacyClientBalance {
+    pub const SPACE: usize = 8 + // account discriminator
+    8 + // amount
+    32 + // token pubkey
+    32 + // client pubkey
+    32 + // client ata pubkey
+    8 + // collateral amount
+    1; // bump
+}
+
 impl Space for Fundlock {
     const INIT_SPACE: usize = 8 + // account discriminator
     32 + // access controller pubkey
     32 + // token validator pubkey
     8 + // trade lock
     8 + // release lock
-    1; // bump
+    1 + // bump
+    8; // withdrawal limit
+}
+
+impl Space for KeeperPool {
+    const INIT_SPACE: usize = 8 + // account discriminator
+    32 + // fundlock pubkey
+    8 + // tip
+    1 + // bump
+    8; // min release
 }
 
 impl Space for ClientBalance {
@@ -51,13 +141,42 @@ impl Space for ClientBalance {
     32 + // client pubkey
     32 + // client ata pubkey
     8 + // collateral amount
-    1; // bump
+    1 + // bump
+    32; // destination pubkey
+}
+
+impl Withdrawals {
+    // Space of the account with room for withdrawal_count queued withdrawals
+    pub const fn space(withdrawal_count: usize) -> usize {
+        8 + // account discriminator
+        4 + (withdrawal_count * 16) + // queued withdrawals
+        8 + // active withdrawals amount
+        32 + // client balance pda
+        8 // bump
+    }
+
+    // Takes every withdrawal past the release lock out of the queue in a single pass, keeping
+    // the order of the others, and returns their sum and count
+    pub fn take_matured(&mut self, release_lock: i64, now: i64) -> Result<(u64, u64)> {
+        let mut released_amount = 0;
+        let mut released_count = 0;
+        let mut pending = Vec::with_capacity(self.withdrawal_queue.len());
+        for withdrawal in self.withdrawal_queue.drain(..) {
+            if math::add_signed(withdrawal.timestamp, release_lock)? < now {
+                released_amount = math::add(released_amount, withdrawal.amount)?;
+                released_count += 1;
+            } else {
+                pending.push(withdrawal);
+            }
+        }
+        self.withdrawal_queue = pending;
+        self.active_withdrawals_amount =
+            math::sub(self.active_withdrawals_amount, released_amount)?;
+        Ok((released_amount, released_count))
+    }
 }
 
 impl Space for Withdrawals {
-    const INIT_SPACE: usize = 8 + // account discriminator
-    4 + (ALLOWED_WITHDRAWAL_LIMIT * 16) + // withdrawals limited to 5 per client
-    8 + // active withdrawals amount
-    32 + // client balance pda
-    8; // bump
+    // Sized for the default limit, withdrawals grow the account past it one entry at a time
+    const INIT_SPACE: usize = Withdrawals::space(DEFAULT_WITHDRAWAL_LIMIT as usize);
 }
@@ -0,0 +1,263 @@
+use crate::constants::{TokenStatus, MAX_WITHDRAWAL_LIMIT};
+use crate::error::GovernanceError;
+use crate::state::{Fundlock, Ledger, WhitelistedToken};
+use anchor_lang::prelude::*;
+
+// expect to derive from AccessController account
+#[account]
+pub struct Governance {
+    pub access_controller: Pubkey,
+    // Minimum time in seconds between scheduling a parameter change and its ETA
+    pub min_delay: i64,
+    pub change_count: u64,
+    pub bump: u8,
+}
+
+// expect to derive from Governance account and the change id
+#[account]
+pub struct ParameterChangeRequest {
+    pub governance: Pubkey,
+    pub change_id: u64,
+    // Fundlock, whitelisted token, ledger or governance account the change applies to
+    pub target: Pubkey,
+    pub change: ParameterChange,
+    // Unix timestamp from which the change can be executed
+    pub eta: i64,
+    // Paid for the request, gets its rent back once executed or cancelled
+    pub proposer: Pubkey,
+    pub bump: u8,
+}
+
+// Every tunable parameter along with its new value
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParameterChange {
+    FundlockTradeLock(i64),
+    FundlockReleaseLock(i64),
+    FundlockWithdrawalLimit(u64),
+    TokenPrecision(u8),
+    TokenStatus(TokenStatus),
+    TokenDepositCap(Option<u64>),
+    TokenClientDepositCap(Option<u64>),
+    TokenMinDeposit(u64),
+    LedgerUnderlyingMultiplier(i64),
+    LedgerStrikeMultiplier(i64),
+    LedgerRentPayer(Pubkey),
+    LedgerInsuranceFund(Pubkey),
+    GovernanceMinDelay(i64),
+}
+
+impl ParameterChange {
+    // Checks that the target belongs to the access controller and can take the new value
+    pub fn validate(&self, target: &AccountInfo, access_controller: &Pubkey) -> Result<()> {
+        match *self {
+            ParameterChange::FundlockTradeLock(trade_lock) => {
+                let fundlock: Fundlock = load(target)?;
+                require_keys_eq!(
+                    fundlock.access_controller,
+                    *access_controller,
+                    GovernanceError::InvalidTarget
+                );
+                Fundlock::validate_trade_lock(trade_lock)?;
+            }
+            ParameterChange::FundlockReleaseLock(release_lock) => {
+                let fundlock: Fundlock = load(target)?;
+                require_keys_eq!(
+                    fundlock.access_controller,
+                    *access_controller,
+                    GovernanceError::InvalidTarget
+                );
+                Fundlock::validate_release_lock(release_lock)?;
+            }
+            ParameterChange::FundlockWithdrawalLimit(withdrawal_limit) => {
+                let fundlock: Fundlock = load(target)?;
+                require_keys_eq!(
+                    fundlock.access_controller,
+                    *access_controller,
+                    GovernanceError::InvalidTarget
+                );
+                // Lowering the limit leaves longer queues as they are until they drain
+                require!(
+                    withdrawal_limit > 0 && withdrawal_limit <= MAX_WITHDRAWAL_LIMIT,
+                    GovernanceError::InvalidParameterValue
+                );
+            }
+            ParameterChange::TokenPrecision(token_precision) => {
+                let whitelisted_token = load_whitelisted_token(target, access_controller)?;
+                require!(
+                    token_precision <= whitelisted_token.token_decimals,
+                    GovernanceError::InvalidParameterValue
+                );
+                require!(
+                    whitelisted_token.ledger_count == 0,
+                    GovernanceError::TokenHasLedgers
+                );
+            }
+            ParameterChange::TokenStatus(_)
+            | ParameterChange::TokenDepositCap(_)
+            | ParameterChange::TokenClientDepositCap(_)
+            | ParameterChange::TokenMinDeposit(_) => {
+                load_whitelisted_token(target, access_controller)?;
+            }
+            ParameterChange::LedgerUnderlyingMultiplier(multiplier)
+            | ParameterChange::LedgerStrikeMultiplier(multiplier) => {
+                let ledger: Ledger = load(target)?;
+                require_keys_eq!(
+                    ledger.access_controller,
+                    *access_controller,
+                    GovernanceError::InvalidTarget
+                );
+                require!(multiplier > 0, GovernanceError::InvalidParameterValue);
+            }
+            ParameterChange::LedgerRentPayer(_) | ParameterChange::LedgerInsuranceFund(_) => {
+                let ledger: Ledger = load(target)?;
+                require_keys_eq!(
+                    ledger.access_controller,
+                    *access_controller,
+                    GovernanceError::InvalidTarget
+                );
+            }
+            ParameterChange::GovernanceMinDelay(min_delay) => {
+                let governance: Governance = load(target)?;
+                require_keys_eq!(
+                    governance.access_controller,
+                    *access_controller,
+                    GovernanceError::InvalidTarget
+                );
+                require!(min_delay >= 0, GovernanceError::InvalidParameterValue);
+            }
+        }
+        Ok(())
+    }
+
+    // Writes the new value into the target, which has been validated when scheduling
+    pub fn apply(&self, target: &AccountInfo) -> Result<()> {
+        match *self {
+            ParameterChange::FundlockTradeLock(trade_lock) => {
+                let mut fundlock: Fundlock = load(target)?;
+                fundlock.trade_lock = trade_lock;
+                store(target, &fundlock)
+            }
+            ParameterChange::FundlockReleaseLock(release_lock) => {
+                let mut fundlock: Fundlock = load(target)?;
+                fundlock.release_lock = release_lock;
+                store(target, &fundlock)
+            }
+            ParameterChange::FundlockWithdrawalLimit(withdrawal_limit) => {
+                let mut fundlock: Fundlock = load(target)?;
+                fundlock.withdrawal_limit = withdrawal_limit;
+                store(target, &fundlock)
+            }
+            ParameterChange::TokenPrecision(token_precision) => {
+                let mut whitelisted_token: WhitelistedToken = load(target)?;
+                // A ledger can have been created since the change was scheduled
+                require!(
+                    whitelisted_token.ledger_count == 0,
+                    GovernanceError::TokenHasLedgers
+                );
+                whitelisted_token.token_precision = token_precision;
+                store(target, &whitelisted_token)
+            }
+            ParameterChange::TokenStatus(status) => {
+                let mut whitelisted_token: WhitelistedToken = load(target)?;
+                whitelisted_token.status = status;
+                store(target, &whitelisted_token)
+            }
+            ParameterChange::TokenDepositCap(deposit_cap) => {
+                let mut whitelisted_token: WhitelistedToken = load(target)?;
+                whitelisted_token.deposit_cap = deposit_cap;
+                store(target, &whitelisted_token)
+            }
+            ParameterChange::TokenClientDepositCap(client_deposit_cap) => {
+                let mut whitelisted_token: WhitelistedToken = load(target)?;
+                whitelisted_token.client_deposit_cap = client_deposit_cap;
+                store(target, &whitelisted_token)
+            }
+            ParameterChange::TokenMinDeposit(min_deposit) => {
+                let mut whitelisted_token: WhitelistedToken = load(target)?;
+                whitelisted_token.min_deposit = min_deposit;
+                store(target, &whitelisted_token)
+            }
+            ParameterChange::LedgerUnderlyingMultiplier(multiplier) => {
+                let mut ledger: Ledger = load(target)?;
+                ledger.underlying_multiplier = multiplier;
+                store(target, &ledger)
+            }
+            ParameterChange::LedgerStrikeMultiplier(multiplier) => {
+                let mut ledger: Ledger = load(target)?;
+                ledger.strike_multiplier = multiplier;
+                store(target, &ledger)
+            }
+            ParameterChange::LedgerRentPayer(rent_payer) => {
+                let mut ledger: Ledger = load(target)?;
+                ledger.rent_payer = rent_payer;
+                store(target, &ledger)
+            }
+            ParameterChange::LedgerInsuranceFund(insurance_fund) => {
+                let mut ledger: Ledger = load(target)?;
+                ledger.insurance_fund = insurance_fund;
+                store(target, &ledger)
+            }
+            ParameterChange::GovernanceMinDelay(min_delay) => {
+                let mut governance: Governance = load(target)?;
+                governance.min_delay = min_delay;
+                store(target, &governance)
+            }
+        }
+    }
+}
+
+// Targets are read and written by hand as their type depends on the change
+fn load<T: AccountDeserialize>(target: &AccountInfo) -> Result<T> {
+    require_keys_eq!(*target.owner, crate::ID, GovernanceError::InvalidTarget);
+    T::try_deserialize(&mut &target.try_borrow_data()?[..])
+}
+
+// Whitelisted tokens don't store their token validator, the target has to be the PDA of the
+// access controller's token validator
+fn load_whitelisted_token(
+    target: &AccountInfo,
+    access_controller: &Pubkey,
+) -> Result<WhitelistedToken> {
+    let whitelisted_token: WhitelistedToken = load(target)?;
+    let (token_validator, _) = Pubkey::find_program_address(
+        &[b"token_validator", access_controller.as_ref()],
+        &crate::ID,
+    );
+    let (expected_target, _) = Pubkey::find_program_address(
+        &[
+            b"whitelisted_token",
+            token_validator.as_ref(),
+            whitelisted_token.token_mint.as_ref(),
+        ],
+        &crate::ID,
+    );
+    require_keys_eq!(
+        target.key(),
+        expected_target,
+        GovernanceError::InvalidTarget
+    );
+    Ok(whitelisted_token)
+}
+
+fn store<T: AccountSerialize>(target: &AccountInfo, account: &T) -> Result<()> {
+    account.try_serialize(&mut &mut target.try_borrow_mut_data()?[..])
+}
+
+impl Space for Governance {
+    const INIT_SPACE: usize = 8 + // account discriminator
+    32 + // access controller pubkey
+    8 + // min delay
+    8 + // change count
+    1; // bump
+}
+
+impl Space for ParameterChangeRequest {
+    const INIT_SPACE: usize = 8 + // account discriminator
+    32 + // governance pubkey
+    8 + // change id
+    32 + // target pubkey
+    1 + 32 + // change, sized for its largest variant
+    8 + // eta
+    32 + // proposer pubkey
+    1; // bump
+}
@@ -1,3 +1,6 @@
+use crate::constants::*;
+use crate::error::LedgerError;
+use crate::math;
 use anchor_lang::prelude::*;
 
 // expect to derive from access_controller account
@@ -10,6 +13,14 @@ pub struct Ledger {
     pub strike_token: Pubkey,
     pub underlying_multiplier: i64,
     pub strike_multiplier: i64,
+    // Highest backend id processed by this ledger
+    pub last_backend_id: u64,
+    // The BACKEND_ID_WINDOW highest backend ids processed, used to reject replayed batches
+    pub recent_backend_ids: Vec<u64>,
+    // Receives the rent of closed contract and position accounts
+    pub rent_payer: Pubkey,
+    // Client whose balances are credited with the funds seized by liquidations
+    pub insurance_fund: Pubkey,
     pub bump: u8,
 }
 
@@ -17,16 +28,48 @@ pub struct Ledger {
 #[account]
 pub struct Contract {
     pub contract_id: u64,
+    // Unix timestamp after which positions can no longer change and the contract can be settled
+    pub expiry: i64,
+    pub strike: u64,
+    pub payoff_type: PayoffType,
+    pub status: ContractStatus,
+    // Price of the underlying at expiry, zero until the contract is settled
+    pub settlement_price: u64,
+    // Amount of open position accounts, the contract can only be closed once there are none
+    pub position_count: u64,
+    // Sums of the long and of the short net positions held in the contract
+    pub long_open_interest: u64,
+    pub short_open_interest: u64,
     pub bump: u8,
 }
 
+// Left in place of a closed contract, so that its id can't be reused
+#[account]
+pub struct ClosedContract {
+    pub contract_id: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PayoffType {
+    Call,
+    Put,
+    Forward,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContractStatus {
+    Active,
+    Settled,
+}
+
 // Unique per contract and client
 // Client pubkey X
 #[account]
 pub struct Position {
     pub contract_id: u64,
     pub client: Pubkey,
-    pub size: u64,
+    // Net position of the client, positive when long and negative when short
+    pub size: i64,
     pub bump: u8,
 }
 
@@ -35,7 +78,17 @@ pub struct Position {
 pub struct PositionsParam {
     pub contract_id: u64,
     pub client: Pubkey,
-    pub size: u64,
+    // Signed change netted into the client's position, positive to buy and negative to sell
+    pub size: i64,
+}
+
+// Used as a parameter only, metadata of the contracts created by a positions batch
+#[account]
+pub struct ContractParams {
+    pub contract_id: u64,
+    pub expiry: i64,
+    pub strike: u64,
+    pub payoff_type: PayoffType,
 }
 
 #[account]
@@ -51,6 +104,132 @@ pub struct FundMovementParamOptimized {
     pub strike_amount: i64,
 }
 
+impl Ledger {
+    // Records a backend id of a processed batch, rejecting ids that were already processed.
+    // Only the highest ids are remembered, so an id lower than every remembered one is
+    // rejected as stale since it can't be told apart from an already evicted one.
+    pub fn record_backend_id(&mut self, backend_id: u64) -> Result<()> {
+        require!(
+            !self.recent_backend_ids.contains(&backend_id),
+            LedgerError::DuplicateBackendId
+        );
+
+        if self.recent_backend_ids.len() >= BACKEND_ID_WINDOW {
+            if let Some((oldest_index, oldest_id)) = self
+                .recent_backend_ids
+                .iter()
+                .copied()
+                .enumerate()
+                .min_by_key(|(_, id)| *id)
+            {
+                require!(backend_id > oldest_id, LedgerError::StaleBackendId);
+                self.recent_backend_ids.swap_remove(oldest_index);
+            }
+        }
+
+        self.recent_backend_ids.push(backend_id);
+        self.last_backend_id = self.last_backend_id.max(backend_id);
+        Ok(())
+    }
+}
+
+impl Contract {
+    pub fn new(params: &ContractParams, bump: u8) -> Self {
+        Self {
+            contract_id: params.contract_id,
+            expiry: params.expiry,
+            strike: params.strike,
+            payoff_type: params.payoff_type,
+            status: ContractStatus::Active,
+            settlement_price: 0,
+            position_count: 0,
+            long_open_interest: 0,
+            short_open_interest: 0,
+            bump,
+        }
+    }
+
+    // Positions can only change while the contract is neither settled nor expired
+    pub fn require_tradable(&self, now: i64) -> Result<()> {
+        require!(
+            self.status == ContractStatus::Active,
+            LedgerError::ContractSettled
+        );
+        require!(now < self.expiry, LedgerError::ContractExpired);
+        Ok(())
+    }
+
+    pub fn is_settled(&self) -> bool {
+        self.status == ContractStatus::Settled
+    }
+
+    // Moves a position's share of the open interest from its previous to its new net size
+    pub fn update_open_interest(&mut self, previous_size: i64, size: i64) -> Result<()> {
+        self.long_open_interest = math::add(
+            math::sub(self.long_open_interest, long_part(previous_size))?,
+            long_part(size),
+        )?;
+        self.short_open_interest = math::add(
+            math::sub(self.short_open_interest, short_part(previous_size))?,
+            short_part(size),
+        )?;
+        Ok(())
+    }
+}
+
+fn long_part(size: i64) -> u64 {
+    if size > 0 {
+        size.unsigned_abs()
+    } else {
+        0
+    }
+}
+
+fn short_part(size: i64) -> u64 {
+    if size < 0 {
+        size.unsigned_abs()
+    } else {
+        0
+    }
+}
+
+impl ContractParams {
+    pub fn validate(&self, now: i64) -> Result<()> {
+        require!(now < self.expiry, LedgerError::ContractExpired);
+        // Forwards have no strike, options always do
+        require!(
+            self.payoff_type == PayoffType::Forward || self.strike > 0,
+            LedgerError::InvalidContractParams
+        );
+        Ok(())
+    }
+}
+
+// Layout of the ledgers created before backend ids were remembered, only read by migrate_ledger
+#[derive(AnchorDeserialize)]
+pub struct LegacyLedger {
+    pub access_controller: Pubkey,
+    pub token_validator: Pubkey,
+    pub fundlock: Pubkey,
+    pub underlying_token: Pubkey,
+    pub strike_token: Pubkey,
+    pub underlying_multiplier: i64,
+    pub strike_multiplier: i64,
+    pub bump: u8,
+}
+
+impl LegacyLedger {
+    pub const SPACE: usize = 8 + // account discriminator
+    32 + // access_controller
+    32 + // token_validator
+    32 + // fundlock
+    32 + // underlying_token
+    32 + // strike_token
+    8 + // underlying_multiplier
+    8 + // strike_multiplier
+    1; // bump
+}
+
 impl Space for Ledger {
     const INIT_SPACE: usize = 8 + // account discriminator
     32 + // access_controller
@@ -60,15 +239,32 @@ impl Space for Ledger {
     32 + // strike_token
     8 + // underlying_multiplier
     8 + // strike_multiplier
+    8 + // last_backend_id
+    4 + (BACKEND_ID_WINDOW * 8) + // recent_backend_ids
+    32 + // rent_payer
+    32 + // insurance_fund
     1; // bump
 }
 
 impl Space for Contract {
     const INIT_SPACE: usize = 8 + // account discriminator
     8 + // contract_id
+    8 + // expiry
+    8 + // strike
+    1 + // payoff_type
+    1 + // status
+    8 + // settlement_price
+    8 + // position_count
+    8 + // long_open_interest
+    8 + // short_open_interest
     1; // bump
 }
 
+impl Space for ClosedContract {
+    const INIT_SPACE: usize = 8 + // account discriminator
+    8; // contract_id
+}
+
 impl Space for Position {
     const INIT_SPACE: usize = 8 + // account discriminator
     8 + // contract_id
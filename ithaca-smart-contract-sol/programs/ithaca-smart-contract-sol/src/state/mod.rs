@@ -8,4 +8,10 @@ pub mod fundlock_state;
 pub use fundlock_state::*;
 
 pub mod ledger_state;
-pub use ledger_state::*;
\ No newline at end of file
+pub use ledger_state::*;
+
+pub mod multisig_state;
+pub use multisig_state::*;
+
+pub mod governance_state;
+pub use governance_state::*;
@@ -0,0 +1,100 @@
+use crate::constants::MAX_MULTISIG_SIGNERS;
+use crate::error::MultisigError;
+use anchor_lang::prelude::*;
+
+// expect to derive from AccessController account
+#[account]
+pub struct Multisig {
+    pub access_controller: Pubkey,
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+    pub proposal_count: u64,
+    // Bumped whenever the signers change so that pending proposals can't be executed anymore
+    pub signers_version: u64,
+    pub bump: u8,
+    // Bump of the multisig authority, the data-less PDA signing the approved instructions
+    pub authority_bump: u8,
+}
+
+// expect to derive from Multisig account and the proposal id
+#[account]
+pub struct Proposal {
+    pub multisig: Pubkey,
+    pub proposal_id: u64,
+    pub proposer: Pubkey,
+    pub signers_version: u64,
+    // Instruction of this program signed by the multisig authority once executed
+    pub accounts: Vec<ProposalAccount>,
+    pub data: Vec<u8>,
+    pub approvals: Vec<Pubkey>,
+    pub executed: bool,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProposalAccount {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl Multisig {
+    pub fn validate_signers(signers: &[Pubkey], threshold: u8) -> Result<()> {
+        require!(
+            signers.len() <= MAX_MULTISIG_SIGNERS,
+            MultisigError::TooManySigners
+        );
+        require!(
+            threshold > 0 && threshold as usize <= signers.len(),
+            MultisigError::InvalidThreshold
+        );
+        for (i, signer) in signers.iter().enumerate() {
+            require!(
+                !signers[..i].contains(signer),
+                MultisigError::DuplicateSigner
+            );
+        }
+        Ok(())
+    }
+
+    pub fn is_signer(&self, key: &Pubkey) -> bool {
+        self.signers.contains(key)
+    }
+}
+
+impl Proposal {
+    pub fn space(accounts: &[ProposalAccount], data: &[u8]) -> usize {
+        Self::INIT_SPACE + accounts.len() * ProposalAccount::SPACE + data.len()
+    }
+}
+
+impl ProposalAccount {
+    pub const SPACE: usize = 32 + // pubkey
+    1 + // is_signer
+    1; // is_writable
+}
+
+impl Space for Multisig {
+    const INIT_SPACE: usize = 8 + // account discriminator
+    32 + // access_controller
+    4 + (MAX_MULTISIG_SIGNERS * 32) + // signers
+    1 + // threshold
+    8 + // proposal_count
+    8 + // signers_version
+    1 + // bump
+    1; // authority_bump
+}
+
+// Excludes the proposed instruction, see Proposal::space
+impl Space for Proposal {
+    const INIT_SPACE: usize = 8 + // account discriminator
+    32 + // multisig
+    8 + // proposal_id
+    32 + // proposer
+    8 + // signers_version
+    4 + // accounts length
+    4 + // data length
+    4 + (MAX_MULTISIG_SIGNERS * 32) + // approvals
+    1 + // executed
+    1; // bump
+}
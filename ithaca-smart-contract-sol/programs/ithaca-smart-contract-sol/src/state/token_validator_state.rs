@@ -1,3 +1,5 @@
+use crate::error::TokenValidatorError;
+use crate::TokenStatus;
 use anchor_lang::prelude::*;
 
 // expect to derive from access_controller account
@@ -16,6 +18,90 @@ pub struct WhitelistedToken {
     // Precision from backend
     pub token_precision: u8,
     pub bump: u8,
+    pub status: TokenStatus,
+    // Caps on the tokens held by the fundlock and by each client balance, none meaning
+    // uncapped. Client balances are keyed by the deposit token account, or the client's
+    // address for native SOL, so a client with several of them gets the client cap on each
+    pub deposit_cap: Option<u64>,
+    pub client_deposit_cap: Option<u64>,
+    pub min_deposit: u64,
+    // Deposited minus released, checked against the deposit cap
+    pub total_deposits: u64,
+    // Ledgers of the token, whose multipliers were derived from its precision
+    pub ledger_count: u64,
+}
+
+impl WhitelistedToken {
+    // Checks a deposit of amount, of which received reached the vault, against the token config,
+    // client_amount being the balance the deposit is credited to
+    pub fn check_deposit(&self, amount: u64, received: u64, client_amount: u64) -> Result<()> {
+        require!(
+            self.status == TokenStatus::Active,
+            TokenValidatorError::DepositsDisabled
+        );
+        require!(
+            amount >= self.min_deposit,
+            TokenValidatorError::DepositBelowMinimum
+        );
+        if let Some(deposit_cap) = self.deposit_cap {
+            require!(
+                self.total_deposits.saturating_add(received) <= deposit_cap,
+                TokenValidatorError::DepositCapExceeded
+            );
+        }
+        if let Some(client_deposit_cap) = self.client_deposit_cap {
+            require!(
+                client_amount.saturating_add(received) <= client_deposit_cap,
+                TokenValidatorError::ClientDepositCapExceeded
+            );
+        }
+        Ok(())
+    }
+
+    pub fn is_tradable(&self) -> bool {
+        self.status != TokenStatus::WithdrawOnly
+    }
+
+    // Whether the config is at least as strict as the current one on every field, only such
+    // a config can be set without going through governance
+    pub fn is_tightened_by(
+        &self,
+        status: TokenStatus,
+        deposit_cap: Option<u64>,
+        client_deposit_cap: Option<u64>,
+        min_deposit: u64,
+    ) -> bool {
+        status as u8 >= self.status as u8
+            && is_cap_tightened(self.deposit_cap, deposit_cap)
+            && is_cap_tightened(self.client_deposit_cap, client_deposit_cap)
+            && min_deposit >= self.min_deposit
+    }
+}
+
+fn is_cap_tightened(current: Option<u64>, cap: Option<u64>) -> bool {
+    match (current, cap) {
+        (_, None) => current.is_none(),
+        (None, Some(_)) => true,
+        (Some(current), Some(cap)) => cap <= current,
+    }
+}
+
+// Layout of the whitelisted tokens created before tokens had a config, only read by
+// migrate_whitelisted_token
+#[derive(AnchorDeserialize)]
+pub struct LegacyWhitelistedToken {
+    pub token_mint: Pubkey,
+    pub token_decimals: u8,
+    pub token_precision: u8,
+    pub bump: u8,
+}
+
+impl LegacyWhitelistedToken {
+    pub const SPACE: usize = 8 + // account discriminator
+    32 + // token mint pubkey
+    1 + // token decimals
+    1 + // token precision
+    1; // bump
 }
 
 impl Space for TokenValidator {
@@ -29,5 +115,11 @@ impl Space for WhitelistedToken {
     32 + // token mint pubkey
     1 + // token decimals
     1 + // token precision
-    1; // bump
+    1 + // bump
+    1 + // status
+    1 + 8 + // deposit cap
+    1 + 8 + // client deposit cap
+    8 + // min deposit
+    8 + // total deposits
+    8; // ledger count
 }
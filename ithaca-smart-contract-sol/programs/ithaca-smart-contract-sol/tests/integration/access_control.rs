@@ -0,0 +1,750 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::{
+    instruction::{AccountMeta, Instruction},
+    system_instruction,
+};
+use anchor_lang::{system_program, AnchorSerialize, Discriminator};
+use ithaca_client::pda;
+use ithaca_smart_contract_sol::error::AccessControlError;
+use ithaca_smart_contract_sol::{
+    accounts, instruction, AccessController, Member, Role, RoleMembers, Roles, Subsystem,
+    WhitelistedToken, ID, ROLE_MEMBERS_PAGE_SIZE,
+};
+use solana_sdk::signature::{Keypair, Signer};
+
+use crate::env::{error_code, ix, TestEnv};
+use crate::fake_partner;
+
+#[tokio::test]
+async fn init_access_controller_makes_the_signer_admin() {
+    let mut env = TestEnv::new().await;
+
+    let access_controller: AccessController = env.fetch(env.access_controller).await;
+    assert_eq!(access_controller.creator, env.admin.pubkey());
+    assert_eq!(access_controller.admin, env.admin.pubkey());
+    assert_eq!(access_controller.pending_admin, None);
+
+    let admin_role = env.role(Roles::Admin);
+    let role: Role = env.fetch(admin_role).await;
+    assert_eq!(role.role, Roles::Admin);
+    assert_eq!(role.member_count, 1);
+
+    let member: Member = env.fetch(env.member(&env.admin.pubkey())).await;
+    assert_eq!(member.member, env.admin.pubkey());
+    assert_eq!(member.roles, Roles::Admin.bit());
+}
+
+#[tokio::test]
+async fn granted_role_can_be_checked_and_renounced() {
+    let mut env = TestEnv::new().await;
+    let utility_account = Keypair::new().pubkey();
+    let utility_role = env.role(Roles::UtilityAccount);
+    let utility_member = env.member(&utility_account);
+
+    env.grant_role(Roles::UtilityAccount, utility_account).await;
+    let role: Role = env.fetch(utility_role).await;
+    assert_eq!(role.member_count, 1);
+
+    assert!(env.has_role(Roles::UtilityAccount, utility_account).await);
+
+    // The last member of a role can't leave it
+    let admin = env.admin.pubkey();
+    let renounce_role_ix =
+        renounce_role_ix(&env, &admin, Roles::UtilityAccount, utility_account, 0);
+    let err = env
+        .process(std::slice::from_ref(&renounce_role_ix), &[])
+        .await
+        .unwrap_err();
+    assert_eq!(error_code(err), u32::from(AccessControlError::LastMember));
+
+    let other_utility_account = Keypair::new().pubkey();
+    env.grant_role(Roles::UtilityAccount, other_utility_account)
+        .await;
+    env.process(&[renounce_role_ix], &[]).await.unwrap();
+
+    let role: Role = env.fetch(utility_role).await;
+    assert_eq!(role.member_count, 1);
+    let role_members: RoleMembers = env.fetch(pda::role_members(&utility_role, 0).0).await;
+    assert_eq!(role_members.members, vec![other_utility_account]);
+    assert!(!env.exists(utility_member).await);
+    assert!(!env.has_role(Roles::UtilityAccount, utility_account).await);
+}
+
+#[tokio::test]
+async fn only_the_admin_can_grant_roles() {
+    let mut env = TestEnv::new().await;
+    let impostor = Keypair::new();
+    let utility_role = env.role(Roles::UtilityAccount);
+
+    let grant_role_ix = ix(
+        accounts::GrantRole {
+            admin: impostor.pubkey(),
+            access_controller: env.access_controller,
+            role: utility_role,
+            member: env.member(&impostor.pubkey()),
+            role_members: pda::role_members(&utility_role, 0).0,
+            system_program: system_program::ID,
+        },
+        instruction::GrantRole {
+            role_granted: Roles::UtilityAccount,
+            new_member: impostor.pubkey(),
+            page_index: 0,
+            expires_at: None,
+        },
+    );
+    assert!(env.process(&[grant_role_ix], &[&impostor]).await.is_err());
+    assert!(!env.exists(utility_role).await);
+}
+
+#[tokio::test]
+async fn one_member_account_holds_several_roles() {
+    let mut env = TestEnv::new().await;
+    let account = Keypair::new().pubkey();
+
+    env.grant_role(Roles::UtilityAccount, account).await;
+    env.grant_role(Roles::Liquidator, account).await;
+    let member: Member = env.fetch(env.member(&account)).await;
+    assert_eq!(
+        member.roles,
+        Roles::UtilityAccount.bit() | Roles::Liquidator.bit()
+    );
+
+    let grant_role_ix = env.grant_role_ix(Roles::Liquidator, account);
+    let err = env.process(&[grant_role_ix], &[]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(AccessControlError::RoleAlreadyGranted)
+    );
+
+    assert!(env.has_role(Roles::Liquidator, account).await);
+    assert!(!env.has_role(Roles::Admin, account).await);
+
+    // Renouncing one role keeps the others
+    env.grant_role(Roles::Liquidator, Keypair::new().pubkey())
+        .await;
+    let admin = env.admin.pubkey();
+    let renounce_role_ix = renounce_role_ix(&env, &admin, Roles::Liquidator, account, 0);
+    env.process(&[renounce_role_ix], &[]).await.unwrap();
+    let member: Member = env.fetch(env.member(&account)).await;
+    assert_eq!(member.roles, Roles::UtilityAccount.bit());
+    let role: Role = env.fetch(env.role(Roles::Liquidator)).await;
+    assert_eq!(role.member_count, 1);
+}
+
+// Writes the access controller, a role and a member account with the layouts used before
+// roles were typed
+async fn set_legacy_accounts(env: &mut TestEnv, role: Roles, member: Pubkey) {
+    let (role_address, role_bump) = Pubkey::find_program_address(
+        &[
+            b"role",
+            env.access_controller.as_ref(),
+            role.as_str().as_bytes(),
+        ],
+        &ID,
+    );
+    let mut role_data = Role::DISCRIMINATOR.to_vec();
+    role_data.extend(
+        (role.as_str().to_string(), 1u64, role_bump)
+            .try_to_vec()
+            .unwrap(),
+    );
+    let (member_address, member_bump) =
+        Pubkey::find_program_address(&[b"member", role_address.as_ref(), member.as_ref()], &ID);
+    let mut member_data = Member::DISCRIMINATOR.to_vec();
+    member_data.extend((member, member_bump).try_to_vec().unwrap());
+
+    for (address, mut data) in [(role_address, role_data), (member_address, member_data)] {
+        // Sized as the legacy accounts were allocated
+        data.resize(data.len().max(49), 0);
+        env.set_program_account(&address, &data);
+    }
+
+    // The legacy access controller only stored its admin
+    let (access_controller, access_controller_bump) = pda::access_controller(&env.admin.pubkey());
+    let mut access_controller_data = AccessController::DISCRIMINATOR.to_vec();
+    access_controller_data.extend(
+        (env.admin.pubkey(), access_controller_bump)
+            .try_to_vec()
+            .unwrap(),
+    );
+    env.set_program_account(&access_controller, &access_controller_data);
+}
+
+fn migrate_access_controller_ix(env: &TestEnv) -> Instruction {
+    ix(
+        accounts::MigrateAccessController {
+            admin: env.admin.pubkey(),
+            access_controller: env.access_controller,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateAccessController {},
+    )
+}
+
+fn migrate_role_ix(env: &TestEnv, role: Roles) -> Instruction {
+    ix(
+        accounts::MigrateRole {
+            admin: env.admin.pubkey(),
+            access_controller: env.access_controller,
+            role: env.role(role),
+        },
+        instruction::MigrateRole {
+            role_migrated: role,
+        },
+    )
+}
+
+fn migrate_member_ix(env: &TestEnv, role: Roles, member: Pubkey) -> Instruction {
+    ix(
+        accounts::MigrateMember {
+            admin: env.admin.pubkey(),
+            access_controller: env.access_controller,
+            role: env.role(role),
+            legacy_member: pda::legacy_member(&env.role(role), &member).0,
+            member: env.member(&member),
+            role_members: pda::role_members(&env.role(role), 0).0,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateMember {
+            role_migrated: role,
+            member_pk: member,
+            page_index: 0,
+        },
+    )
+}
+
+#[tokio::test]
+async fn legacy_access_control_accounts_are_migrated() {
+    let mut env = TestEnv::new().await;
+    let liquidator = Keypair::new().pubkey();
+    env.grant_role(Roles::UtilityAccount, liquidator).await;
+    set_legacy_accounts(&mut env, Roles::Liquidator, liquidator).await;
+
+    // Roles can only be migrated once the access controller is
+    let migrate_role_ix = migrate_role_ix(&env, Roles::Liquidator);
+    assert!(env
+        .process(std::slice::from_ref(&migrate_role_ix), &[])
+        .await
+        .is_err());
+
+    let migrate_access_controller_ix = migrate_access_controller_ix(&env);
+    env.process(std::slice::from_ref(&migrate_access_controller_ix), &[])
+        .await
+        .unwrap();
+    let access_controller: AccessController = env.fetch(env.access_controller).await;
+    assert_eq!(access_controller.creator, env.admin.pubkey());
+    assert_eq!(access_controller.admin, env.admin.pubkey());
+    assert_eq!(access_controller.pending_admin, None);
+    assert!(!access_controller.paused);
+
+    // Members can only be migrated once their role is
+    let migrate_member_ix = migrate_member_ix(&env, Roles::Liquidator, liquidator);
+    assert!(env
+        .process(std::slice::from_ref(&migrate_member_ix), &[])
+        .await
+        .is_err());
+
+    env.process(std::slice::from_ref(&migrate_role_ix), &[])
+        .await
+        .unwrap();
+    let role: Role = env.fetch(env.role(Roles::Liquidator)).await;
+    assert_eq!(role.role, Roles::Liquidator);
+    assert_eq!(role.member_count, 1);
+    assert_eq!(role.page_count, 0);
+
+    let legacy_member = pda::legacy_member(&env.role(Roles::Liquidator), &liquidator).0;
+    env.process(std::slice::from_ref(&migrate_member_ix), &[])
+        .await
+        .unwrap();
+    assert!(!env.exists(legacy_member).await);
+    let member: Member = env.fetch(env.member(&liquidator)).await;
+    assert_eq!(
+        member.roles,
+        Roles::UtilityAccount.bit() | Roles::Liquidator.bit()
+    );
+    assert!(env.has_role(Roles::Liquidator, liquidator).await);
+    let role_members: RoleMembers = env
+        .fetch(pda::role_members(&env.role(Roles::Liquidator), 0).0)
+        .await;
+    assert_eq!(role_members.members, vec![liquidator]);
+
+    // Migrated accounts can't be migrated again
+    let err = env
+        .process(&[migrate_access_controller_ix], &[])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(AccessControlError::AlreadyMigrated)
+    );
+    let err = env.process(&[migrate_role_ix], &[]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(AccessControlError::AlreadyMigrated)
+    );
+    assert!(env.process(&[migrate_member_ix], &[]).await.is_err());
+}
+
+fn propose_admin_ix(env: &TestEnv, admin: &Pubkey, new_admin: Pubkey) -> Instruction {
+    ix(
+        accounts::ProposeAdmin {
+            admin: *admin,
+            access_controller: env.access_controller,
+        },
+        instruction::ProposeAdmin { new_admin },
+    )
+}
+
+fn accept_admin_ix(env: &TestEnv, previous_admin: &Pubkey, new_admin: &Pubkey) -> Instruction {
+    let admin_role = env.role(Roles::Admin);
+    ix(
+        accounts::AcceptAdmin {
+            new_admin: *new_admin,
+            previous_admin: *previous_admin,
+            access_controller: env.access_controller,
+            role: admin_role,
+            previous_admin_member: env.member(previous_admin),
+            new_admin_member: env.member(new_admin),
+            role_members: pda::role_members(&admin_role, 0).0,
+            system_program: system_program::ID,
+        },
+        instruction::AcceptAdmin { page_index: 0 },
+    )
+}
+
+#[tokio::test]
+async fn role_expiry_is_kept_per_role() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+    let member = Keypair::new();
+    let transfer_ix = system_instruction::transfer(&admin, &member.pubkey(), 1_000_000_000);
+    env.process(&[transfer_ix], &[]).await.unwrap();
+    let expires_at = env.now().await + 60;
+
+    let grant_role_ix =
+        env.grant_role_in_page_ix(Roles::UtilityAccount, member.pubkey(), 0, Some(expires_at));
+    env.process(&[grant_role_ix], &[]).await.unwrap();
+
+    // Granting another role doesn't lift the expiry of the first one
+    env.grant_role(Roles::Liquidator, member.pubkey()).await;
+    let member_account: Member = env.fetch(env.member(&member.pubkey())).await;
+    assert_eq!(
+        member_account.expires_at[Roles::UtilityAccount.index()],
+        Some(expires_at)
+    );
+    assert_eq!(member_account.expires_at[Roles::Liquidator.index()], None);
+
+    // Nor does becoming the admin
+    let propose_ix = propose_admin_ix(&env, &admin, member.pubkey());
+    env.process(&[propose_ix], &[]).await.unwrap();
+    let accept_ix = accept_admin_ix(&env, &admin, &member.pubkey());
+    env.process(&[accept_ix], &[&member]).await.unwrap();
+
+    env.advance_clock(60).await;
+    assert!(!env.has_role(Roles::UtilityAccount, member.pubkey()).await);
+    assert!(env.has_role(Roles::Liquidator, member.pubkey()).await);
+    assert!(env.has_role(Roles::Admin, member.pubkey()).await);
+}
+
+#[tokio::test]
+async fn admin_is_transferred_in_two_steps() {
+    let mut env = TestEnv::new().await;
+    let previous_admin = env.admin.pubkey();
+    let new_admin = Keypair::new();
+    let transfer_ix =
+        system_instruction::transfer(&previous_admin, &new_admin.pubkey(), 1_000_000_000);
+    env.process(&[transfer_ix], &[]).await.unwrap();
+
+    let accept_ix = accept_admin_ix(&env, &previous_admin, &new_admin.pubkey());
+    let err = env
+        .process(std::slice::from_ref(&accept_ix), &[&new_admin])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(AccessControlError::UnauthorizedPendingAdmin)
+    );
+
+    let propose_ix = propose_admin_ix(&env, &new_admin.pubkey(), new_admin.pubkey());
+    let err = env.process(&[propose_ix], &[&new_admin]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(AccessControlError::UnauthorizedAdmin)
+    );
+
+    let propose_ix = propose_admin_ix(&env, &previous_admin, new_admin.pubkey());
+    env.process(&[propose_ix], &[]).await.unwrap();
+    let access_controller: AccessController = env.fetch(env.access_controller).await;
+    assert_eq!(access_controller.admin, previous_admin);
+    assert_eq!(access_controller.pending_admin, Some(new_admin.pubkey()));
+
+    env.process(&[accept_ix], &[&new_admin]).await.unwrap();
+    let access_controller: AccessController = env.fetch(env.access_controller).await;
+    assert_eq!(access_controller.creator, previous_admin);
+    assert_eq!(access_controller.admin, new_admin.pubkey());
+    assert_eq!(access_controller.pending_admin, None);
+
+    // The admin role membership moved to the new admin
+    let admin_role = env.role(Roles::Admin);
+    let role: Role = env.fetch(admin_role).await;
+    assert_eq!(role.member_count, 1);
+    assert!(!env.exists(env.member(&previous_admin)).await);
+    assert!(env.exists(env.member(&new_admin.pubkey())).await);
+    let role_members: RoleMembers = env.fetch(pda::role_members(&admin_role, 0).0).await;
+    assert_eq!(role_members.members, vec![new_admin.pubkey()]);
+
+    let grant_role_ix = env.grant_role_ix(Roles::Liquidator, Keypair::new().pubkey());
+    let err = env.process(&[grant_role_ix], &[]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(AccessControlError::UnauthorizedAdmin)
+    );
+
+    // PDAs derived from the access controller keep working with the new admin
+    let mint = env.create_mint(6, &previous_admin).await;
+    let whitelisted_token = pda::whitelisted_token(&env.token_validator, &mint).0;
+    let whitelist_token_ix = ix(
+        accounts::AddTokenToWhitelist {
+            admin: new_admin.pubkey(),
+            access_controller: env.access_controller,
+            role: admin_role,
+            member: env.member(&new_admin.pubkey()),
+            token_validator: env.token_validator,
+            new_token_to_whitelist: mint,
+            whitelisted_token,
+            system_program: system_program::ID,
+        },
+        instruction::AddTokenToWhitelist {
+            token_precision: 2,
+            allow_dangerous_extensions: false,
+        },
+    );
+    env.process(&[whitelist_token_ix], &[&new_admin])
+        .await
+        .unwrap();
+    let token: WhitelistedToken = env.fetch(whitelisted_token).await;
+    assert_eq!(token.token_mint, mint);
+}
+
+fn renounce_role_ix(
+    env: &TestEnv,
+    authority: &Pubkey,
+    role: Roles,
+    member: Pubkey,
+    page_index: u64,
+) -> Instruction {
+    ix(
+        accounts::RenounceRole {
+            authority: *authority,
+            admin: env.admin.pubkey(),
+            access_controller: env.access_controller,
+            role: env.role(role),
+            member: env.member(&member),
+            role_members: pda::role_members(&env.role(role), page_index).0,
+            system_program: system_program::ID,
+        },
+        instruction::RenounceRole {
+            role_renounced: role,
+            member_pk: member,
+            page_index,
+        },
+    )
+}
+
+#[tokio::test]
+async fn role_members_are_listed_in_pages() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+    let liquidator_role = env.role(Roles::Liquidator);
+    let liquidators: Vec<Pubkey> = (0..=ROLE_MEMBERS_PAGE_SIZE)
+        .map(|_| Keypair::new().pubkey())
+        .collect();
+
+    // Pages are created in order
+    let grant_role_ix = env.grant_role_in_page_ix(Roles::Liquidator, liquidators[0], 1, None);
+    let err = env.process(&[grant_role_ix], &[]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(AccessControlError::InvalidRoleMembersPage)
+    );
+
+    for chunk in liquidators[..ROLE_MEMBERS_PAGE_SIZE].chunks(4) {
+        let grant_role_ixs: Vec<_> = chunk
+            .iter()
+            .map(|liquidator| env.grant_role_ix(Roles::Liquidator, *liquidator))
+            .collect();
+        env.process(&grant_role_ixs, &[]).await.unwrap();
+    }
+    let last_liquidator = liquidators[ROLE_MEMBERS_PAGE_SIZE];
+    let grant_role_ix = env.grant_role_ix(Roles::Liquidator, last_liquidator);
+    let err = env.process(&[grant_role_ix], &[]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(AccessControlError::RoleMembersPageFull)
+    );
+    let grant_role_ix = env.grant_role_in_page_ix(Roles::Liquidator, last_liquidator, 1, None);
+    env.process(&[grant_role_ix], &[]).await.unwrap();
+
+    let role: Role = env.fetch(liquidator_role).await;
+    assert_eq!(role.member_count, liquidators.len() as u64);
+    assert_eq!(role.page_count, 2);
+    let first_page: RoleMembers = env.fetch(pda::role_members(&liquidator_role, 0).0).await;
+    let second_page: RoleMembers = env.fetch(pda::role_members(&liquidator_role, 1).0).await;
+    assert_eq!(first_page.members, liquidators[..ROLE_MEMBERS_PAGE_SIZE]);
+    assert_eq!(second_page.page_index, 1);
+    assert_eq!(second_page.members, vec![last_liquidator]);
+
+    // A member is renounced from the page listing it, which frees a slot
+    let err = env
+        .process(
+            &[renounce_role_ix(
+                &env,
+                &admin,
+                Roles::Liquidator,
+                liquidators[0],
+                1,
+            )],
+            &[],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(AccessControlError::MemberNotListed)
+    );
+    env.process(
+        &[renounce_role_ix(
+            &env,
+            &admin,
+            Roles::Liquidator,
+            liquidators[0],
+            0,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let first_page: RoleMembers = env.fetch(pda::role_members(&liquidator_role, 0).0).await;
+    assert_eq!(first_page.members.len(), ROLE_MEMBERS_PAGE_SIZE - 1);
+    assert!(!first_page.members.contains(&liquidators[0]));
+    env.grant_role(Roles::Liquidator, liquidators[0]).await;
+}
+
+#[tokio::test]
+async fn members_can_renounce_their_own_roles() {
+    let mut env = TestEnv::new().await;
+    let utility_account = Keypair::new();
+    let outsider = Keypair::new();
+    env.grant_role(Roles::UtilityAccount, utility_account.pubkey())
+        .await;
+    env.grant_role(Roles::UtilityAccount, outsider.pubkey())
+        .await;
+    let admin_lamports = env.lamports(env.admin.pubkey()).await;
+
+    let renounce_ix = renounce_role_ix(
+        &env,
+        &outsider.pubkey(),
+        Roles::UtilityAccount,
+        utility_account.pubkey(),
+        0,
+    );
+    let err = env.process(&[renounce_ix], &[&outsider]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(AccessControlError::UnauthorizedRenounce)
+    );
+
+    let renounce_ix = renounce_role_ix(
+        &env,
+        &utility_account.pubkey(),
+        Roles::UtilityAccount,
+        utility_account.pubkey(),
+        0,
+    );
+    env.process(&[renounce_ix], &[&utility_account])
+        .await
+        .unwrap();
+    assert!(!env.exists(env.member(&utility_account.pubkey())).await);
+    let role: Role = env.fetch(env.role(Roles::UtilityAccount)).await;
+    assert_eq!(role.member_count, 1);
+    // The member account rent goes back to the admin that paid for it
+    assert!(env.lamports(env.admin.pubkey()).await > admin_lamports);
+}
+
+#[tokio::test]
+async fn admin_role_of_the_current_admin_moves_only_with_the_transfer() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+    let new_admin = Keypair::new();
+    let transfer_ix = system_instruction::transfer(&admin, &new_admin.pubkey(), 1_000_000_000);
+    env.process(&[transfer_ix], &[]).await.unwrap();
+    env.grant_role(Roles::Admin, new_admin.pubkey()).await;
+
+    // Another admin member exists, but renouncing would close the member account accept_admin
+    // moves the role from
+    let renounce_ix = renounce_role_ix(&env, &admin, Roles::Admin, admin, 0);
+    let err = env.process(&[renounce_ix], &[]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(AccessControlError::AdminCannotRenounce)
+    );
+
+    env.process(&[propose_admin_ix(&env, &admin, new_admin.pubkey())], &[])
+        .await
+        .unwrap();
+    env.process(
+        &[accept_admin_ix(&env, &admin, &new_admin.pubkey())],
+        &[&new_admin],
+    )
+    .await
+    .unwrap();
+    let access_controller: AccessController = env.fetch(env.access_controller).await;
+    assert_eq!(access_controller.admin, new_admin.pubkey());
+    assert!(!env.has_role(Roles::Admin, admin).await);
+    let role: Role = env.fetch(env.role(Roles::Admin)).await;
+    assert_eq!(role.member_count, 1);
+}
+
+// Calls the fake partner program, which gates on the role through role_gate
+fn partner_ix(env: &TestEnv, role: Roles, member: Pubkey, member_account: Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        fake_partner::ID,
+        &(role, member).try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(ID, false),
+            AccountMeta::new_readonly(env.access_controller, false),
+            AccountMeta::new_readonly(member_account, false),
+        ],
+    )
+}
+
+#[tokio::test]
+async fn partner_programs_gate_on_roles_over_cpi() {
+    let mut env = TestEnv::new().await;
+    let liquidator = Keypair::new().pubkey();
+    let member = env.member(&liquidator);
+
+    // A member account that doesn't exist answers no instead of failing to deserialize
+    assert!(!env.has_role(Roles::Liquidator, liquidator).await);
+    let gated_ix = partner_ix(&env, Roles::Liquidator, liquidator, member);
+    let err = env
+        .process(std::slice::from_ref(&gated_ix), &[])
+        .await
+        .unwrap_err();
+    assert_eq!(error_code(err), u32::from(AccessControlError::NoRole));
+
+    env.grant_role(Roles::Liquidator, liquidator).await;
+    env.process(std::slice::from_ref(&gated_ix), &[])
+        .await
+        .unwrap();
+
+    // Leaving the member out or checking another role answers no
+    let member_left_out_ix = partner_ix(&env, Roles::Liquidator, liquidator, ID);
+    let err = env.process(&[member_left_out_ix], &[]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(AccessControlError::NoRole));
+    let other_role_ix = partner_ix(&env, Roles::UtilityAccount, liquidator, member);
+    let err = env.process(&[other_role_ix], &[]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(AccessControlError::NoRole));
+
+    // The member account has to be the PDA of the member asked about
+    let admin_member = env.member(&env.admin.pubkey());
+    let wrong_member_ix = partner_ix(&env, Roles::Liquidator, liquidator, admin_member);
+    assert!(env.process(&[wrong_member_ix], &[]).await.is_err());
+}
+
+fn set_pause_ix(
+    env: &TestEnv,
+    guardian: &Pubkey,
+    paused: bool,
+    paused_subsystems: Vec<Subsystem>,
+    releases_allowed: bool,
+) -> Instruction {
+    ix(
+        accounts::SetPause {
+            guardian: *guardian,
+            access_controller: env.access_controller,
+            role: env.role(Roles::Guardian),
+            member: env.member(guardian),
+        },
+        instruction::SetPause {
+            paused,
+            paused_subsystems,
+            releases_allowed,
+        },
+    )
+}
+
+#[tokio::test]
+async fn guardians_pause_the_program_and_can_let_releases_through() {
+    let mut env = TestEnv::new().await;
+    let guardian = Keypair::new();
+    env.grant_role(Roles::Guardian, guardian.pubkey()).await;
+    let mint = env.whitelisted_mint(6, 2).await;
+    let (client, client_ata) = env.new_client(&mint, 1_000).await;
+    env.deposit(&client, client_ata, mint, 1_000).await;
+    let withdraw_ix = env.withdraw_ix(&client.pubkey(), client_ata, mint, 200);
+    env.process(&[withdraw_ix.clone(), withdraw_ix.clone()], &[&client])
+        .await
+        .unwrap();
+    env.advance_clock(crate::env::RELEASE_LOCK + 1).await;
+
+    // Pausing needs the guardian role, the admin doesn't hold it
+    let admin = env.admin.pubkey();
+    let pause_ix = set_pause_ix(&env, &admin, true, vec![], true);
+    let err = env.process(&[pause_ix], &[]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(AccessControlError::NoRole));
+
+    let pause_ix = set_pause_ix(&env, &guardian.pubkey(), true, vec![], true);
+    env.process(&[pause_ix], &[&guardian]).await.unwrap();
+    let access_controller: AccessController = env.fetch(env.access_controller).await;
+    assert!(access_controller.paused);
+    assert!(access_controller.releases_allowed);
+
+    let deposit_ix = env.deposit_ix(&client.pubkey(), client_ata, mint, 1);
+    let err = env.process(&[deposit_ix], &[&client]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(AccessControlError::Paused));
+    let err = env
+        .process(std::slice::from_ref(&withdraw_ix), &[&client])
+        .await
+        .unwrap_err();
+    assert_eq!(error_code(err), u32::from(AccessControlError::Paused));
+    let new_mint = env.create_mint(6, &admin).await;
+    let whitelist_ix = env.whitelist_token_ix(new_mint, 2);
+    let err = env.process(&[whitelist_ix], &[]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(AccessControlError::Paused));
+
+    // Clients can still exit through the releases
+    let release_ix = env.release_ix(&client.pubkey(), client_ata, mint, 0);
+    env.process(std::slice::from_ref(&release_ix), &[&client])
+        .await
+        .unwrap();
+    assert_eq!(env.token_balance(client_ata).await, 200);
+
+    let pause_ix = set_pause_ix(&env, &guardian.pubkey(), true, vec![], false);
+    env.process(&[pause_ix], &[&guardian]).await.unwrap();
+    let err = env
+        .process(std::slice::from_ref(&release_ix), &[&client])
+        .await
+        .unwrap_err();
+    assert_eq!(error_code(err), u32::from(AccessControlError::Paused));
+
+    // A single subsystem can be paused without the global pause
+    let pause_ix = set_pause_ix(
+        &env,
+        &guardian.pubkey(),
+        false,
+        vec![Subsystem::Deposits],
+        false,
+    );
+    env.process(&[pause_ix], &[&guardian]).await.unwrap();
+    let deposit_ix = env.deposit_ix(&client.pubkey(), client_ata, mint, 2);
+    let err = env.process(&[deposit_ix], &[&client]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(AccessControlError::Paused));
+    env.process(&[release_ix], &[&client]).await.unwrap();
+    assert_eq!(env.token_balance(client_ata).await, 400);
+
+    let unpause_ix = set_pause_ix(&env, &guardian.pubkey(), false, vec![], false);
+    env.process(&[unpause_ix], &[&guardian]).await.unwrap();
+    env.deposit(&client, client_ata, mint, 100).await;
+    assert_eq!(env.token_balance(client_ata).await, 300);
+}
@@ -0,0 +1,680 @@
+use anchor_lang::prelude::{AccountInfo, Clock, Pubkey, Rent};
+use anchor_lang::solana_program::{
+    entrypoint::ProgramResult, instruction::Instruction, program_pack::Pack, system_instruction,
+};
+use anchor_lang::{
+    system_program, AccountDeserialize, AnchorDeserialize, InstructionData, ToAccountMetas,
+};
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+};
+use ithaca_client::pda;
+use ithaca_smart_contract_sol::{accounts, instruction, Roles, TokenStatus, ID};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::InstructionError,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use std::collections::HashMap;
+
+use crate::{fake_klend, fake_partner};
+
+// Anchor's entry ties the accounts slice and the account infos to a single lifetime, which
+// the builtin processor signature doesn't, so the account infos are leaked for the test run
+fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    ithaca_smart_contract_sol::entry(program_id, accounts, data)
+}
+
+pub fn program_test() -> ProgramTest {
+    let mut program_test = ProgramTest::default();
+    // The fake KLend only exists as a native processor
+    let prefer_bpf = std::env::var("SBF_OUT_DIR").is_ok() || std::env::var("BPF_OUT_DIR").is_ok();
+    program_test.prefer_bpf(false);
+    program_test.add_program(
+        "fake_klend",
+        kamino_lending_interface::ID,
+        processor!(fake_klend::process_instruction),
+    );
+    program_test.add_program(
+        "fake_partner",
+        fake_partner::ID,
+        processor!(fake_partner::process_instruction),
+    );
+    // Runs the compiled program under `cargo test-sbf` and the native entrypoint under `cargo test`
+    program_test.prefer_bpf(prefer_bpf);
+    program_test.add_program(
+        "ithaca_smart_contract_sol",
+        ID,
+        processor!(process_instruction),
+    );
+    program_test
+}
+
+pub fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+// Extracts the custom error code of a failed transaction
+pub fn error_code(err: BanksClientError) -> u32 {
+    match err.unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => code,
+        err => panic!("Expected a custom program error, got {err:?}"),
+    }
+}
+
+pub const TRADE_LOCK: i64 = 30;
+pub const RELEASE_LOCK: i64 = 30;
+
+// Access controller, token validator and fundlock owned by a single admin
+pub struct TestEnv {
+    pub context: ProgramTestContext,
+    pub admin: Keypair,
+    pub access_controller: Pubkey,
+    pub token_validator: Pubkey,
+    pub fundlock: Pubkey,
+    // Token program of every mint created by the tests, SPL Token unless listed
+    token_programs: HashMap<Pubkey, Pubkey>,
+}
+
+impl TestEnv {
+    pub async fn new() -> Self {
+        let context = program_test().start_with_context().await;
+        let admin = context.payer.insecure_clone();
+        let access_controller = pda::access_controller(&admin.pubkey()).0;
+        let token_validator = pda::token_validator(&access_controller).0;
+        let fundlock = pda::fundlock(&access_controller, &token_validator).0;
+        let mut env = Self {
+            context,
+            admin,
+            access_controller,
+            token_validator,
+            fundlock,
+            token_programs: HashMap::new(),
+        };
+
+        let admin_role = env.role(Roles::Admin);
+        let admin_member = env.member(&env.admin.pubkey());
+        env.process(
+            &[
+                ix(
+                    accounts::InitAccessController {
+                        admin: env.admin.pubkey(),
+                        access_controller,
+                        role: admin_role,
+                        member: admin_member,
+                        role_members: pda::role_members(&admin_role, 0).0,
+                        system_program: system_program::ID,
+                    },
+                    instruction::InitAccessController {},
+                ),
+                ix(
+                    accounts::InitTokenValidator {
+                        admin: env.admin.pubkey(),
+                        access_controller,
+                        role: admin_role,
+                        member: admin_member,
+                        token_validator,
+                        system_program: system_program::ID,
+                    },
+                    instruction::InitTokenValidator {},
+                ),
+                ix(
+                    accounts::InitFundlock {
+                        caller: env.admin.pubkey(),
+                        access_controller,
+                        role: admin_role,
+                        member: admin_member,
+                        token_validator,
+                        fundlock,
+                        system_program: system_program::ID,
+                    },
+                    instruction::InitFundlock {
+                        trade_lock: TRADE_LOCK,
+                        release_lock: RELEASE_LOCK,
+                    },
+                ),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        env
+    }
+
+    // Sends the instructions in a single transaction paid by the admin
+    pub async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        let blockhash = self.context.get_new_latest_blockhash().await.unwrap();
+        let mut all_signers = vec![&self.admin];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.admin.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+        self.context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+    }
+
+    pub async fn fetch<T: AccountDeserialize>(&mut self, address: Pubkey) -> T {
+        let account = self
+            .context
+            .banks_client
+            .get_account(address)
+            .await
+            .unwrap()
+            .unwrap_or_else(|| panic!("Account {address} does not exist"));
+        T::try_deserialize(&mut account.data.as_ref()).unwrap()
+    }
+
+    // Writes a rent exempt account of the program with raw data, to recreate legacy layouts
+    pub fn set_program_account(&mut self, address: &Pubkey, data: &[u8]) {
+        let rent = Rent::default().minimum_balance(data.len());
+        let mut account = AccountSharedData::new(rent, data.len(), &ID);
+        account.set_data_from_slice(data);
+        self.context.set_account(address, &account);
+    }
+
+    pub async fn exists(&mut self, address: Pubkey) -> bool {
+        self.context
+            .banks_client
+            .get_account(address)
+            .await
+            .unwrap()
+            .is_some()
+    }
+
+    pub async fn lamports(&mut self, address: Pubkey) -> u64 {
+        self.context
+            .banks_client
+            .get_balance(address)
+            .await
+            .unwrap()
+    }
+
+    pub async fn now(&mut self) -> i64 {
+        let clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp
+    }
+
+    // Moves the clock forward so that time locks can expire
+    pub async fn advance_clock(&mut self, seconds: i64) {
+        let mut clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp += seconds;
+        self.context.set_sysvar(&clock);
+    }
+
+    pub fn role(&self, role: Roles) -> Pubkey {
+        pda::role(&self.access_controller, &role).0
+    }
+
+    pub fn member(&self, member: &Pubkey) -> Pubkey {
+        pda::member(&self.access_controller, member).0
+    }
+
+    // Lists the member in the first page, the tests grant fewer members than a page holds
+    pub fn grant_role_ix(&self, role: Roles, new_member: Pubkey) -> Instruction {
+        self.grant_role_in_page_ix(role, new_member, 0, None)
+    }
+
+    pub fn grant_role_in_page_ix(
+        &self,
+        role: Roles,
+        new_member: Pubkey,
+        page_index: u64,
+        expires_at: Option<i64>,
+    ) -> Instruction {
+        let role_pda = self.role(role);
+        ix(
+            accounts::GrantRole {
+                admin: self.admin.pubkey(),
+                access_controller: self.access_controller,
+                role: role_pda,
+                member: self.member(&new_member),
+                role_members: pda::role_members(&role_pda, page_index).0,
+                system_program: system_program::ID,
+            },
+            instruction::GrantRole {
+                role_granted: role,
+                new_member,
+                page_index,
+                expires_at,
+            },
+        )
+    }
+
+    pub async fn grant_role(&mut self, role: Roles, new_member: Pubkey) {
+        let grant_role_ix = self.grant_role_ix(role, new_member);
+        self.process(&[grant_role_ix], &[]).await.unwrap();
+    }
+
+    pub fn check_role_ix(&self, role: Roles, member: Pubkey) -> Instruction {
+        ix(
+            accounts::CheckRole {
+                access_controller: self.access_controller,
+                member: Some(self.member(&member)),
+            },
+            instruction::CheckRole {
+                role_checked: role,
+                member_pk: member,
+            },
+        )
+    }
+
+    // Reads the answer of check_role from the return data of a simulated transaction
+    pub async fn has_role(&mut self, role: Roles, member: Pubkey) -> bool {
+        let check_role_ix = self.check_role_ix(role, member);
+        let blockhash = self.context.get_new_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            &[check_role_ix],
+            Some(&self.admin.pubkey()),
+            &[&self.admin],
+            blockhash,
+        );
+        let simulation = self
+            .context
+            .banks_client
+            .simulate_transaction(transaction)
+            .await
+            .unwrap();
+        simulation.result.unwrap().unwrap();
+        let return_data = simulation.simulation_details.unwrap().return_data.unwrap();
+        assert_eq!(return_data.program_id, ID);
+        bool::try_from_slice(&return_data.data).unwrap()
+    }
+
+    pub fn whitelist_token_ix(&self, mint: Pubkey, token_precision: u8) -> Instruction {
+        self.whitelist_token_with_extensions_ix(mint, token_precision, false)
+    }
+
+    pub fn whitelist_token_with_extensions_ix(
+        &self,
+        mint: Pubkey,
+        token_precision: u8,
+        allow_dangerous_extensions: bool,
+    ) -> Instruction {
+        let admin_role = self.role(Roles::Admin);
+        ix(
+            accounts::AddTokenToWhitelist {
+                admin: self.admin.pubkey(),
+                access_controller: self.access_controller,
+                role: admin_role,
+                member: self.member(&self.admin.pubkey()),
+                token_validator: self.token_validator,
+                new_token_to_whitelist: mint,
+                whitelisted_token: pda::whitelisted_token(&self.token_validator, &mint).0,
+                system_program: system_program::ID,
+            },
+            instruction::AddTokenToWhitelist {
+                token_precision,
+                allow_dangerous_extensions,
+            },
+        )
+    }
+
+    pub fn set_token_config_ix(
+        &self,
+        mint: Pubkey,
+        status: TokenStatus,
+        deposit_cap: Option<u64>,
+        client_deposit_cap: Option<u64>,
+        min_deposit: u64,
+    ) -> Instruction {
+        let admin_role = self.role(Roles::Admin);
+        ix(
+            accounts::SetTokenConfig {
+                admin: self.admin.pubkey(),
+                access_controller: self.access_controller,
+                role: admin_role,
+                member: self.member(&self.admin.pubkey()),
+                token_validator: self.token_validator,
+                token: mint,
+                whitelisted_token: pda::whitelisted_token(&self.token_validator, &mint).0,
+                system_program: system_program::ID,
+            },
+            instruction::SetTokenConfig {
+                status,
+                deposit_cap,
+                client_deposit_cap,
+                min_deposit,
+            },
+        )
+    }
+
+    // Creates a mint controlled by the admin and whitelists it
+    pub async fn whitelisted_mint(&mut self, decimals: u8, token_precision: u8) -> Pubkey {
+        let mint = self.create_mint(decimals, &self.admin.pubkey()).await;
+        let whitelist_token_ix = self.whitelist_token_ix(mint, token_precision);
+        self.process(&[whitelist_token_ix], &[]).await.unwrap();
+        mint
+    }
+
+    pub async fn create_mint(&mut self, decimals: u8, authority: &Pubkey) -> Pubkey {
+        let mint = Keypair::new();
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+        self.process(
+            &[
+                system_instruction::create_account(
+                    &self.admin.pubkey(),
+                    &mint.pubkey(),
+                    rent.minimum_balance(spl_token::state::Mint::LEN),
+                    spl_token::state::Mint::LEN as u64,
+                    &spl_token::ID,
+                ),
+                spl_token::instruction::initialize_mint2(
+                    &spl_token::ID,
+                    &mint.pubkey(),
+                    authority,
+                    None,
+                    decimals,
+                )
+                .unwrap(),
+            ],
+            &[&mint],
+        )
+        .await
+        .unwrap();
+        mint.pubkey()
+    }
+
+    // Creates a Token-2022 mint controlled by the admin, `extension_ixs` initializing the
+    // `extensions` of the mint before the mint itself
+    pub async fn create_mint_2022(
+        &mut self,
+        mint: &Keypair,
+        decimals: u8,
+        extensions: &[ExtensionType],
+        extension_ixs: Vec<Instruction>,
+    ) -> Pubkey {
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+        let space =
+            ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(extensions)
+                .unwrap();
+        let mut instructions = vec![system_instruction::create_account(
+            &self.admin.pubkey(),
+            &mint.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &spl_token_2022::ID,
+        )];
+        instructions.extend(extension_ixs);
+        instructions.push(
+            spl_token_2022::instruction::initialize_mint2(
+                &spl_token_2022::ID,
+                &mint.pubkey(),
+                &self.admin.pubkey(),
+                None,
+                decimals,
+            )
+            .unwrap(),
+        );
+        self.process(&instructions, &[mint]).await.unwrap();
+        self.token_programs
+            .insert(mint.pubkey(), spl_token_2022::ID);
+        mint.pubkey()
+    }
+
+    pub fn token_program(&self, mint: &Pubkey) -> Pubkey {
+        self.token_programs
+            .get(mint)
+            .copied()
+            .unwrap_or(spl_token::ID)
+    }
+
+    // Sized for the account extensions the mint requires, if any
+    pub async fn create_token_account(&mut self, mint: &Pubkey, owner: &Pubkey) -> Pubkey {
+        let token_account = Keypair::new();
+        let token_program = self.token_program(mint);
+        let mint_account = self
+            .context
+            .banks_client
+            .get_account(*mint)
+            .await
+            .unwrap()
+            .unwrap();
+        let mint_extensions =
+            StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_account.data)
+                .unwrap()
+                .get_extension_types()
+                .unwrap();
+        let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(
+            &ExtensionType::get_required_init_account_extensions(&mint_extensions),
+        )
+        .unwrap();
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+        self.process(
+            &[
+                system_instruction::create_account(
+                    &self.admin.pubkey(),
+                    &token_account.pubkey(),
+                    rent.minimum_balance(space),
+                    space as u64,
+                    &token_program,
+                ),
+                spl_token_2022::instruction::initialize_account3(
+                    &token_program,
+                    &token_account.pubkey(),
+                    mint,
+                    owner,
+                )
+                .unwrap(),
+            ],
+            &[&token_account],
+        )
+        .await
+        .unwrap();
+        token_account.pubkey()
+    }
+
+    // Mints tokens of an admin controlled mint
+    pub async fn mint_to(&mut self, mint: &Pubkey, token_account: &Pubkey, amount: u64) {
+        let mint_to_ix = spl_token_2022::instruction::mint_to(
+            &self.token_program(mint),
+            mint,
+            token_account,
+            &self.admin.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap();
+        self.process(&[mint_to_ix], &[]).await.unwrap();
+    }
+
+    pub async fn token_balance(&mut self, token_account: Pubkey) -> u64 {
+        let account = self
+            .context
+            .banks_client
+            .get_account(token_account)
+            .await
+            .unwrap()
+            .unwrap();
+        StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+            .unwrap()
+            .base
+            .amount
+    }
+
+    // Funds a fresh client with SOL for rent and with `amount` tokens in a new token account
+    pub async fn new_client(&mut self, mint: &Pubkey, amount: u64) -> (Keypair, Pubkey) {
+        let client = Keypair::new();
+        let client_ata = self.fund_client(&client, mint, amount).await;
+        (client, client_ata)
+    }
+
+    pub async fn fund_client(&mut self, client: &Keypair, mint: &Pubkey, amount: u64) -> Pubkey {
+        let transfer_ix =
+            system_instruction::transfer(&self.admin.pubkey(), &client.pubkey(), 1_000_000_000);
+        self.process(&[transfer_ix], &[]).await.unwrap();
+        let client_ata = self.create_token_account(mint, &client.pubkey()).await;
+        self.mint_to(mint, &client_ata, amount).await;
+        client_ata
+    }
+
+    pub fn deposit_ix(
+        &self,
+        client: &Pubkey,
+        client_ata: Pubkey,
+        mint: Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        let fundlock_token_vault = pda::fundlock_token_vault(&self.fundlock, &mint).0;
+        let client_balance = pda::client_balance(&fundlock_token_vault, &client_ata).0;
+        ix(
+            accounts::DepositFundlock {
+                client: *client,
+                access_controller: self.access_controller,
+                token_validator: self.token_validator,
+                fundlock: self.fundlock,
+                token: mint,
+                whitelisted_token: pda::whitelisted_token(&self.token_validator, &mint).0,
+                fundlock_token_vault,
+                client_balance,
+                client_ata,
+                withdrawals: pda::withdrawals(&self.fundlock, &client_balance).0,
+                system_program: system_program::ID,
+                token_program: self.token_program(&mint),
+            },
+            instruction::DepositFundlock { amount },
+        )
+    }
+
+    pub async fn deposit(
+        &mut self,
+        client: &Keypair,
+        client_ata: Pubkey,
+        mint: Pubkey,
+        amount: u64,
+    ) {
+        let deposit_ix = self.deposit_ix(&client.pubkey(), client_ata, mint, amount);
+        self.process(&[deposit_ix], &[client]).await.unwrap();
+    }
+
+    pub fn withdraw_ix(
+        &self,
+        client: &Pubkey,
+        client_ata: Pubkey,
+        mint: Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        let fundlock_token_vault = pda::fundlock_token_vault(&self.fundlock, &mint).0;
+        let client_balance = pda::client_balance(&fundlock_token_vault, &client_ata).0;
+        ix(
+            accounts::WithdrawFundlock {
+                client: *client,
+                access_controller: self.access_controller,
+                token_validator: self.token_validator,
+                fundlock: self.fundlock,
+                token: mint,
+                whitelisted_token: pda::whitelisted_token(&self.token_validator, &mint).0,
+                fundlock_token_vault,
+                client_balance,
+                client_ata,
+                withdrawals: pda::withdrawals(&self.fundlock, &client_balance).0,
+                system_program: system_program::ID,
+                token_program: self.token_program(&mint),
+            },
+            instruction::WithdrawFundlock { amount },
+        )
+    }
+
+    pub fn release_ix(
+        &self,
+        client: &Pubkey,
+        client_ata: Pubkey,
+        mint: Pubkey,
+        index: u64,
+    ) -> Instruction {
+        let fundlock_token_vault = pda::fundlock_token_vault(&self.fundlock, &mint).0;
+        let client_balance = pda::client_balance(&fundlock_token_vault, &client_ata).0;
+        ix(
+            accounts::ReleaseFundlock {
+                client: *client,
+                access_controller: self.access_controller,
+                token_validator: self.token_validator,
+                fundlock: self.fundlock,
+                token: mint,
+                whitelisted_token: pda::whitelisted_token(&self.token_validator, &mint).0,
+                fundlock_token_vault,
+                client_balance,
+                client_ata,
+                withdrawals: pda::withdrawals(&self.fundlock, &client_balance).0,
+                system_program: system_program::ID,
+                token_program: self.token_program(&mint),
+            },
+            instruction::ReleaseFundlock { index },
+        )
+    }
+
+    pub fn release_all_matured_ix(
+        &self,
+        client: &Pubkey,
+        client_ata: Pubkey,
+        destination: Pubkey,
+        mint: Pubkey,
+    ) -> Instruction {
+        let fundlock_token_vault = pda::fundlock_token_vault(&self.fundlock, &mint).0;
+        let client_balance = pda::client_balance(&fundlock_token_vault, &client_ata).0;
+        ix(
+            accounts::ReleaseAllMatured {
+                client: *client,
+                access_controller: self.access_controller,
+                token_validator: self.token_validator,
+                fundlock: self.fundlock,
+                token: mint,
+                whitelisted_token: pda::whitelisted_token(&self.token_validator, &mint).0,
+                fundlock_token_vault,
+                client_balance,
+                client_ata,
+                destination,
+                withdrawals: pda::withdrawals(&self.fundlock, &client_balance).0,
+                system_program: system_program::ID,
+                token_program: self.token_program(&mint),
+            },
+            instruction::ReleaseAllMatured {},
+        )
+    }
+
+    pub fn cancel_withdrawal_ix(
+        &self,
+        client: &Pubkey,
+        client_ata: Pubkey,
+        mint: Pubkey,
+        index: u64,
+        amount: u64,
+    ) -> Instruction {
+        let fundlock_token_vault = pda::fundlock_token_vault(&self.fundlock, &mint).0;
+        let client_balance = pda::client_balance(&fundlock_token_vault, &client_ata).0;
+        ix(
+            accounts::CancelWithdrawal {
+                client: *client,
+                access_controller: self.access_controller,
+                token_validator: self.token_validator,
+                fundlock: self.fundlock,
+                token: mint,
+                fundlock_token_vault,
+                client_balance,
+                withdrawals: pda::withdrawals(&self.fundlock, &client_balance).0,
+            },
+            instruction::CancelWithdrawal { index, amount },
+        )
+    }
+}
@@ -0,0 +1,161 @@
+use anchor_lang::solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022::{self, extension::StateWithExtensions, state::Mint};
+use kamino_lending_interface::{
+    DepositReserveLiquidityIxData, RedeemReserveCollateralIxData,
+    DEPOSIT_RESERVE_LIQUIDITY_IX_DISCM, REDEEM_RESERVE_COLLATERAL_IX_DISCM,
+};
+
+// Stand-in for KLend registered under the Kamino program id. It only implements the two
+// instructions the fundlock CPIs into, using the same account layouts as the real program,
+// and exchanges liquidity for collateral at a fixed rate instead of reading reserve state.
+
+// Collateral minted per unit of liquidity, kept different from 1 so tests can tell the amounts apart
+pub const COLLATERAL_PER_LIQUIDITY: u64 = 2;
+
+pub fn lending_market_authority(lending_market: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"lma", lending_market.as_ref()],
+        &kamino_lending_interface::ID,
+    )
+}
+
+pub fn process_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let discriminator: [u8; 8] = data[..8].try_into().unwrap();
+    match discriminator {
+        DEPOSIT_RESERVE_LIQUIDITY_IX_DISCM => {
+            let args = DepositReserveLiquidityIxData::deserialize(data)
+                .map_err(|_| ProgramError::InvalidInstructionData)?
+                .0;
+            deposit_reserve_liquidity(accounts, args.liquidity_amount)
+        }
+        REDEEM_RESERVE_COLLATERAL_IX_DISCM => {
+            let args = RedeemReserveCollateralIxData::deserialize(data)
+                .map_err(|_| ProgramError::InvalidInstructionData)?
+                .0;
+            redeem_reserve_collateral(accounts, args.collateral_amount)
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+// owner, reserve, lending_market, lending_market_authority, reserve_liquidity_mint,
+// reserve_liquidity_supply, reserve_collateral_mint, user_source_liquidity,
+// user_destination_collateral, collateral_token_program, liquidity_token_program, instructions
+fn deposit_reserve_liquidity(accounts: &[AccountInfo], liquidity_amount: u64) -> ProgramResult {
+    let [owner, _reserve, lending_market, market_authority, reserve_liquidity_mint, reserve_liquidity_supply, reserve_collateral_mint, user_source_liquidity, user_destination_collateral, collateral_token_program, liquidity_token_program, ..] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let (authority, bump) = lending_market_authority(lending_market.key);
+    if authority != *market_authority.key || !owner.is_signer {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    invoke(
+        &spl_token_2022::instruction::transfer_checked(
+            liquidity_token_program.key,
+            user_source_liquidity.key,
+            reserve_liquidity_mint.key,
+            reserve_liquidity_supply.key,
+            owner.key,
+            &[],
+            liquidity_amount,
+            decimals(reserve_liquidity_mint)?,
+        )?,
+        &[
+            user_source_liquidity.clone(),
+            reserve_liquidity_mint.clone(),
+            reserve_liquidity_supply.clone(),
+            owner.clone(),
+        ],
+    )?;
+
+    invoke_signed(
+        &spl_token::instruction::mint_to(
+            collateral_token_program.key,
+            reserve_collateral_mint.key,
+            user_destination_collateral.key,
+            market_authority.key,
+            &[],
+            liquidity_amount * COLLATERAL_PER_LIQUIDITY,
+        )?,
+        &[
+            reserve_collateral_mint.clone(),
+            user_destination_collateral.clone(),
+            market_authority.clone(),
+        ],
+        &[&[b"lma", lending_market.key.as_ref(), &[bump]]],
+    )
+}
+
+// owner, lending_market, reserve, lending_market_authority, reserve_liquidity_mint,
+// reserve_collateral_mint, reserve_liquidity_supply, user_source_collateral,
+// user_destination_liquidity, collateral_token_program, liquidity_token_program, instructions
+fn redeem_reserve_collateral(accounts: &[AccountInfo], collateral_amount: u64) -> ProgramResult {
+    let [owner, lending_market, _reserve, market_authority, reserve_liquidity_mint, reserve_collateral_mint, reserve_liquidity_supply, user_source_collateral, user_destination_liquidity, collateral_token_program, liquidity_token_program, ..] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let (authority, bump) = lending_market_authority(lending_market.key);
+    if authority != *market_authority.key || !owner.is_signer {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    invoke(
+        &spl_token::instruction::burn(
+            collateral_token_program.key,
+            user_source_collateral.key,
+            reserve_collateral_mint.key,
+            owner.key,
+            &[],
+            collateral_amount,
+        )?,
+        &[
+            user_source_collateral.clone(),
+            reserve_collateral_mint.clone(),
+            owner.clone(),
+        ],
+    )?;
+
+    invoke_signed(
+        &spl_token_2022::instruction::transfer_checked(
+            liquidity_token_program.key,
+            reserve_liquidity_supply.key,
+            reserve_liquidity_mint.key,
+            user_destination_liquidity.key,
+            market_authority.key,
+            &[],
+            collateral_amount / COLLATERAL_PER_LIQUIDITY,
+            decimals(reserve_liquidity_mint)?,
+        )?,
+        &[
+            reserve_liquidity_supply.clone(),
+            reserve_liquidity_mint.clone(),
+            user_destination_liquidity.clone(),
+            market_authority.clone(),
+        ],
+        &[&[b"lma", lending_market.key.as_ref(), &[bump]]],
+    )
+}
+
+// Liquidity mints can belong to either token program
+fn decimals(mint: &AccountInfo) -> Result<u8, ProgramError> {
+    let data = mint.try_borrow_data()?;
+    Ok(StateWithExtensions::<Mint>::unpack(&data)?.base.decimals)
+}
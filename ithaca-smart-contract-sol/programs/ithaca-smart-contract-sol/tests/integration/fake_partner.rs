@@ -0,0 +1,31 @@
+use anchor_lang::prelude::{AccountInfo, Pubkey};
+use anchor_lang::solana_program::{entrypoint::ProgramResult, program_error::ProgramError};
+use anchor_lang::AnchorDeserialize;
+use ithaca_smart_contract_sol::{role_gate, Roles};
+
+// Stand-in for a partner program that gates its only instruction on an Ithaca role through
+// role_gate. Passing the Ithaca program id in place of the member leaves the member out.
+
+pub const ID: Pubkey = Pubkey::new_from_array([7; 32]);
+
+// ithaca_program, access_controller, member
+pub fn process_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let [ithaca_program, access_controller, member, ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let (role, member_pk) = <(Roles, Pubkey)>::try_from_slice(data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+    let member = (member.key != ithaca_program.key).then(|| member.clone());
+    role_gate::require_role(
+        ithaca_program.clone(),
+        access_controller.clone(),
+        member,
+        role,
+        member_pk,
+    )?;
+    Ok(())
+}
@@ -0,0 +1,919 @@
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::{instruction::Instruction, system_instruction};
+use anchor_lang::{system_program, AnchorSerialize, Discriminator};
+use anchor_spl::token::spl_token::{self, native_mint};
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{transfer_fee::instruction::initialize_transfer_fee_config, ExtensionType},
+};
+use ithaca_client::pda;
+use ithaca_smart_contract_sol::error::FundlockError;
+use ithaca_smart_contract_sol::{
+    accounts, instruction, ClientBalance, Fundlock, KeeperPool, Roles, Withdrawals,
+    DEFAULT_WITHDRAWAL_LIMIT,
+};
+use solana_sdk::signature::{Keypair, Signer};
+
+use crate::env::{error_code, ix, TestEnv, RELEASE_LOCK, TRADE_LOCK};
+
+#[tokio::test]
+async fn deposit_credits_the_client_balance() {
+    let mut env = TestEnv::new().await;
+    let fundlock: Fundlock = env.fetch(env.fundlock).await;
+    assert_eq!(fundlock.trade_lock, TRADE_LOCK);
+    assert_eq!(fundlock.release_lock, RELEASE_LOCK);
+
+    let mint = env.whitelisted_mint(6, 2).await;
+    let (client, client_ata) = env.new_client(&mint, 1_000).await;
+
+    env.deposit(&client, client_ata, mint, 600).await;
+    env.deposit(&client, client_ata, mint, 150).await;
+
+    let fundlock_token_vault = pda::fundlock_token_vault(&env.fundlock, &mint).0;
+    let client_balance: ClientBalance = env
+        .fetch(pda::client_balance(&fundlock_token_vault, &client_ata).0)
+        .await;
+    assert_eq!(client_balance.amount, 750);
+    assert_eq!(client_balance.client, client.pubkey());
+    assert_eq!(client_balance.client_ata, client_ata);
+    assert_eq!(client_balance.token, mint);
+    assert_eq!(env.token_balance(fundlock_token_vault).await, 750);
+    assert_eq!(env.token_balance(client_ata).await, 250);
+
+    let deposit_ix = env.deposit_ix(&client.pubkey(), client_ata, mint, 0);
+    let err = env.process(&[deposit_ix], &[&client]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(FundlockError::AmountZero));
+}
+
+// 1% transfer fee, rounded up by the token program
+const TRANSFER_FEE_BPS: u16 = 100;
+
+#[tokio::test]
+async fn transfer_fee_tokens_credit_what_the_vault_receives() {
+    let mut env = TestEnv::new().await;
+    let mint = Keypair::new();
+    let fee_config_ix = initialize_transfer_fee_config(
+        &spl_token_2022::ID,
+        &mint.pubkey(),
+        None,
+        None,
+        TRANSFER_FEE_BPS,
+        u64::MAX,
+    )
+    .unwrap();
+    let mint = env
+        .create_mint_2022(
+            &mint,
+            6,
+            &[ExtensionType::TransferFeeConfig],
+            vec![fee_config_ix],
+        )
+        .await;
+    let whitelist_token_ix = env.whitelist_token_ix(mint, 2);
+    env.process(&[whitelist_token_ix], &[]).await.unwrap();
+    let (client, client_ata) = env.new_client(&mint, 10_000).await;
+
+    env.deposit(&client, client_ata, mint, 1_000).await;
+    let fundlock_token_vault = pda::fundlock_token_vault(&env.fundlock, &mint).0;
+    let client_balance = pda::client_balance(&fundlock_token_vault, &client_ata).0;
+    let balance: ClientBalance = env.fetch(client_balance).await;
+    assert_eq!(balance.amount, 990);
+    assert_eq!(env.token_balance(fundlock_token_vault).await, 990);
+
+    // The fee of the release is withheld from what reaches the client
+    let withdraw_ix = env.withdraw_ix(&client.pubkey(), client_ata, mint, 990);
+    env.process(&[withdraw_ix], &[&client]).await.unwrap();
+    env.advance_clock(RELEASE_LOCK + 1).await;
+    let release_ix = env.release_ix(&client.pubkey(), client_ata, mint, 0);
+    env.process(&[release_ix], &[&client]).await.unwrap();
+    assert_eq!(env.token_balance(fundlock_token_vault).await, 0);
+    assert_eq!(env.token_balance(client_ata).await, 9_000 + 980);
+}
+
+// Accounts shared by the native SOL instructions, keyed by the client's system account
+struct SolAccounts {
+    fundlock_token_vault: Pubkey,
+    client_balance: Pubkey,
+    withdrawals: Pubkey,
+}
+
+fn sol_accounts(env: &TestEnv, client: &Pubkey) -> SolAccounts {
+    let fundlock_token_vault = pda::fundlock_token_vault(&env.fundlock, &native_mint::ID).0;
+    let client_balance = pda::client_balance(&fundlock_token_vault, client).0;
+    SolAccounts {
+        fundlock_token_vault,
+        client_balance,
+        withdrawals: pda::withdrawals(&env.fundlock, &client_balance).0,
+    }
+}
+
+fn deposit_sol_ix(env: &TestEnv, client: &Pubkey, amount: u64) -> Instruction {
+    let sol = sol_accounts(env, client);
+    ix(
+        accounts::DepositFundlockSol {
+            client: *client,
+            access_controller: env.access_controller,
+            token_validator: env.token_validator,
+            fundlock: env.fundlock,
+            token: native_mint::ID,
+            whitelisted_token: pda::whitelisted_token(&env.token_validator, &native_mint::ID).0,
+            fundlock_token_vault: sol.fundlock_token_vault,
+            client_balance: sol.client_balance,
+            withdrawals: sol.withdrawals,
+            system_program: system_program::ID,
+            token_program: spl_token::ID,
+        },
+        instruction::DepositFundlockSol { amount },
+    )
+}
+
+fn withdraw_sol_ix(env: &TestEnv, client: &Pubkey, amount: u64) -> Instruction {
+    let sol = sol_accounts(env, client);
+    ix(
+        accounts::WithdrawFundlockSol {
+            client: *client,
+            access_controller: env.access_controller,
+            token_validator: env.token_validator,
+            fundlock: env.fundlock,
+            token: native_mint::ID,
+            whitelisted_token: pda::whitelisted_token(&env.token_validator, &native_mint::ID).0,
+            fundlock_token_vault: sol.fundlock_token_vault,
+            client_balance: sol.client_balance,
+            withdrawals: sol.withdrawals,
+            system_program: system_program::ID,
+        },
+        instruction::WithdrawFundlockSol { amount },
+    )
+}
+
+fn release_sol_ix(env: &TestEnv, client: &Pubkey, index: u64) -> Instruction {
+    let sol = sol_accounts(env, client);
+    ix(
+        accounts::ReleaseFundlockSol {
+            client: *client,
+            access_controller: env.access_controller,
+            token_validator: env.token_validator,
+            fundlock: env.fundlock,
+            token: native_mint::ID,
+            whitelisted_token: pda::whitelisted_token(&env.token_validator, &native_mint::ID).0,
+            fundlock_token_vault: sol.fundlock_token_vault,
+            client_balance: sol.client_balance,
+            withdrawals: sol.withdrawals,
+            unwrap_account: pda::sol_release(&sol.fundlock_token_vault, client).0,
+            system_program: system_program::ID,
+            token_program: spl_token::ID,
+        },
+        instruction::ReleaseFundlockSol { index },
+    )
+}
+
+fn release_all_matured_sol_ix(env: &TestEnv, client: &Pubkey) -> Instruction {
+    let sol = sol_accounts(env, client);
+    ix(
+        accounts::ReleaseAllMaturedSol {
+            client: *client,
+            access_controller: env.access_controller,
+            token_validator: env.token_validator,
+            fundlock: env.fundlock,
+            token: native_mint::ID,
+            whitelisted_token: pda::whitelisted_token(&env.token_validator, &native_mint::ID).0,
+            fundlock_token_vault: sol.fundlock_token_vault,
+            client_balance: sol.client_balance,
+            withdrawals: sol.withdrawals,
+            unwrap_account: pda::sol_release(&sol.fundlock_token_vault, client).0,
+            system_program: system_program::ID,
+            token_program: spl_token::ID,
+        },
+        instruction::ReleaseAllMaturedSol {},
+    )
+}
+
+fn crank_release_sol_ix(
+    env: &TestEnv,
+    keeper: &Pubkey,
+    client: &Pubkey,
+    keeper_pool: Option<Pubkey>,
+) -> Instruction {
+    let sol = sol_accounts(env, client);
+    ix(
+        accounts::CrankReleaseSol {
+            keeper: *keeper,
+            access_controller: env.access_controller,
+            token_validator: env.token_validator,
+            fundlock: env.fundlock,
+            token: native_mint::ID,
+            whitelisted_token: pda::whitelisted_token(&env.token_validator, &native_mint::ID).0,
+            fundlock_token_vault: sol.fundlock_token_vault,
+            client_balance: sol.client_balance,
+            client: *client,
+            withdrawals: sol.withdrawals,
+            unwrap_account: pda::sol_release(&sol.fundlock_token_vault, client).0,
+            keeper_pool,
+            system_program: system_program::ID,
+            token_program: spl_token::ID,
+        },
+        instruction::CrankReleaseSol {},
+    )
+}
+
+#[tokio::test]
+async fn native_sol_is_wrapped_on_deposit_and_unwrapped_on_release() {
+    let mut env = TestEnv::new().await;
+    let whitelist_token_ix = env.whitelist_token_ix(native_mint::ID, 4);
+    env.process(&[whitelist_token_ix], &[]).await.unwrap();
+    let client = Keypair::new();
+    let fund_ix =
+        system_instruction::transfer(&env.admin.pubkey(), &client.pubkey(), 2_000_000_000);
+    env.process(&[fund_ix], &[]).await.unwrap();
+    let sol = sol_accounts(&env, &client.pubkey());
+
+    let deposit_ix = deposit_sol_ix(&env, &client.pubkey(), 1_000_000_000);
+    env.process(&[deposit_ix], &[&client]).await.unwrap();
+    let balance: ClientBalance = env.fetch(sol.client_balance).await;
+    assert_eq!(balance.amount, 1_000_000_000);
+    assert_eq!(balance.token, native_mint::ID);
+    assert_eq!(balance.client_ata, client.pubkey());
+    assert_eq!(
+        env.token_balance(sol.fundlock_token_vault).await,
+        1_000_000_000
+    );
+
+    let withdraw_ix = withdraw_sol_ix(&env, &client.pubkey(), 400_000_000);
+    env.process(&[withdraw_ix], &[&client]).await.unwrap();
+    let release_ix = release_sol_ix(&env, &client.pubkey(), 0);
+    let err = env
+        .process(std::slice::from_ref(&release_ix), &[&client])
+        .await
+        .unwrap_err();
+    assert_eq!(error_code(err), u32::from(FundlockError::ReleaseLockActive));
+
+    // The released wSOL reaches the client's system account as lamports
+    env.advance_clock(RELEASE_LOCK + 1).await;
+    let lamports = env.lamports(client.pubkey()).await;
+    env.process(&[release_ix], &[&client]).await.unwrap();
+    assert_eq!(env.lamports(client.pubkey()).await, lamports + 400_000_000);
+    assert!(
+        !env.exists(pda::sol_release(&sol.fundlock_token_vault, &client.pubkey()).0)
+            .await
+    );
+    assert_eq!(
+        env.token_balance(sol.fundlock_token_vault).await,
+        600_000_000
+    );
+    let queue: Withdrawals = env.fetch(sol.withdrawals).await;
+    assert!(queue.withdrawal_queue.is_empty());
+    assert_eq!(queue.active_withdrawals_amount, 0);
+
+    // Matured SOL withdrawals are released together the same way
+    for amount in [100_000_000, 200_000_000] {
+        let withdraw_ix = withdraw_sol_ix(&env, &client.pubkey(), amount);
+        env.process(&[withdraw_ix], &[&client]).await.unwrap();
+    }
+    env.advance_clock(RELEASE_LOCK + 1).await;
+    let lamports = env.lamports(client.pubkey()).await;
+    let release_all_ix = release_all_matured_sol_ix(&env, &client.pubkey());
+    env.process(&[release_all_ix], &[&client]).await.unwrap();
+    assert_eq!(env.lamports(client.pubkey()).await, lamports + 300_000_000);
+    assert_eq!(
+        env.token_balance(sol.fundlock_token_vault).await,
+        300_000_000
+    );
+    let queue: Withdrawals = env.fetch(sol.withdrawals).await;
+    assert!(queue.withdrawal_queue.is_empty());
+
+    // Keepers crank SOL releases to the client, getting back the rent of the unwrap account
+    let keeper = Keypair::new();
+    let fund_keeper_ix =
+        system_instruction::transfer(&env.admin.pubkey(), &keeper.pubkey(), 1_000_000_000);
+    env.process(&[fund_keeper_ix], &[]).await.unwrap();
+    let withdraw_ix = withdraw_sol_ix(&env, &client.pubkey(), 100_000_000);
+    env.process(&[withdraw_ix], &[&client]).await.unwrap();
+    env.advance_clock(RELEASE_LOCK + 1).await;
+    let lamports = env.lamports(client.pubkey()).await;
+    let crank_ix = crank_release_sol_ix(&env, &keeper.pubkey(), &client.pubkey(), None);
+    env.process(&[crank_ix], &[&keeper]).await.unwrap();
+    assert_eq!(env.lamports(client.pubkey()).await, lamports + 100_000_000);
+    assert_eq!(env.lamports(keeper.pubkey()).await, 1_000_000_000);
+    assert_eq!(
+        env.token_balance(sol.fundlock_token_vault).await,
+        200_000_000
+    );
+}
+
+#[tokio::test]
+async fn sol_deposits_keep_the_registered_destination() {
+    let mut env = TestEnv::new().await;
+    let whitelist_token_ix = env.whitelist_token_ix(native_mint::ID, 4);
+    env.process(&[whitelist_token_ix], &[]).await.unwrap();
+    let client = Keypair::new();
+    let fund_ix =
+        system_instruction::transfer(&env.admin.pubkey(), &client.pubkey(), 2_000_000_000);
+    env.process(&[fund_ix], &[]).await.unwrap();
+    let sol = sol_accounts(&env, &client.pubkey());
+
+    let deposit_ix = deposit_sol_ix(&env, &client.pubkey(), 500_000_000);
+    env.process(&[deposit_ix], &[&client]).await.unwrap();
+    let balance: ClientBalance = env.fetch(sol.client_balance).await;
+    assert_eq!(balance.destination, client.pubkey());
+
+    let wsol_account = env
+        .create_token_account(&native_mint::ID, &client.pubkey())
+        .await;
+    let set_destination_ix = set_client_destination_ix(
+        &env,
+        &client.pubkey(),
+        client.pubkey(),
+        native_mint::ID,
+        wsol_account,
+    );
+    env.process(&[set_destination_ix], &[&client])
+        .await
+        .unwrap();
+
+    // Depositing again doesn't reset the destination to the system account
+    let deposit_ix = deposit_sol_ix(&env, &client.pubkey(), 500_000_000);
+    env.process(&[deposit_ix], &[&client]).await.unwrap();
+    let balance: ClientBalance = env.fetch(sol.client_balance).await;
+    assert_eq!(balance.amount, 1_000_000_000);
+    assert_eq!(balance.destination, wsol_account);
+}
+
+#[tokio::test]
+async fn withdrawal_is_released_after_the_release_lock() {
+    let mut env = TestEnv::new().await;
+    let mint = env.whitelisted_mint(6, 2).await;
+    let (client, client_ata) = env.new_client(&mint, 1_000).await;
+    env.deposit(&client, client_ata, mint, 1_000).await;
+
+    let fundlock_token_vault = pda::fundlock_token_vault(&env.fundlock, &mint).0;
+    let client_balance = pda::client_balance(&fundlock_token_vault, &client_ata).0;
+    let withdrawals = pda::withdrawals(&env.fundlock, &client_balance).0;
+
+    let withdraw_ix = env.withdraw_ix(&client.pubkey(), client_ata, mint, 400);
+    env.process(&[withdraw_ix], &[&client]).await.unwrap();
+
+    let balance: ClientBalance = env.fetch(client_balance).await;
+    assert_eq!(balance.amount, 600);
+    let queue: Withdrawals = env.fetch(withdrawals).await;
+    assert_eq!(queue.withdrawal_queue.len(), 1);
+    assert_eq!(queue.withdrawal_queue[0].amount, 400);
+    assert_eq!(queue.active_withdrawals_amount, 400);
+
+    // Funds stay in the fundlock until the release lock has passed
+    let release_ix = env.release_ix(&client.pubkey(), client_ata, mint, 0);
+    let err = env
+        .process(std::slice::from_ref(&release_ix), &[&client])
+        .await
+        .unwrap_err();
+    assert_eq!(error_code(err), u32::from(FundlockError::ReleaseLockActive));
+
+    env.advance_clock(RELEASE_LOCK + 1).await;
+    env.process(&[release_ix], &[&client]).await.unwrap();
+
+    let queue: Withdrawals = env.fetch(withdrawals).await;
+    assert!(queue.withdrawal_queue.is_empty());
+    assert_eq!(queue.active_withdrawals_amount, 0);
+    assert_eq!(env.token_balance(client_ata).await, 400);
+    assert_eq!(env.token_balance(fundlock_token_vault).await, 600);
+}
+
+#[tokio::test]
+async fn matured_withdrawals_are_released_together() {
+    let mut env = TestEnv::new().await;
+    let mint = env.whitelisted_mint(6, 2).await;
+    let (client, client_ata) = env.new_client(&mint, 1_000).await;
+    env.deposit(&client, client_ata, mint, 1_000).await;
+
+    let fundlock_token_vault = pda::fundlock_token_vault(&env.fundlock, &mint).0;
+    let client_balance = pda::client_balance(&fundlock_token_vault, &client_ata).0;
+    let withdrawals = pda::withdrawals(&env.fundlock, &client_balance).0;
+
+    let release_all_ix = env.release_all_matured_ix(&client.pubkey(), client_ata, client_ata, mint);
+    let err = env
+        .process(std::slice::from_ref(&release_all_ix), &[&client])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(FundlockError::NoMaturedWithdrawals)
+    );
+
+    for amount in [100, 200] {
+        let withdraw_ix = env.withdraw_ix(&client.pubkey(), client_ata, mint, amount);
+        env.process(&[withdraw_ix], &[&client]).await.unwrap();
+    }
+    env.advance_clock(RELEASE_LOCK + 1).await;
+    let withdraw_ix = env.withdraw_ix(&client.pubkey(), client_ata, mint, 300);
+    env.process(&[withdraw_ix], &[&client]).await.unwrap();
+
+    // Both matured entries go out in one transfer and the pending one moves to the front
+    env.process(std::slice::from_ref(&release_all_ix), &[&client])
+        .await
+        .unwrap();
+    assert_eq!(env.token_balance(client_ata).await, 300);
+    let queue: Withdrawals = env.fetch(withdrawals).await;
+    assert_eq!(queue.withdrawal_queue.len(), 1);
+    assert_eq!(queue.withdrawal_queue[0].amount, 300);
+    assert_eq!(queue.active_withdrawals_amount, 300);
+
+    env.advance_clock(RELEASE_LOCK + 1).await;
+    env.process(&[release_all_ix], &[&client]).await.unwrap();
+    assert_eq!(env.token_balance(client_ata).await, 600);
+    assert_eq!(env.token_balance(fundlock_token_vault).await, 400);
+    let queue: Withdrawals = env.fetch(withdrawals).await;
+    assert!(queue.withdrawal_queue.is_empty());
+    assert_eq!(queue.active_withdrawals_amount, 0);
+}
+
+fn crank_release_ix(
+    env: &TestEnv,
+    keeper: &Pubkey,
+    client_ata: Pubkey,
+    destination: Pubkey,
+    mint: Pubkey,
+    keeper_pool: Option<Pubkey>,
+) -> Instruction {
+    let fundlock_token_vault = pda::fundlock_token_vault(&env.fundlock, &mint).0;
+    let client_balance = pda::client_balance(&fundlock_token_vault, &client_ata).0;
+    ix(
+        accounts::CrankRelease {
+            keeper: *keeper,
+            access_controller: env.access_controller,
+            token_validator: env.token_validator,
+            fundlock: env.fundlock,
+            token: mint,
+            whitelisted_token: pda::whitelisted_token(&env.token_validator, &mint).0,
+            fundlock_token_vault,
+            client_balance,
+            destination,
+            withdrawals: pda::withdrawals(&env.fundlock, &client_balance).0,
+            keeper_pool,
+            token_program: env.token_program(&mint),
+        },
+        instruction::CrankRelease {},
+    )
+}
+
+fn set_keeper_tip_ix(env: &TestEnv, tip: u64, min_release: u64) -> Instruction {
+    let admin = env.admin.pubkey();
+    ix(
+        accounts::SetKeeperTip {
+            admin,
+            access_controller: env.access_controller,
+            role: env.role(Roles::Admin),
+            member: env.member(&admin),
+            token_validator: env.token_validator,
+            fundlock: env.fundlock,
+            keeper_pool: pda::keeper_pool(&env.fundlock).0,
+            system_program: system_program::ID,
+        },
+        instruction::SetKeeperTip { tip, min_release },
+    )
+}
+
+#[tokio::test]
+async fn keepers_crank_releases_to_the_recorded_account_for_a_tip() {
+    let mut env = TestEnv::new().await;
+    let mint = env.whitelisted_mint(6, 2).await;
+    let (client, client_ata) = env.new_client(&mint, 1_000).await;
+    env.deposit(&client, client_ata, mint, 1_000).await;
+    let keeper = Keypair::new();
+    let keeper_pool = pda::keeper_pool(&env.fundlock).0;
+
+    let set_tip_ix = set_keeper_tip_ix(&env, 5_000, 100);
+    let fund_pool_ix = system_instruction::transfer(&env.admin.pubkey(), &keeper_pool, 8_000);
+    let fund_keeper_ix =
+        system_instruction::transfer(&env.admin.pubkey(), &keeper.pubkey(), 1_000_000_000);
+    env.process(&[set_tip_ix, fund_pool_ix, fund_keeper_ix], &[])
+        .await
+        .unwrap();
+    let pool: KeeperPool = env.fetch(keeper_pool).await;
+    assert_eq!(pool.fundlock, env.fundlock);
+    assert_eq!(pool.tip, 5_000);
+    assert_eq!(pool.min_release, 100);
+
+    for amount in [400, 100] {
+        let withdraw_ix = env.withdraw_ix(&client.pubkey(), client_ata, mint, amount);
+        env.process(&[withdraw_ix], &[&client]).await.unwrap();
+    }
+    let crank_ix = crank_release_ix(
+        &env,
+        &keeper.pubkey(),
+        client_ata,
+        client_ata,
+        mint,
+        Some(keeper_pool),
+    );
+    let err = env
+        .process(std::slice::from_ref(&crank_ix), &[&keeper])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(FundlockError::NoMaturedWithdrawals)
+    );
+
+    // The tokens can't be redirected to the keeper
+    env.advance_clock(RELEASE_LOCK + 1).await;
+    let keeper_ata = env.create_token_account(&mint, &keeper.pubkey()).await;
+    let mut redirected_crank_ix = crank_ix.clone();
+    redirected_crank_ix.accounts[8].pubkey = keeper_ata;
+    assert!(env
+        .process(&[redirected_crank_ix], &[&keeper])
+        .await
+        .is_err());
+
+    env.process(&[crank_ix], &[&keeper]).await.unwrap();
+    assert_eq!(env.token_balance(client_ata).await, 500);
+    assert_eq!(env.lamports(keeper.pubkey()).await, 1_000_005_000);
+    let queue: Withdrawals = env
+        .fetch(
+            pda::withdrawals(
+                &env.fundlock,
+                &pda::client_balance(
+                    &pda::fundlock_token_vault(&env.fundlock, &mint).0,
+                    &client_ata,
+                )
+                .0,
+            )
+            .0,
+        )
+        .await;
+    assert!(queue.withdrawal_queue.is_empty());
+
+    // Once the pool can't pay the tip while staying rent exempt, cranks go on without it
+    let withdraw_ix = env.withdraw_ix(&client.pubkey(), client_ata, mint, 100);
+    env.process(&[withdraw_ix], &[&client]).await.unwrap();
+    env.advance_clock(RELEASE_LOCK + 1).await;
+    let crank_ix = crank_release_ix(
+        &env,
+        &keeper.pubkey(),
+        client_ata,
+        client_ata,
+        mint,
+        Some(keeper_pool),
+    );
+    env.process(&[crank_ix], &[&keeper]).await.unwrap();
+    assert_eq!(env.token_balance(client_ata).await, 600);
+    assert_eq!(env.lamports(keeper.pubkey()).await, 1_000_005_000);
+
+    // Clients cranking their own withdrawals aren't tipped
+    let fund_pool_ix = system_instruction::transfer(&env.admin.pubkey(), &keeper_pool, 10_000);
+    env.process(&[fund_pool_ix], &[]).await.unwrap();
+    let pool_lamports = env.lamports(keeper_pool).await;
+    let withdraw_ix = env.withdraw_ix(&client.pubkey(), client_ata, mint, 100);
+    env.process(&[withdraw_ix], &[&client]).await.unwrap();
+    env.advance_clock(RELEASE_LOCK + 1).await;
+    let client_lamports = env.lamports(client.pubkey()).await;
+    let crank_ix = crank_release_ix(
+        &env,
+        &client.pubkey(),
+        client_ata,
+        client_ata,
+        mint,
+        Some(keeper_pool),
+    );
+    env.process(&[crank_ix], &[&client]).await.unwrap();
+    assert_eq!(env.token_balance(client_ata).await, 700);
+    assert_eq!(env.lamports(client.pubkey()).await, client_lamports);
+    assert_eq!(env.lamports(keeper_pool).await, pool_lamports);
+
+    // nor are releases below the floor, even when cranked from another key
+    let withdraw_ix = env.withdraw_ix(&client.pubkey(), client_ata, mint, 99);
+    env.process(&[withdraw_ix], &[&client]).await.unwrap();
+    env.advance_clock(RELEASE_LOCK + 1).await;
+    let keeper_lamports = env.lamports(keeper.pubkey()).await;
+    let crank_ix = crank_release_ix(
+        &env,
+        &keeper.pubkey(),
+        client_ata,
+        client_ata,
+        mint,
+        Some(keeper_pool),
+    );
+    env.process(&[crank_ix], &[&keeper]).await.unwrap();
+    assert_eq!(env.token_balance(client_ata).await, 799);
+    assert_eq!(env.lamports(keeper.pubkey()).await, keeper_lamports);
+    assert_eq!(env.lamports(keeper_pool).await, pool_lamports);
+}
+
+fn set_client_destination_ix(
+    env: &TestEnv,
+    client: &Pubkey,
+    client_ata: Pubkey,
+    mint: Pubkey,
+    destination: Pubkey,
+) -> Instruction {
+    let fundlock_token_vault = pda::fundlock_token_vault(&env.fundlock, &mint).0;
+    ix(
+        accounts::SetClientDestination {
+            client: *client,
+            access_controller: env.access_controller,
+            token_validator: env.token_validator,
+            fundlock: env.fundlock,
+            token: mint,
+            fundlock_token_vault,
+            client_balance: pda::client_balance(&fundlock_token_vault, &client_ata).0,
+            destination,
+        },
+        instruction::SetClientDestination {},
+    )
+}
+
+fn release_to_ix(
+    env: &TestEnv,
+    client: &Pubkey,
+    client_ata: Pubkey,
+    mint: Pubkey,
+    destination: Pubkey,
+    index: u64,
+) -> Instruction {
+    let fundlock_token_vault = pda::fundlock_token_vault(&env.fundlock, &mint).0;
+    let client_balance = pda::client_balance(&fundlock_token_vault, &client_ata).0;
+    ix(
+        accounts::ReleaseFundlockTo {
+            client: *client,
+            access_controller: env.access_controller,
+            token_validator: env.token_validator,
+            fundlock: env.fundlock,
+            token: mint,
+            whitelisted_token: pda::whitelisted_token(&env.token_validator, &mint).0,
+            fundlock_token_vault,
+            client_balance,
+            destination,
+            withdrawals: pda::withdrawals(&env.fundlock, &client_balance).0,
+            system_program: system_program::ID,
+            token_program: env.token_program(&mint),
+        },
+        instruction::ReleaseFundlockTo { index },
+    )
+}
+
+#[tokio::test]
+async fn balances_outlive_the_token_account_they_were_deposited_from() {
+    let mut env = TestEnv::new().await;
+    let mint = env.whitelisted_mint(6, 2).await;
+    let (client, client_ata) = env.new_client(&mint, 1_000).await;
+    env.deposit(&client, client_ata, mint, 1_000).await;
+    let client_balance = pda::client_balance(
+        &pda::fundlock_token_vault(&env.fundlock, &mint).0,
+        &client_ata,
+    )
+    .0;
+    let balance: ClientBalance = env.fetch(client_balance).await;
+    assert_eq!(balance.destination, client_ata);
+
+    // The emptied token account can be closed and withdrawals still get queued
+    let close_ix = spl_token::instruction::close_account(
+        &spl_token::ID,
+        &client_ata,
+        &client.pubkey(),
+        &client.pubkey(),
+        &[],
+    )
+    .unwrap();
+    env.process(&[close_ix], &[&client]).await.unwrap();
+    assert!(!env.exists(client_ata).await);
+    let withdraw_ix = env.withdraw_ix(&client.pubkey(), client_ata, mint, 300);
+    env.process(&[withdraw_ix], &[&client]).await.unwrap();
+
+    // Destinations have to be token accounts of the client for the same mint
+    let (stranger, stranger_ata) = env.new_client(&mint, 0).await;
+    let set_destination_ix =
+        set_client_destination_ix(&env, &client.pubkey(), client_ata, mint, stranger_ata);
+    assert!(env
+        .process(&[set_destination_ix], &[&client])
+        .await
+        .is_err());
+    let withdraw_ix = env.withdraw_ix(&stranger.pubkey(), client_ata, mint, 100);
+    let err = env.process(&[withdraw_ix], &[&stranger]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(ErrorCode::ConstraintHasOne));
+
+    let new_ata = env.create_token_account(&mint, &client.pubkey()).await;
+    let set_destination_ix =
+        set_client_destination_ix(&env, &client.pubkey(), client_ata, mint, new_ata);
+    env.process(&[set_destination_ix], &[&client])
+        .await
+        .unwrap();
+    let balance: ClientBalance = env.fetch(client_balance).await;
+    assert_eq!(balance.destination, new_ata);
+    assert_eq!(balance.client_ata, client_ata);
+
+    // Client signed releases can go to any of the client's token accounts
+    env.advance_clock(RELEASE_LOCK + 1).await;
+    let release_ix = release_to_ix(&env, &client.pubkey(), client_ata, mint, stranger_ata, 0);
+    assert!(env.process(&[release_ix], &[&client]).await.is_err());
+    let other_ata = env.create_token_account(&mint, &client.pubkey()).await;
+    let release_ix = release_to_ix(&env, &client.pubkey(), client_ata, mint, other_ata, 0);
+    env.process(&[release_ix], &[&client]).await.unwrap();
+    assert_eq!(env.token_balance(other_ata).await, 300);
+    let withdraw_ix = env.withdraw_ix(&client.pubkey(), client_ata, mint, 100);
+    env.process(&[withdraw_ix], &[&client]).await.unwrap();
+    env.advance_clock(RELEASE_LOCK + 1).await;
+    let release_all_ix =
+        env.release_all_matured_ix(&client.pubkey(), client_ata, stranger_ata, mint);
+    assert!(env.process(&[release_all_ix], &[&client]).await.is_err());
+    let release_all_ix = env.release_all_matured_ix(&client.pubkey(), client_ata, other_ata, mint);
+    env.process(&[release_all_ix], &[&client]).await.unwrap();
+    assert_eq!(env.token_balance(other_ata).await, 400);
+
+    // Cranked releases follow the registered destination
+    let withdraw_ix = env.withdraw_ix(&client.pubkey(), client_ata, mint, 200);
+    env.process(&[withdraw_ix], &[&client]).await.unwrap();
+    env.advance_clock(RELEASE_LOCK + 1).await;
+    let keeper = env.admin.pubkey();
+    let crank_ix = crank_release_ix(&env, &keeper, client_ata, client_ata, mint, None);
+    assert!(env.process(&[crank_ix], &[]).await.is_err());
+    let crank_ix = crank_release_ix(&env, &keeper, client_ata, new_ata, mint, None);
+    env.process(&[crank_ix], &[]).await.unwrap();
+    assert_eq!(env.token_balance(new_ata).await, 200);
+}
+
+#[tokio::test]
+async fn withdrawals_are_bounded_by_balance_and_queue_limit() {
+    let mut env = TestEnv::new().await;
+    let mint = env.whitelisted_mint(6, 2).await;
+    let (client, client_ata) = env.new_client(&mint, 1_000).await;
+    env.deposit(&client, client_ata, mint, 100).await;
+
+    let withdraw_ix = env.withdraw_ix(&client.pubkey(), client_ata, mint, 101);
+    let err = env.process(&[withdraw_ix], &[&client]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(FundlockError::InsufficientFunds));
+
+    for _ in 0..DEFAULT_WITHDRAWAL_LIMIT {
+        let withdraw_ix = env.withdraw_ix(&client.pubkey(), client_ata, mint, 10);
+        env.process(&[withdraw_ix], &[&client]).await.unwrap();
+    }
+    let withdraw_ix = env.withdraw_ix(&client.pubkey(), client_ata, mint, 10);
+    let err = env.process(&[withdraw_ix], &[&client]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(FundlockError::WithdrawalLimitReached)
+    );
+
+    let release_ix = env.release_ix(&client.pubkey(), client_ata, mint, DEFAULT_WITHDRAWAL_LIMIT);
+    let err = env.process(&[release_ix], &[&client]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(FundlockError::InvalidIndex));
+}
+
+#[tokio::test]
+async fn queued_withdrawals_can_be_cancelled() {
+    let mut env = TestEnv::new().await;
+    let mint = env.whitelisted_mint(6, 2).await;
+    let (client, client_ata) = env.new_client(&mint, 1_000).await;
+    env.deposit(&client, client_ata, mint, 1_000).await;
+
+    let fundlock_token_vault = pda::fundlock_token_vault(&env.fundlock, &mint).0;
+    let client_balance = pda::client_balance(&fundlock_token_vault, &client_ata).0;
+    let withdrawals = pda::withdrawals(&env.fundlock, &client_balance).0;
+    for amount in [300, 200] {
+        let withdraw_ix = env.withdraw_ix(&client.pubkey(), client_ata, mint, amount);
+        env.process(&[withdraw_ix], &[&client]).await.unwrap();
+    }
+
+    // Only the client owning the balance can cancel its withdrawals
+    let impostor = Keypair::new();
+    let mut impostor_cancel_ix =
+        env.cancel_withdrawal_ix(&client.pubkey(), client_ata, mint, 0, 100);
+    impostor_cancel_ix.accounts[0].pubkey = impostor.pubkey();
+    let err = env
+        .process(&[impostor_cancel_ix], &[&impostor])
+        .await
+        .unwrap_err();
+    assert_eq!(error_code(err), u32::from(ErrorCode::ConstraintHasOne));
+
+    let cancel_ix = env.cancel_withdrawal_ix(&client.pubkey(), client_ata, mint, 0, 301);
+    let err = env.process(&[cancel_ix], &[&client]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(FundlockError::InsufficientFunds));
+
+    // A partial cancel shrinks the entry in place
+    let cancel_ix = env.cancel_withdrawal_ix(&client.pubkey(), client_ata, mint, 0, 100);
+    env.process(&[cancel_ix], &[&client]).await.unwrap();
+    let balance: ClientBalance = env.fetch(client_balance).await;
+    assert_eq!(balance.amount, 600);
+    let queue: Withdrawals = env.fetch(withdrawals).await;
+    assert_eq!(queue.withdrawal_queue[0].amount, 200);
+    assert_eq!(queue.active_withdrawals_amount, 400);
+
+    // Cancelling what's left of it drops the entry
+    let cancel_ix = env.cancel_withdrawal_ix(&client.pubkey(), client_ata, mint, 0, 200);
+    env.process(&[cancel_ix], &[&client]).await.unwrap();
+    let balance: ClientBalance = env.fetch(client_balance).await;
+    assert_eq!(balance.amount, 800);
+    let queue: Withdrawals = env.fetch(withdrawals).await;
+    assert_eq!(queue.withdrawal_queue.len(), 1);
+    assert_eq!(queue.withdrawal_queue[0].amount, 200);
+    assert_eq!(queue.active_withdrawals_amount, 200);
+
+    env.advance_clock(RELEASE_LOCK + 1).await;
+    let release_ix = env.release_ix(&client.pubkey(), client_ata, mint, 0);
+    env.process(&[release_ix], &[&client]).await.unwrap();
+    assert_eq!(env.token_balance(client_ata).await, 200);
+}
+
+#[tokio::test]
+async fn legacy_fundlock_is_migrated() {
+    let mut env = TestEnv::new().await;
+    let mint = env.whitelisted_mint(6, 2).await;
+    let (client, client_ata) = env.new_client(&mint, 1_000).await;
+
+    // Fundlocks didn't store a withdrawal limit before
+    let bump = pda::fundlock(&env.access_controller, &env.token_validator).1;
+    let mut data = Fundlock::DISCRIMINATOR.to_vec();
+    data.extend(
+        (
+            env.access_controller,
+            env.token_validator,
+            TRADE_LOCK,
+            RELEASE_LOCK,
+            bump,
+        )
+            .try_to_vec()
+            .unwrap(),
+    );
+    let fundlock = env.fundlock;
+    env.set_program_account(&fundlock, &data);
+    let deposit_ix = env.deposit_ix(&client.pubkey(), client_ata, mint, 400);
+    assert!(env.process(&[deposit_ix], &[&client]).await.is_err());
+
+    let migrate_ix = ix(
+        accounts::MigrateFundlock {
+            admin: env.admin.pubkey(),
+            access_controller: env.access_controller,
+            fundlock,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateFundlock {},
+    );
+    env.process(std::slice::from_ref(&migrate_ix), &[])
+        .await
+        .unwrap();
+    let fundlock_account: Fundlock = env.fetch(fundlock).await;
+    assert_eq!(fundlock_account.trade_lock, TRADE_LOCK);
+    assert_eq!(fundlock_account.release_lock, RELEASE_LOCK);
+    assert_eq!(fundlock_account.withdrawal_limit, DEFAULT_WITHDRAWAL_LIMIT);
+    env.deposit(&client, client_ata, mint, 400).await;
+
+    let err = env.process(&[migrate_ix], &[]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(FundlockError::AlreadyMigrated));
+}
+
+#[tokio::test]
+async fn legacy_client_balance_is_migrated() {
+    let mut env = TestEnv::new().await;
+    let mint = env.whitelisted_mint(6, 2).await;
+    let (client, client_ata) = env.new_client(&mint, 1_000).await;
+    env.deposit(&client, client_ata, mint, 600).await;
+
+    // Client balances didn't store a release destination before
+    let client_balance = pda::client_balance(
+        &pda::fundlock_token_vault(&env.fundlock, &mint).0,
+        &client_ata,
+    );
+    let mut data = ClientBalance::DISCRIMINATOR.to_vec();
+    data.extend(
+        (
+            600u64,
+            mint,
+            client.pubkey(),
+            client_ata,
+            0u64,
+            client_balance.1,
+        )
+            .try_to_vec()
+            .unwrap(),
+    );
+    env.set_program_account(&client_balance.0, &data);
+    let withdraw_ix = env.withdraw_ix(&client.pubkey(), client_ata, mint, 100);
+    assert!(env
+        .process(std::slice::from_ref(&withdraw_ix), &[&client])
+        .await
+        .is_err());
+
+    // Anyone can pay for the migration
+    let payer = Keypair::new();
+    let fund_payer_ix =
+        system_instruction::transfer(&env.admin.pubkey(), &payer.pubkey(), 1_000_000_000);
+    env.process(&[fund_payer_ix], &[]).await.unwrap();
+    let migrate_ix = ix(
+        accounts::MigrateClientBalance {
+            payer: payer.pubkey(),
+            client_balance: client_balance.0,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateClientBalance {},
+    );
+    env.process(std::slice::from_ref(&migrate_ix), &[&payer])
+        .await
+        .unwrap();
+    let balance: ClientBalance = env.fetch(client_balance.0).await;
+    assert_eq!(balance.amount, 600);
+    assert_eq!(balance.client, client.pubkey());
+    assert_eq!(balance.client_ata, client_ata);
+    assert_eq!(balance.destination, client_ata);
+    env.process(&[withdraw_ix], &[&client]).await.unwrap();
+
+    let err = env.process(&[migrate_ix], &[&payer]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(FundlockError::AlreadyMigrated));
+}
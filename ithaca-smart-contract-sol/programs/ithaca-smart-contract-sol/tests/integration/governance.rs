@@ -0,0 +1,476 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::system_program;
+use ithaca_client::pda;
+use ithaca_smart_contract_sol::error::{AccessControlError, FundlockError, GovernanceError};
+use ithaca_smart_contract_sol::{
+    accounts, instruction, FundMovementParamOptimized, Fundlock, Governance, Ledger,
+    ParameterChange, ParameterChangeRequest, TokenStatus, WhitelistedToken, Withdrawals,
+    MAX_RELEASE_LOCK, MAX_WITHDRAWAL_LIMIT,
+};
+use solana_sdk::signature::{Keypair, Signer};
+
+use crate::env::{error_code, ix, TestEnv};
+use crate::ledger::Market;
+
+const MIN_DELAY: i64 = 3600;
+
+async fn init_governance(env: &mut TestEnv) -> Pubkey {
+    let governance = pda::governance(&env.access_controller).0;
+    let init_governance_ix = ix(
+        accounts::InitGovernance {
+            admin: env.admin.pubkey(),
+            access_controller: env.access_controller,
+            governance,
+            system_program: system_program::ID,
+        },
+        instruction::InitGovernance {
+            min_delay: MIN_DELAY,
+        },
+    );
+    env.process(&[init_governance_ix], &[]).await.unwrap();
+    governance
+}
+
+fn schedule_ix(
+    env: &TestEnv,
+    change_id: u64,
+    target: Pubkey,
+    change: ParameterChange,
+    eta: i64,
+) -> Instruction {
+    let governance = pda::governance(&env.access_controller).0;
+    ix(
+        accounts::ScheduleParameterChange {
+            admin: env.admin.pubkey(),
+            access_controller: env.access_controller,
+            governance,
+            target,
+            parameter_change: pda::parameter_change(&governance, change_id).0,
+            system_program: system_program::ID,
+        },
+        instruction::ScheduleParameterChange { change, eta },
+    )
+}
+
+fn execute_ix(env: &TestEnv, change_id: u64, target: Pubkey) -> Instruction {
+    let governance = pda::governance(&env.access_controller).0;
+    ix(
+        accounts::ExecuteParameterChange {
+            executor: env.admin.pubkey(),
+            access_controller: env.access_controller,
+            governance,
+            parameter_change: pda::parameter_change(&governance, change_id).0,
+            target,
+            proposer: env.admin.pubkey(),
+        },
+        instruction::ExecuteParameterChange {},
+    )
+}
+
+fn cancel_ix(env: &TestEnv, admin: &Pubkey, change_id: u64) -> Instruction {
+    let governance = pda::governance(&env.access_controller).0;
+    ix(
+        accounts::CancelParameterChange {
+            admin: *admin,
+            access_controller: env.access_controller,
+            governance,
+            parameter_change: pda::parameter_change(&governance, change_id).0,
+            proposer: env.admin.pubkey(),
+        },
+        instruction::CancelParameterChange {},
+    )
+}
+
+#[tokio::test]
+async fn parameter_changes_are_executed_after_the_delay() {
+    let mut env = TestEnv::new().await;
+    let governance = init_governance(&mut env).await;
+    let change = ParameterChange::FundlockTradeLock(60);
+    let now = env.now().await;
+
+    let schedule = schedule_ix(&env, 0, env.fundlock, change, now + MIN_DELAY - 1);
+    let err = env.process(&[schedule], &[]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(GovernanceError::EtaTooEarly));
+
+    let eta = now + MIN_DELAY;
+    let schedule = schedule_ix(&env, 0, env.fundlock, change, eta);
+    env.process(&[schedule], &[]).await.unwrap();
+
+    // The queued change can be inspected until it's executed
+    let request: ParameterChangeRequest = env.fetch(pda::parameter_change(&governance, 0).0).await;
+    assert_eq!(request.target, env.fundlock);
+    assert_eq!(request.change, change);
+    assert_eq!(request.eta, eta);
+    let governance_account: Governance = env.fetch(governance).await;
+    assert_eq!(governance_account.change_count, 1);
+
+    let execute = execute_ix(&env, 0, env.fundlock);
+    let err = env
+        .process(std::slice::from_ref(&execute), &[])
+        .await
+        .unwrap_err();
+    assert_eq!(error_code(err), u32::from(GovernanceError::ChangeNotReady));
+
+    env.advance_clock(MIN_DELAY).await;
+    env.process(&[execute], &[]).await.unwrap();
+    let fundlock: Fundlock = env.fetch(env.fundlock).await;
+    assert_eq!(fundlock.trade_lock, 60);
+    assert!(!env.exists(pda::parameter_change(&governance, 0).0).await);
+}
+
+#[tokio::test]
+async fn parameter_changes_can_be_cancelled_by_the_admin() {
+    let mut env = TestEnv::new().await;
+    let governance = init_governance(&mut env).await;
+    let eta = env.now().await + MIN_DELAY;
+    let schedule = schedule_ix(
+        &env,
+        0,
+        env.fundlock,
+        ParameterChange::FundlockReleaseLock(0),
+        eta,
+    );
+    env.process(&[schedule], &[]).await.unwrap();
+
+    let impostor = Keypair::new();
+    let cancel = cancel_ix(&env, &impostor.pubkey(), 0);
+    let err = env.process(&[cancel], &[&impostor]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(AccessControlError::UnauthorizedAdmin)
+    );
+
+    let cancel = cancel_ix(&env, &env.admin.pubkey(), 0);
+    env.process(&[cancel], &[]).await.unwrap();
+    assert!(!env.exists(pda::parameter_change(&governance, 0).0).await);
+
+    env.advance_clock(MIN_DELAY).await;
+    assert!(env
+        .process(&[execute_ix(&env, 0, env.fundlock)], &[])
+        .await
+        .is_err());
+    let fundlock: Fundlock = env.fetch(env.fundlock).await;
+    assert_eq!(fundlock.release_lock, crate::env::RELEASE_LOCK);
+}
+
+#[tokio::test]
+async fn fundlock_locks_are_bounded_like_at_init() {
+    let mut env = TestEnv::new().await;
+    init_governance(&mut env).await;
+    let eta = env.now().await + MIN_DELAY;
+
+    let rejected = [
+        (
+            ParameterChange::FundlockTradeLock(0),
+            FundlockError::InvalidTradeLock,
+        ),
+        (
+            ParameterChange::FundlockReleaseLock(-1),
+            FundlockError::InvalidReleaseLock,
+        ),
+        (
+            ParameterChange::FundlockReleaseLock(MAX_RELEASE_LOCK),
+            FundlockError::ReleaseLockTooLong,
+        ),
+    ];
+    for (change, error) in rejected {
+        let schedule = schedule_ix(&env, 0, env.fundlock, change, eta);
+        let err = env.process(&[schedule], &[]).await.unwrap_err();
+        assert_eq!(error_code(err), u32::from(error));
+    }
+
+    let schedule = schedule_ix(
+        &env,
+        0,
+        env.fundlock,
+        ParameterChange::FundlockReleaseLock(MAX_RELEASE_LOCK - 1),
+        eta,
+    );
+    env.process(&[schedule], &[]).await.unwrap();
+    env.advance_clock(MIN_DELAY).await;
+    env.process(&[execute_ix(&env, 0, env.fundlock)], &[])
+        .await
+        .unwrap();
+    let fundlock: Fundlock = env.fetch(env.fundlock).await;
+    assert_eq!(fundlock.release_lock, MAX_RELEASE_LOCK - 1);
+}
+
+#[tokio::test]
+async fn token_and_ledger_parameters_are_validated() {
+    let mut market = Market::new().await;
+    let ledger: Ledger = market.env.fetch(market.ledger).await;
+    let env = &mut market.env;
+    init_governance(env).await;
+    let whitelisted_token =
+        pda::whitelisted_token(&env.token_validator, &ledger.underlying_token).0;
+    let eta = env.now().await + MIN_DELAY;
+
+    // The precision can't exceed the token decimals
+    let schedule = schedule_ix(
+        env,
+        0,
+        whitelisted_token,
+        ParameterChange::TokenPrecision(10),
+        eta,
+    );
+    let err = env.process(&[schedule], &[]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(GovernanceError::InvalidParameterValue)
+    );
+
+    // The target has to be the account of the changed parameter
+    let schedule = schedule_ix(
+        env,
+        0,
+        env.fundlock,
+        ParameterChange::LedgerUnderlyingMultiplier(100),
+        eta,
+    );
+    assert!(env.process(&[schedule], &[]).await.is_err());
+
+    let rent_payer = Keypair::new().pubkey();
+    let insurance_fund = Keypair::new().pubkey();
+    let changes = [
+        (
+            market.ledger,
+            ParameterChange::LedgerUnderlyingMultiplier(1_000_000),
+        ),
+        (market.ledger, ParameterChange::LedgerStrikeMultiplier(10)),
+        (market.ledger, ParameterChange::LedgerRentPayer(rent_payer)),
+        (
+            market.ledger,
+            ParameterChange::LedgerInsuranceFund(insurance_fund),
+        ),
+    ];
+    let schedules: Vec<_> = changes
+        .iter()
+        .enumerate()
+        .map(|(change_id, (target, change))| {
+            schedule_ix(env, change_id as u64, *target, *change, eta)
+        })
+        .collect();
+    env.process(&schedules, &[]).await.unwrap();
+
+    env.advance_clock(MIN_DELAY).await;
+    let executions: Vec<_> = changes
+        .iter()
+        .enumerate()
+        .map(|(change_id, (target, _))| execute_ix(env, change_id as u64, *target))
+        .collect();
+    env.process(&executions, &[]).await.unwrap();
+
+    let ledger: Ledger = env.fetch(market.ledger).await;
+    assert_eq!(ledger.underlying_multiplier, 1_000_000);
+    assert_eq!(ledger.strike_multiplier, 10);
+    assert_eq!(ledger.rent_payer, rent_payer);
+    assert_eq!(ledger.insurance_fund, insurance_fund);
+}
+
+#[tokio::test]
+async fn token_precision_is_fixed_once_the_token_has_ledgers() {
+    let mut env = TestEnv::new().await;
+    init_governance(&mut env).await;
+    let underlying_token = env.whitelisted_mint(9, 4).await;
+    let strike_token = env.whitelisted_mint(6, 2).await;
+    let whitelisted_underlying_token =
+        pda::whitelisted_token(&env.token_validator, &underlying_token).0;
+    let whitelisted_strike_token = pda::whitelisted_token(&env.token_validator, &strike_token).0;
+    let eta = env.now().await + MIN_DELAY;
+
+    // Without ledgers the precision can change, the ledger then derives its multiplier from it
+    let schedule = schedule_ix(
+        &env,
+        0,
+        whitelisted_underlying_token,
+        ParameterChange::TokenPrecision(3),
+        eta,
+    );
+    env.process(&[schedule], &[]).await.unwrap();
+    // Queued before the ledger exists, executed after
+    let schedule = schedule_ix(
+        &env,
+        1,
+        whitelisted_strike_token,
+        ParameterChange::TokenPrecision(1),
+        eta,
+    );
+    env.process(&[schedule], &[]).await.unwrap();
+    env.advance_clock(MIN_DELAY).await;
+    env.process(&[execute_ix(&env, 0, whitelisted_underlying_token)], &[])
+        .await
+        .unwrap();
+
+    let mut market = Market::with_tokens(env, underlying_token, strike_token).await;
+    let token: WhitelistedToken = market.env.fetch(whitelisted_underlying_token).await;
+    assert_eq!(token.token_precision, 3);
+    assert_eq!(token.ledger_count, 1);
+    let ledger: Ledger = market.env.fetch(market.ledger).await;
+    assert_eq!(ledger.underlying_multiplier, 1_000_000);
+
+    let buyer = market.trader(3_000_000, 100_000).await;
+    let seller = market.trader(3_000_000, 100_000).await;
+    let fund_movements_ix = market.fund_movements_ix(
+        vec![
+            (
+                &buyer,
+                FundMovementParamOptimized {
+                    underlying_amount: -2,
+                    strike_amount: 3,
+                },
+            ),
+            (
+                &seller,
+                FundMovementParamOptimized {
+                    underlying_amount: 2,
+                    strike_amount: -3,
+                },
+            ),
+        ],
+        1,
+    );
+    market.env.process(&[fund_movements_ix], &[]).await.unwrap();
+    // Moved at the new underlying multiplier of 10^6
+    assert_eq!(market.balances(&buyer).await, (5_000_000, 70_000));
+    assert_eq!(market.balances(&seller).await, (1_000_000, 130_000));
+
+    // The ledger's multipliers would go stale, so the strike change can't execute anymore
+    let env = &mut market.env;
+    let execute = execute_ix(env, 1, whitelisted_strike_token);
+    let err = env.process(&[execute], &[]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(GovernanceError::TokenHasLedgers));
+
+    // nor can a new one be scheduled
+    let eta = env.now().await + MIN_DELAY;
+    let schedule = schedule_ix(
+        env,
+        2,
+        whitelisted_underlying_token,
+        ParameterChange::TokenPrecision(2),
+        eta,
+    );
+    let err = env.process(&[schedule], &[]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(GovernanceError::TokenHasLedgers));
+}
+
+#[tokio::test]
+async fn token_config_is_loosened_through_governance() {
+    let mut env = TestEnv::new().await;
+    init_governance(&mut env).await;
+    let mint = env.whitelisted_mint(6, 2).await;
+    let whitelisted_token = pda::whitelisted_token(&env.token_validator, &mint).0;
+    let config_ix = env.set_token_config_ix(mint, TokenStatus::WithdrawOnly, Some(10), Some(5), 3);
+    env.process(&[config_ix], &[]).await.unwrap();
+    let eta = env.now().await + MIN_DELAY;
+
+    // The target has to be a whitelisted token of the access controller
+    let schedule = schedule_ix(
+        &env,
+        0,
+        env.fundlock,
+        ParameterChange::TokenStatus(TokenStatus::Active),
+        eta,
+    );
+    assert!(env.process(&[schedule], &[]).await.is_err());
+
+    let changes = [
+        ParameterChange::TokenStatus(TokenStatus::Active),
+        ParameterChange::TokenDepositCap(None),
+        ParameterChange::TokenClientDepositCap(Some(1_000)),
+        ParameterChange::TokenMinDeposit(0),
+    ];
+    let schedules: Vec<_> = changes
+        .iter()
+        .enumerate()
+        .map(|(change_id, change)| {
+            schedule_ix(&env, change_id as u64, whitelisted_token, *change, eta)
+        })
+        .collect();
+    env.process(&schedules, &[]).await.unwrap();
+
+    env.advance_clock(MIN_DELAY).await;
+    let executions: Vec<_> = (0..changes.len())
+        .map(|change_id| execute_ix(&env, change_id as u64, whitelisted_token))
+        .collect();
+    env.process(&executions, &[]).await.unwrap();
+
+    let token: WhitelistedToken = env.fetch(whitelisted_token).await;
+    assert_eq!(token.status, TokenStatus::Active);
+    assert_eq!(token.deposit_cap, None);
+    assert_eq!(token.client_deposit_cap, Some(1_000));
+    assert_eq!(token.min_deposit, 0);
+    let (client, client_ata) = env.new_client(&mint, 1_000).await;
+    env.deposit(&client, client_ata, mint, 1_000).await;
+}
+
+#[tokio::test]
+async fn raised_withdrawal_limit_grows_the_queue() {
+    let mut env = TestEnv::new().await;
+    init_governance(&mut env).await;
+    let mint = env.whitelisted_mint(6, 2).await;
+    let (client, client_ata) = env.new_client(&mint, 1_000).await;
+    env.deposit(&client, client_ata, mint, 1_000).await;
+    let eta = env.now().await + MIN_DELAY;
+
+    let schedule = schedule_ix(
+        &env,
+        0,
+        env.fundlock,
+        ParameterChange::FundlockWithdrawalLimit(MAX_WITHDRAWAL_LIMIT + 1),
+        eta,
+    );
+    let err = env.process(&[schedule], &[]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(GovernanceError::InvalidParameterValue)
+    );
+
+    let schedule = schedule_ix(
+        &env,
+        0,
+        env.fundlock,
+        ParameterChange::FundlockWithdrawalLimit(8),
+        eta,
+    );
+    env.process(&[schedule], &[]).await.unwrap();
+    env.advance_clock(MIN_DELAY).await;
+    let execute = execute_ix(&env, 0, env.fundlock);
+    env.process(&[execute], &[]).await.unwrap();
+    let fundlock: Fundlock = env.fetch(env.fundlock).await;
+    assert_eq!(fundlock.withdrawal_limit, 8);
+
+    // The queue account is reallocated past its initial size as withdrawals come in
+    let withdrawals = pda::withdrawals(
+        &env.fundlock,
+        &pda::client_balance(
+            &pda::fundlock_token_vault(&env.fundlock, &mint).0,
+            &client_ata,
+        )
+        .0,
+    )
+    .0;
+    for amount in 1..=8 {
+        let withdraw_ix = env.withdraw_ix(&client.pubkey(), client_ata, mint, amount);
+        env.process(&[withdraw_ix], &[&client]).await.unwrap();
+    }
+    let queue: Withdrawals = env.fetch(withdrawals).await;
+    assert_eq!(queue.withdrawal_queue.len(), 8);
+    assert_eq!(queue.withdrawal_queue[7].amount, 8);
+    assert_eq!(queue.active_withdrawals_amount, 36);
+    let account = env
+        .context
+        .banks_client
+        .get_account(withdrawals)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data.len(), Withdrawals::space(8));
+
+    let withdraw_ix = env.withdraw_ix(&client.pubkey(), client_ata, mint, 1);
+    let err = env.process(&[withdraw_ix], &[&client]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(FundlockError::WithdrawalLimitReached)
+    );
+}
@@ -0,0 +1,203 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::{instruction::Instruction, sysvar};
+use anchor_lang::system_program;
+use anchor_spl::token::spl_token;
+use ithaca_client::pda;
+use ithaca_smart_contract_sol::error::FundlockError;
+use ithaca_smart_contract_sol::{accounts, instruction, ClientBalance};
+use solana_sdk::signature::{Keypair, Signer};
+
+use crate::env::{error_code, ix, TestEnv};
+use crate::fake_klend::{lending_market_authority, COLLATERAL_PER_LIQUIDITY};
+
+// Reserve of the fake KLend program for a whitelisted liquidity token
+struct Reserve {
+    reserve: Pubkey,
+    lending_market: Pubkey,
+    lending_market_authority: Pubkey,
+    liquidity_mint: Pubkey,
+    liquidity_supply: Pubkey,
+    collateral_mint: Pubkey,
+}
+
+impl Reserve {
+    async fn new(env: &mut TestEnv) -> Self {
+        let lending_market = Keypair::new().pubkey();
+        let (authority, _) = lending_market_authority(&lending_market);
+        let liquidity_mint = env.whitelisted_mint(6, 2).await;
+        let liquidity_supply = env.create_token_account(&liquidity_mint, &authority).await;
+        let collateral_mint = env.create_mint(6, &authority).await;
+        Self {
+            reserve: Keypair::new().pubkey(),
+            lending_market,
+            lending_market_authority: authority,
+            liquidity_mint,
+            liquidity_supply,
+            collateral_mint,
+        }
+    }
+
+    fn fundlock_token_vault(&self, env: &TestEnv) -> Pubkey {
+        pda::fundlock_token_vault(&env.fundlock, &self.liquidity_mint).0
+    }
+
+    fn fundlock_collateral_vault(&self, env: &TestEnv) -> Pubkey {
+        pda::fundlock_collateral_vault(&self.fundlock_token_vault(env), &self.collateral_mint).0
+    }
+
+    fn deposit_ix(
+        &self,
+        env: &TestEnv,
+        client: &Pubkey,
+        client_ata: Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        let fundlock_token_vault = self.fundlock_token_vault(env);
+        ix(
+            accounts::DepositKamino {
+                client: *client,
+                access_controller: env.access_controller,
+                token_validator: env.token_validator,
+                fundlock: env.fundlock,
+                token: self.liquidity_mint,
+                whitelisted_token: pda::whitelisted_token(
+                    &env.token_validator,
+                    &self.liquidity_mint,
+                )
+                .0,
+                fundlock_token_vault,
+                client_ata,
+                client_balance: pda::client_balance(&fundlock_token_vault, &client_ata).0,
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                collateral_token_program: spl_token::ID,
+                reserve: self.reserve,
+                lending_market: self.lending_market,
+                lending_market_authority: self.lending_market_authority,
+                reserve_liquidity_supply: self.liquidity_supply,
+                reserve_collateral_token: self.collateral_mint,
+                fundlock_collateral_vault: self.fundlock_collateral_vault(env),
+                kamino_program: kamino_lending_interface::ID,
+                instructions: sysvar::instructions::ID,
+            },
+            instruction::DepositKamino { amount },
+        )
+    }
+
+    fn redeem_ix(
+        &self,
+        env: &TestEnv,
+        client: &Pubkey,
+        client_ata: Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        let fundlock_token_vault = self.fundlock_token_vault(env);
+        ix(
+            accounts::ReedemKamino {
+                client: *client,
+                access_controller: env.access_controller,
+                token_validator: env.token_validator,
+                fundlock: env.fundlock,
+                token: self.liquidity_mint,
+                whitelisted_token: pda::whitelisted_token(
+                    &env.token_validator,
+                    &self.liquidity_mint,
+                )
+                .0,
+                fundlock_token_vault,
+                client_ata,
+                client_balance: pda::client_balance(&fundlock_token_vault, &client_ata).0,
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                collateral_token_program: spl_token::ID,
+                reserve: self.reserve,
+                lending_market: self.lending_market,
+                lending_market_authority: self.lending_market_authority,
+                reserve_liquidity_supply: self.liquidity_supply,
+                reserve_collateral_token: self.collateral_mint,
+                fundlock_collateral_vault: self.fundlock_collateral_vault(env),
+                kamino_program: kamino_lending_interface::ID,
+                instructions: sysvar::instructions::ID,
+            },
+            instruction::RedeemKamino { amount },
+        )
+    }
+}
+
+#[tokio::test]
+async fn fundlock_liquidity_is_deposited_into_and_redeemed_from_kamino() {
+    let mut env = TestEnv::new().await;
+    let reserve = Reserve::new(&mut env).await;
+    let (client, client_ata) = env.new_client(&reserve.liquidity_mint, 1_000).await;
+    env.deposit(&client, client_ata, reserve.liquidity_mint, 1_000)
+        .await;
+
+    let fundlock_token_vault = reserve.fundlock_token_vault(&env);
+    let fundlock_collateral_vault = reserve.fundlock_collateral_vault(&env);
+    let client_balance = pda::client_balance(&fundlock_token_vault, &client_ata).0;
+
+    let deposit_ix = reserve.deposit_ix(&env, &client.pubkey(), client_ata, 400);
+    env.process(&[deposit_ix], &[&client]).await.unwrap();
+
+    let collateral_amount = 400 * COLLATERAL_PER_LIQUIDITY;
+    assert_eq!(env.token_balance(fundlock_token_vault).await, 600);
+    assert_eq!(env.token_balance(reserve.liquidity_supply).await, 400);
+    assert_eq!(
+        env.token_balance(fundlock_collateral_vault).await,
+        collateral_amount
+    );
+    let balance: ClientBalance = env.fetch(client_balance).await;
+    assert_eq!(balance.amount, 600);
+    assert_eq!(balance.collateral_amount, collateral_amount);
+
+    let redeem_ix = reserve.redeem_ix(&env, &client.pubkey(), client_ata, collateral_amount / 2);
+    env.process(&[redeem_ix], &[&client]).await.unwrap();
+
+    assert_eq!(env.token_balance(fundlock_token_vault).await, 800);
+    assert_eq!(env.token_balance(reserve.liquidity_supply).await, 200);
+    assert_eq!(
+        env.token_balance(fundlock_collateral_vault).await,
+        collateral_amount / 2
+    );
+    // What the client holds in the fundlock and in Kamino stays what it deposited
+    let redeemed_balance: ClientBalance = env.fetch(client_balance).await;
+    assert_eq!(redeemed_balance.collateral_amount, collateral_amount / 2);
+    assert_eq!(redeemed_balance.amount, 800);
+    assert_eq!(
+        redeemed_balance.amount + redeemed_balance.collateral_amount / COLLATERAL_PER_LIQUIDITY,
+        1_000
+    );
+    assert_eq!(
+        redeemed_balance.amount,
+        env.token_balance(fundlock_token_vault).await
+    );
+}
+
+#[tokio::test]
+async fn kamino_amounts_are_bounded_by_the_client_balance() {
+    let mut env = TestEnv::new().await;
+    let reserve = Reserve::new(&mut env).await;
+    let (client, client_ata) = env.new_client(&reserve.liquidity_mint, 1_000).await;
+    env.deposit(&client, client_ata, reserve.liquidity_mint, 100)
+        .await;
+
+    let deposit_ix = reserve.deposit_ix(&env, &client.pubkey(), client_ata, 101);
+    let err = env.process(&[deposit_ix], &[&client]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(FundlockError::InsufficientFunds));
+
+    let deposit_ix = reserve.deposit_ix(&env, &client.pubkey(), client_ata, 0);
+    let err = env.process(&[deposit_ix], &[&client]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(FundlockError::AmountZero));
+
+    let deposit_ix = reserve.deposit_ix(&env, &client.pubkey(), client_ata, 50);
+    env.process(&[deposit_ix], &[&client]).await.unwrap();
+
+    let redeem_ix = reserve.redeem_ix(
+        &env,
+        &client.pubkey(),
+        client_ata,
+        50 * COLLATERAL_PER_LIQUIDITY + 1,
+    );
+    let err = env.process(&[redeem_ix], &[&client]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(FundlockError::InsufficientFunds));
+}
@@ -0,0 +1,920 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::{system_program, AnchorSerialize, Discriminator};
+use ithaca_client::pda;
+use ithaca_smart_contract_sol::error::{
+    AccessControlError, FundlockError, LedgerError, TokenValidatorError,
+};
+use ithaca_smart_contract_sol::{
+    accounts, instruction, ClientBalance, ClosedContract, Contract, ContractParams, ContractStatus,
+    FundMovementParamOptimized, Ledger, PayoffType, Position, PositionsParam, Roles, TokenStatus,
+    Withdrawals,
+};
+use solana_sdk::signature::{Keypair, Signer};
+
+use crate::env::{error_code, ix, TestEnv};
+
+// Time to expiry of the contracts created by the tests
+const EXPIRY_IN: i64 = 3_600;
+
+// Whitelisted underlying and strike tokens with an initialized ledger, the admin is also
+// granted the utility account role and is the caller submitting positions and fund movements
+pub struct Market {
+    pub env: TestEnv,
+    caller: Pubkey,
+    pub underlying_token: Pubkey,
+    pub strike_token: Pubkey,
+    pub ledger: Pubkey,
+    rent_payer: Pubkey,
+    // Credited with the funds seized by liquidations
+    pub insurance_fund: Keypair,
+}
+
+// A client with deposits of both tokens
+pub struct Trader {
+    pub keypair: Keypair,
+    pub underlying_ata: Pubkey,
+    pub strike_ata: Pubkey,
+}
+
+impl Market {
+    pub async fn new() -> Self {
+        let mut env = TestEnv::new().await;
+        let underlying_token = env.whitelisted_mint(9, 4).await;
+        let strike_token = env.whitelisted_mint(6, 2).await;
+        Self::with_tokens(env, underlying_token, strike_token).await
+    }
+
+    // Initializes the ledger of tokens already whitelisted in the environment
+    pub async fn with_tokens(
+        mut env: TestEnv,
+        underlying_token: Pubkey,
+        strike_token: Pubkey,
+    ) -> Self {
+        let ledger = pda::ledger(
+            &env.access_controller,
+            &env.token_validator,
+            &underlying_token,
+            &strike_token,
+        )
+        .0;
+        let admin = env.admin.pubkey();
+        env.grant_role(Roles::UtilityAccount, admin).await;
+        let rent_payer = Keypair::new().pubkey();
+        let insurance_fund = Keypair::new();
+
+        let admin_role = env.role(Roles::Admin);
+        let init_ledger_ix = ix(
+            accounts::InitLedger {
+                admin,
+                access_controller: env.access_controller,
+                role: admin_role,
+                member: env.member(&admin),
+                token_validator: env.token_validator,
+                fundlock: env.fundlock,
+                underlying_token,
+                whitelisted_underlying_token: pda::whitelisted_token(
+                    &env.token_validator,
+                    &underlying_token,
+                )
+                .0,
+                strike_token,
+                whitelisted_strike_token: pda::whitelisted_token(
+                    &env.token_validator,
+                    &strike_token,
+                )
+                .0,
+                ledger,
+                system_program: system_program::ID,
+            },
+            instruction::InitLedger {
+                rent_payer,
+                insurance_fund: insurance_fund.pubkey(),
+            },
+        );
+        env.process(&[init_ledger_ix], &[]).await.unwrap();
+
+        Self {
+            env,
+            caller: admin,
+            underlying_token,
+            strike_token,
+            ledger,
+            rent_payer,
+            insurance_fund,
+        }
+    }
+
+    pub async fn trader(&mut self, underlying_amount: u64, strike_amount: u64) -> Trader {
+        self.trader_with(Keypair::new(), underlying_amount, strike_amount)
+            .await
+    }
+
+    // The insurance fund with a minimal deposit of both tokens, so that it holds balances
+    pub async fn insurance_trader(&mut self) -> Trader {
+        let keypair = self.insurance_fund.insecure_clone();
+        self.trader_with(keypair, 1, 1).await
+    }
+
+    async fn trader_with(
+        &mut self,
+        keypair: Keypair,
+        underlying_amount: u64,
+        strike_amount: u64,
+    ) -> Trader {
+        let (underlying_token, strike_token) = (self.underlying_token, self.strike_token);
+        let underlying_ata = self
+            .env
+            .fund_client(&keypair, &underlying_token, underlying_amount)
+            .await;
+        let strike_ata = self
+            .env
+            .create_token_account(&strike_token, &keypair.pubkey())
+            .await;
+        self.env
+            .mint_to(&strike_token, &strike_ata, strike_amount)
+            .await;
+        self.env
+            .deposit(
+                &keypair,
+                underlying_ata,
+                underlying_token,
+                underlying_amount,
+            )
+            .await;
+        self.env
+            .deposit(&keypair, strike_ata, strike_token, strike_amount)
+            .await;
+        Trader {
+            keypair,
+            underlying_ata,
+            strike_ata,
+        }
+    }
+
+    pub fn client_balance(&self, mint: &Pubkey, client_ata: &Pubkey) -> Pubkey {
+        pda::client_balance(
+            &pda::fundlock_token_vault(&self.env.fundlock, mint).0,
+            client_ata,
+        )
+        .0
+    }
+
+    // Call option expiring EXPIRY_IN seconds from now
+    pub async fn call(&mut self, contract_id: u64) -> ContractParams {
+        ContractParams {
+            contract_id,
+            expiry: self.env.now().await + EXPIRY_IN,
+            strike: 50_000,
+            payoff_type: PayoffType::Call,
+        }
+    }
+
+    pub fn positions_ix(
+        &self,
+        positions: Vec<PositionsParam>,
+        contract_params: Vec<ContractParams>,
+        backend_id: u64,
+    ) -> Instruction {
+        let caller = self.caller;
+        let utility_role = self.env.role(Roles::UtilityAccount);
+        let mut instruction = ix(
+            accounts::CreateOrUpdatePositions {
+                caller,
+                access_controller: self.env.access_controller,
+                role_util: utility_role,
+                member_util: self.env.member(&caller),
+                token_validator: self.env.token_validator,
+                underlying_token: self.underlying_token,
+                whitelisted_underlying_token: pda::whitelisted_token(
+                    &self.env.token_validator,
+                    &self.underlying_token,
+                )
+                .0,
+                strike_token: self.strike_token,
+                whitelisted_strike_token: pda::whitelisted_token(
+                    &self.env.token_validator,
+                    &self.strike_token,
+                )
+                .0,
+                ledger: self.ledger,
+                system_program: system_program::ID,
+            },
+            instruction::CreateContractsAndPositions {
+                position_params: positions.clone(),
+                contract_params,
+                backend_id,
+            },
+        );
+        // (contract, position) pair for every position
+        for position in positions {
+            let contract = pda::contract(&self.ledger, position.contract_id).0;
+            instruction.accounts.push(AccountMeta::new(contract, false));
+            instruction.accounts.push(AccountMeta::new(
+                pda::position(&contract, &position.client).0,
+                false,
+            ));
+        }
+        instruction
+    }
+
+    fn migrate_ledger_ix(&self, rent_payer: Pubkey, insurance_fund: Pubkey) -> Instruction {
+        ix(
+            accounts::MigrateLedger {
+                admin: self.env.admin.pubkey(),
+                access_controller: self.env.access_controller,
+                ledger: self.ledger,
+                system_program: system_program::ID,
+            },
+            instruction::MigrateLedger {
+                rent_payer,
+                insurance_fund,
+            },
+        )
+    }
+
+    fn settle_ix(&self, contract_id: u64, settlement_price: u64) -> Instruction {
+        let admin = self.env.admin.pubkey();
+        let utility_role = self.env.role(Roles::UtilityAccount);
+        ix(
+            accounts::SettleContract {
+                caller: admin,
+                access_controller: self.env.access_controller,
+                role: utility_role,
+                member: self.env.member(&admin),
+                ledger: self.ledger,
+                contract: pda::contract(&self.ledger, contract_id).0,
+            },
+            instruction::SettleContract {
+                contract_id,
+                settlement_price,
+            },
+        )
+    }
+
+    // Every contract is followed by the positions of the given clients
+    fn close_positions_ix(&self, contracts: Vec<(u64, Vec<Pubkey>)>) -> Instruction {
+        let admin = self.env.admin.pubkey();
+        let utility_role = self.env.role(Roles::UtilityAccount);
+        let mut instruction = ix(
+            accounts::ClosePositions {
+                caller: admin,
+                access_controller: self.env.access_controller,
+                role: utility_role,
+                member: self.env.member(&admin),
+                ledger: self.ledger,
+                rent_payer: self.rent_payer,
+            },
+            instruction::ClosePositions {},
+        );
+        for (contract_id, clients) in contracts {
+            let contract = pda::contract(&self.ledger, contract_id).0;
+            instruction.accounts.push(AccountMeta::new(contract, false));
+            for client in clients {
+                instruction
+                    .accounts
+                    .push(AccountMeta::new(pda::position(&contract, &client).0, false));
+            }
+        }
+        instruction
+    }
+
+    pub fn fund_movements_ix(
+        &self,
+        fund_movements: Vec<(&Trader, FundMovementParamOptimized)>,
+        backend_id: u64,
+    ) -> Instruction {
+        let caller = self.caller;
+        let utility_role = self.env.role(Roles::UtilityAccount);
+        let mut instruction = ix(
+            accounts::UpdateFundMovements {
+                caller,
+                access_controller: self.env.access_controller,
+                role: utility_role,
+                member: self.env.member(&caller),
+                token_validator: self.env.token_validator,
+                fundlock: self.env.fundlock,
+                underlying_token: self.underlying_token,
+                whitelisted_underlying_token: pda::whitelisted_token(
+                    &self.env.token_validator,
+                    &self.underlying_token,
+                )
+                .0,
+                strike_token: self.strike_token,
+                whitelisted_strike_token: pda::whitelisted_token(
+                    &self.env.token_validator,
+                    &self.strike_token,
+                )
+                .0,
+                ledger: self.ledger,
+                system_program: system_program::ID,
+            },
+            instruction::UpdateFundMovements {
+                fund_movements: fund_movements
+                    .iter()
+                    .map(|(_, fund_movement)| fund_movement.clone())
+                    .collect(),
+                backend_id,
+            },
+        );
+        // Underlying balance, strike balance, underlying withdrawals and strike withdrawals of every client
+        for (trader, _) in fund_movements {
+            let underlying_balance =
+                self.client_balance(&self.underlying_token, &trader.underlying_ata);
+            let strike_balance = self.client_balance(&self.strike_token, &trader.strike_ata);
+            instruction.accounts.extend([
+                AccountMeta::new(underlying_balance, false),
+                AccountMeta::new(strike_balance, false),
+                AccountMeta::new(
+                    pda::withdrawals(&self.env.fundlock, &underlying_balance).0,
+                    false,
+                ),
+                AccountMeta::new(
+                    pda::withdrawals(&self.env.fundlock, &strike_balance).0,
+                    false,
+                ),
+            ]);
+        }
+        instruction
+    }
+
+    pub async fn balances(&mut self, trader: &Trader) -> (u64, u64) {
+        let underlying_balance =
+            self.client_balance(&self.underlying_token, &trader.underlying_ata);
+        let strike_balance = self.client_balance(&self.strike_token, &trader.strike_ata);
+        let underlying: ClientBalance = self.env.fetch(underlying_balance).await;
+        let strike: ClientBalance = self.env.fetch(strike_balance).await;
+        (underlying.amount, strike.amount)
+    }
+}
+
+#[tokio::test]
+async fn ledger_multipliers_follow_token_precision() {
+    let mut market = Market::new().await;
+
+    let ledger: Ledger = market.env.fetch(market.ledger).await;
+    assert_eq!(ledger.fundlock, market.env.fundlock);
+    assert_eq!(ledger.underlying_token, market.underlying_token);
+    assert_eq!(ledger.strike_token, market.strike_token);
+    assert_eq!(ledger.underlying_multiplier, 100_000);
+    assert_eq!(ledger.strike_multiplier, 10_000);
+}
+
+#[tokio::test]
+async fn legacy_ledger_is_migrated() {
+    let mut market = Market::new().await;
+    let ledger: Ledger = market.env.fetch(market.ledger).await;
+    let mut legacy_data = Ledger::DISCRIMINATOR.to_vec();
+    legacy_data.extend(
+        (
+            ledger.access_controller,
+            ledger.token_validator,
+            ledger.fundlock,
+            ledger.underlying_token,
+            ledger.strike_token,
+            ledger.underlying_multiplier,
+            ledger.strike_multiplier,
+            ledger.bump,
+        )
+            .try_to_vec()
+            .unwrap(),
+    );
+    market.env.set_program_account(&market.ledger, &legacy_data);
+
+    // Ledger instructions can't read the legacy layout until it's migrated
+    let call = market.call(1).await;
+    let position = PositionsParam {
+        contract_id: 1,
+        client: Keypair::new().pubkey(),
+        size: 10,
+    };
+    let positions_ix = market.positions_ix(vec![position.clone()], vec![call.clone()], 1);
+    assert!(market.env.process(&[positions_ix], &[]).await.is_err());
+
+    let insurance_fund = market.insurance_fund.pubkey();
+    let migrate_ix = market.migrate_ledger_ix(market.rent_payer, insurance_fund);
+    market
+        .env
+        .process(std::slice::from_ref(&migrate_ix), &[])
+        .await
+        .unwrap();
+    let migrated: Ledger = market.env.fetch(market.ledger).await;
+    assert_eq!(migrated.underlying_multiplier, ledger.underlying_multiplier);
+    assert_eq!(migrated.strike_token, ledger.strike_token);
+    assert_eq!(migrated.recent_backend_ids, Vec::<u64>::new());
+    assert_eq!(migrated.rent_payer, market.rent_payer);
+    assert_eq!(migrated.insurance_fund, insurance_fund);
+
+    let positions_ix = market.positions_ix(vec![position], vec![call], 1);
+    market.env.process(&[positions_ix], &[]).await.unwrap();
+
+    let err = market.env.process(&[migrate_ix], &[]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(LedgerError::AlreadyMigrated));
+}
+
+#[tokio::test]
+async fn positions_are_netted_per_client() {
+    let mut market = Market::new().await;
+    let client_one = Keypair::new().pubkey();
+    let client_two = Keypair::new().pubkey();
+    let call = market.call(1).await;
+
+    let positions_ix = market.positions_ix(
+        vec![
+            PositionsParam {
+                contract_id: 1,
+                client: client_one,
+                size: 10,
+            },
+            PositionsParam {
+                contract_id: 1,
+                client: client_two,
+                size: -10,
+            },
+        ],
+        vec![call.clone()],
+        1,
+    );
+    market.env.process(&[positions_ix], &[]).await.unwrap();
+
+    let contract_pda = pda::contract(&market.ledger, 1).0;
+    let contract: Contract = market.env.fetch(contract_pda).await;
+    assert_eq!(contract.contract_id, 1);
+    assert_eq!(contract.expiry, call.expiry);
+    assert_eq!(contract.strike, call.strike);
+    assert_eq!(contract.payoff_type, PayoffType::Call);
+    assert_eq!(contract.status, ContractStatus::Active);
+    assert_eq!(contract.long_open_interest, 10);
+    assert_eq!(contract.short_open_interest, 10);
+    let position: Position = market
+        .env
+        .fetch(pda::position(&contract_pda, &client_two).0)
+        .await;
+    assert_eq!(position.client, client_two);
+    assert_eq!(position.size, -10);
+
+    // Both sells are netted into the long, flipping it short
+    let positions_ix = market.positions_ix(
+        vec![
+            PositionsParam {
+                contract_id: 1,
+                client: client_one,
+                size: -5,
+            },
+            PositionsParam {
+                contract_id: 1,
+                client: client_one,
+                size: -10,
+            },
+        ],
+        // Params of an existing contract are ignored
+        vec![ContractParams {
+            strike: 1,
+            ..call.clone()
+        }],
+        2,
+    );
+    market.env.process(&[positions_ix], &[]).await.unwrap();
+    let contract: Contract = market.env.fetch(contract_pda).await;
+    assert_eq!(contract.strike, call.strike);
+    assert_eq!(contract.position_count, 2);
+    assert_eq!(contract.long_open_interest, 0);
+    assert_eq!(contract.short_open_interest, 15);
+    let position: Position = market
+        .env
+        .fetch(pda::position(&contract_pda, &client_one).0)
+        .await;
+    assert_eq!(position.size, -5);
+}
+
+#[tokio::test]
+async fn positions_must_be_passed_in_contract_position_pairs() {
+    let mut market = Market::new().await;
+    let client = Keypair::new().pubkey();
+    let call = market.call(1).await;
+
+    let mut positions_ix = market.positions_ix(
+        vec![PositionsParam {
+            contract_id: 1,
+            client,
+            size: 10,
+        }],
+        vec![call],
+        1,
+    );
+    let accounts_len = positions_ix.accounts.len();
+    positions_ix
+        .accounts
+        .swap(accounts_len - 2, accounts_len - 1);
+    let err = market.env.process(&[positions_ix], &[]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(LedgerError::AccountOrderViolated)
+    );
+}
+
+#[tokio::test]
+async fn new_contracts_require_valid_params() {
+    let mut market = Market::new().await;
+    let position = PositionsParam {
+        contract_id: 1,
+        client: Keypair::new().pubkey(),
+        size: 10,
+    };
+    let call = market.call(1).await;
+
+    let cases = [
+        (vec![], LedgerError::MissingContractParams),
+        (
+            vec![ContractParams {
+                contract_id: 2,
+                ..call.clone()
+            }],
+            LedgerError::MissingContractParams,
+        ),
+        (
+            vec![ContractParams {
+                expiry: call.expiry - EXPIRY_IN,
+                ..call.clone()
+            }],
+            LedgerError::ContractExpired,
+        ),
+        (
+            vec![ContractParams {
+                strike: 0,
+                ..call.clone()
+            }],
+            LedgerError::InvalidContractParams,
+        ),
+    ];
+    for (backend_id, (contract_params, expected)) in (1..).zip(cases) {
+        let positions_ix = market.positions_ix(vec![position.clone()], contract_params, backend_id);
+        let err = market.env.process(&[positions_ix], &[]).await.unwrap_err();
+        assert_eq!(error_code(err), u32::from(expected));
+    }
+}
+
+#[tokio::test]
+async fn contracts_are_settled_after_expiry() {
+    let mut market = Market::new().await;
+    let position = PositionsParam {
+        contract_id: 1,
+        client: Keypair::new().pubkey(),
+        size: 10,
+    };
+    let call = market.call(1).await;
+    let positions_ix = market.positions_ix(vec![position.clone()], vec![call], 1);
+    market.env.process(&[positions_ix], &[]).await.unwrap();
+
+    let settle_ix = market.settle_ix(1, 60_000);
+    let err = market
+        .env
+        .process(std::slice::from_ref(&settle_ix), &[])
+        .await
+        .unwrap_err();
+    assert_eq!(error_code(err), u32::from(LedgerError::ContractNotExpired));
+
+    // Positions are frozen once the contract expires, even before settlement
+    market.env.advance_clock(EXPIRY_IN + 1).await;
+    let positions_ix = market.positions_ix(vec![position.clone()], vec![], 2);
+    let err = market.env.process(&[positions_ix], &[]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(LedgerError::ContractExpired));
+
+    market.env.process(&[settle_ix], &[]).await.unwrap();
+    let contract: Contract = market.env.fetch(pda::contract(&market.ledger, 1).0).await;
+    assert_eq!(contract.status, ContractStatus::Settled);
+    assert_eq!(contract.settlement_price, 60_000);
+
+    let positions_ix = market.positions_ix(vec![position], vec![], 3);
+    let err = market.env.process(&[positions_ix], &[]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(LedgerError::ContractSettled));
+
+    let settle_ix = market.settle_ix(1, 70_000);
+    let err = market.env.process(&[settle_ix], &[]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(LedgerError::ContractSettled));
+}
+
+#[tokio::test]
+async fn flat_positions_and_expired_contracts_are_closed() {
+    let mut market = Market::new().await;
+    let client_one = Keypair::new().pubkey();
+    let client_two = Keypair::new().pubkey();
+    let call = market.call(1).await;
+    let positions_ix = market.positions_ix(
+        vec![
+            PositionsParam {
+                contract_id: 1,
+                client: client_one,
+                size: 10,
+            },
+            PositionsParam {
+                contract_id: 1,
+                client: client_two,
+                size: 0,
+            },
+        ],
+        vec![call],
+        1,
+    );
+    market.env.process(&[positions_ix], &[]).await.unwrap();
+    let contract_pda = pda::contract(&market.ledger, 1).0;
+    let contract: Contract = market.env.fetch(contract_pda).await;
+    assert_eq!(contract.position_count, 2);
+
+    let close_ix = market.close_positions_ix(vec![(1, vec![client_one])]);
+    let err = market.env.process(&[close_ix], &[]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(LedgerError::PositionNotClosable));
+
+    // Positions have to follow their contract
+    let mut close_ix = market.close_positions_ix(vec![(1, vec![client_two])]);
+    let accounts_len = close_ix.accounts.len();
+    close_ix.accounts.swap(accounts_len - 2, accounts_len - 1);
+    let err = market.env.process(&[close_ix], &[]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(LedgerError::AccountOrderViolated)
+    );
+
+    // The flat position is closed while the contract stays open
+    let close_ix = market.close_positions_ix(vec![(1, vec![client_two])]);
+    market.env.process(&[close_ix], &[]).await.unwrap();
+    assert!(
+        !market
+            .env
+            .exists(pda::position(&contract_pda, &client_two).0)
+            .await
+    );
+    let contract: Contract = market.env.fetch(contract_pda).await;
+    assert_eq!(contract.position_count, 1);
+    let rent_refund = market.env.lamports(market.rent_payer).await;
+    assert!(rent_refund > 0);
+
+    // A tradable contract stays open without positions
+    let positions_ix = market.positions_ix(
+        vec![PositionsParam {
+            contract_id: 1,
+            client: client_one,
+            size: -10,
+        }],
+        vec![],
+        2,
+    );
+    market.env.process(&[positions_ix], &[]).await.unwrap();
+    let close_ix = market.close_positions_ix(vec![(1, vec![client_one])]);
+    market.env.process(&[close_ix], &[]).await.unwrap();
+    let contract: Contract = market.env.fetch(contract_pda).await;
+    assert_eq!(contract.position_count, 0);
+
+    // Once expired the contract is closed even though it was never settled, leaving a
+    // tombstone behind
+    market.env.advance_clock(EXPIRY_IN + 1).await;
+    let close_ix = market.close_positions_ix(vec![(1, vec![])]);
+    market.env.process(&[close_ix], &[]).await.unwrap();
+    let tombstone: ClosedContract = market.env.fetch(contract_pda).await;
+    assert_eq!(tombstone.contract_id, 1);
+    assert!(market.env.lamports(market.rent_payer).await > 2 * rent_refund);
+
+    // The id of a closed contract can't be used again
+    let call = market.call(1).await;
+    let positions_ix = market.positions_ix(
+        vec![PositionsParam {
+            contract_id: 1,
+            client: client_one,
+            size: 10,
+        }],
+        vec![call],
+        3,
+    );
+    let err = market.env.process(&[positions_ix], &[]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(LedgerError::ContractClosed));
+}
+
+#[tokio::test]
+async fn fund_movements_move_balances_between_clients() {
+    let mut market = Market::new().await;
+    let buyer = market.trader(1_000_000, 100_000).await;
+    let seller = market.trader(1_000_000, 100_000).await;
+
+    // Amounts are in token precision units, a positive amount is charged to the client
+    let fund_movements_ix = market.fund_movements_ix(
+        vec![
+            (
+                &buyer,
+                FundMovementParamOptimized {
+                    underlying_amount: -2,
+                    strike_amount: 3,
+                },
+            ),
+            (
+                &seller,
+                FundMovementParamOptimized {
+                    underlying_amount: 2,
+                    strike_amount: -3,
+                },
+            ),
+        ],
+        1,
+    );
+    market.env.process(&[fund_movements_ix], &[]).await.unwrap();
+
+    assert_eq!(market.balances(&buyer).await, (1_200_000, 70_000));
+    assert_eq!(market.balances(&seller).await, (800_000, 130_000));
+
+    let ledger: Ledger = market.env.fetch(market.ledger).await;
+    assert_eq!(ledger.last_backend_id, 1);
+    assert_eq!(ledger.recent_backend_ids, vec![1]);
+}
+
+#[tokio::test]
+async fn withdraw_only_tokens_take_no_fund_movements() {
+    let mut market = Market::new().await;
+    let buyer = market.trader(1_000_000, 100_000).await;
+    let seller = market.trader(1_000_000, 100_000).await;
+
+    let strike_token = market.strike_token;
+    let config_ix =
+        market
+            .env
+            .set_token_config_ix(strike_token, TokenStatus::WithdrawOnly, None, None, 0);
+    market.env.process(&[config_ix], &[]).await.unwrap();
+
+    let fund_movements_ix = market.fund_movements_ix(
+        vec![
+            (
+                &buyer,
+                FundMovementParamOptimized {
+                    underlying_amount: 0,
+                    strike_amount: 3,
+                },
+            ),
+            (
+                &seller,
+                FundMovementParamOptimized {
+                    underlying_amount: 0,
+                    strike_amount: -3,
+                },
+            ),
+        ],
+        1,
+    );
+    let err = market
+        .env
+        .process(&[fund_movements_ix], &[])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(TokenValidatorError::TokenWithdrawOnly)
+    );
+    assert_eq!(market.balances(&buyer).await, (1_000_000, 100_000));
+}
+
+#[tokio::test]
+async fn expired_utility_accounts_cannot_submit_updates() {
+    let mut market = Market::new().await;
+    let trader = market.trader(1_000_000, 100_000).await;
+    let utility_account = Keypair::new();
+    let now = market.env.now().await;
+
+    let grant_role_ix = market.env.grant_role_in_page_ix(
+        Roles::UtilityAccount,
+        utility_account.pubkey(),
+        0,
+        Some(now),
+    );
+    let err = market.env.process(&[grant_role_ix], &[]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(AccessControlError::InvalidExpiry)
+    );
+    let grant_role_ix = market.env.grant_role_in_page_ix(
+        Roles::UtilityAccount,
+        utility_account.pubkey(),
+        0,
+        Some(now + EXPIRY_IN),
+    );
+    market.env.process(&[grant_role_ix], &[]).await.unwrap();
+    market.caller = utility_account.pubkey();
+
+    let fund_movement = FundMovementParamOptimized {
+        underlying_amount: -1,
+        strike_amount: -1,
+    };
+    let fund_movements_ix = market.fund_movements_ix(vec![(&trader, fund_movement.clone())], 1);
+    market
+        .env
+        .process(&[fund_movements_ix], &[&utility_account])
+        .await
+        .unwrap();
+
+    market.env.advance_clock(EXPIRY_IN).await;
+    let fund_movements_ix = market.fund_movements_ix(vec![(&trader, fund_movement)], 2);
+    let err = market
+        .env
+        .process(&[fund_movements_ix], &[&utility_account])
+        .await
+        .unwrap_err();
+    assert_eq!(error_code(err), u32::from(AccessControlError::RoleExpired));
+    let call = market.call(1).await;
+    let positions_ix = market.positions_ix(
+        vec![PositionsParam {
+            contract_id: 1,
+            client: trader.keypair.pubkey(),
+            size: 1,
+        }],
+        vec![call],
+        3,
+    );
+    let err = market
+        .env
+        .process(&[positions_ix], &[&utility_account])
+        .await
+        .unwrap_err();
+    assert_eq!(error_code(err), u32::from(AccessControlError::RoleExpired));
+}
+
+#[tokio::test]
+async fn replayed_backend_id_is_rejected() {
+    let mut market = Market::new().await;
+    let trader = market.trader(1_000_000, 100_000).await;
+
+    let fund_movements_ix = |market: &Market| {
+        market.fund_movements_ix(
+            vec![(
+                &trader,
+                FundMovementParamOptimized {
+                    underlying_amount: -1,
+                    strike_amount: -1,
+                },
+            )],
+            7,
+        )
+    };
+    let first_ix = fund_movements_ix(&market);
+    market.env.process(&[first_ix], &[]).await.unwrap();
+    let replayed_ix = fund_movements_ix(&market);
+    let err = market.env.process(&[replayed_ix], &[]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(LedgerError::DuplicateBackendId));
+
+    assert_eq!(market.balances(&trader).await, (1_100_000, 110_000));
+}
+
+#[tokio::test]
+async fn shortfall_is_funded_from_pending_withdrawals() {
+    let mut market = Market::new().await;
+    let trader = market.trader(1_000_000, 100_000).await;
+    let (underlying_token, underlying_ata) = (market.underlying_token, trader.underlying_ata);
+
+    // The withdrawal is still within the trade lock, so it can fund the shortfall
+    let withdraw_ix = market.env.withdraw_ix(
+        &trader.keypair.pubkey(),
+        underlying_ata,
+        underlying_token,
+        900_000,
+    );
+    market
+        .env
+        .process(&[withdraw_ix], &[&trader.keypair])
+        .await
+        .unwrap();
+    assert_eq!(market.balances(&trader).await, (100_000, 100_000));
+
+    let fund_movements_ix = market.fund_movements_ix(
+        vec![(
+            &trader,
+            FundMovementParamOptimized {
+                underlying_amount: 3,
+                strike_amount: 1,
+            },
+        )],
+        1,
+    );
+    market.env.process(&[fund_movements_ix], &[]).await.unwrap();
+
+    assert_eq!(market.balances(&trader).await, (0, 90_000));
+    let underlying_balance = market.client_balance(&underlying_token, &underlying_ata);
+    let withdrawals: Withdrawals = market
+        .env
+        .fetch(pda::withdrawals(&market.env.fundlock, &underlying_balance).0)
+        .await;
+    assert_eq!(withdrawals.active_withdrawals_amount, 700_000);
+    assert_eq!(withdrawals.withdrawal_queue[0].amount, 700_000);
+
+    // Nothing left to cover a charge larger than the balance and the pending withdrawals
+    let fund_movements_ix = market.fund_movements_ix(
+        vec![(
+            &trader,
+            FundMovementParamOptimized {
+                underlying_amount: 8,
+                strike_amount: 1,
+            },
+        )],
+        2,
+    );
+    let err = market
+        .env
+        .process(&[fund_movements_ix], &[])
+        .await
+        .unwrap_err();
+    assert_eq!(error_code(err), u32::from(FundlockError::InsufficientFunds));
+}
@@ -0,0 +1,291 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::system_instruction;
+use anchor_lang::system_program;
+use ithaca_client::pda;
+use ithaca_smart_contract_sol::error::{AccessControlError, LedgerError, LiquidationError};
+use ithaca_smart_contract_sol::{
+    accounts, instruction, Contract, Ledger, Position, PositionsParam, Roles,
+};
+use solana_sdk::signature::{Keypair, Signer};
+
+use crate::env::{error_code, ix};
+use crate::ledger::{Market, Trader};
+
+// Liquidation of `client` closing out its positions in `contract_ids`, crediting `insurance`
+fn liquidate_ix(
+    market: &Market,
+    liquidator: &Pubkey,
+    client: &Trader,
+    insurance: &Trader,
+    contract_ids: &[u64],
+    amounts: (u64, u64),
+    backend_id: u64,
+) -> Instruction {
+    let env = &market.env;
+    let (underlying_token, strike_token) = (market.underlying_token, market.strike_token);
+    let mut instruction = ix(
+        accounts::LiquidateClient {
+            liquidator: *liquidator,
+            access_controller: env.access_controller,
+            role_liquidator: env.role(Roles::Liquidator),
+            member_liquidator: env.member(liquidator),
+            token_validator: env.token_validator,
+            fundlock: env.fundlock,
+            underlying_token,
+            whitelisted_underlying_token: pda::whitelisted_token(
+                &env.token_validator,
+                &underlying_token,
+            )
+            .0,
+            strike_token,
+            whitelisted_strike_token: pda::whitelisted_token(&env.token_validator, &strike_token).0,
+            ledger: market.ledger,
+            fundlock_underlying_vault: pda::fundlock_token_vault(&env.fundlock, &underlying_token)
+                .0,
+            fundlock_strike_vault: pda::fundlock_token_vault(&env.fundlock, &strike_token).0,
+            client_underlying_balance: market
+                .client_balance(&underlying_token, &client.underlying_ata),
+            client_strike_balance: market.client_balance(&strike_token, &client.strike_ata),
+            insurance_underlying_balance: market
+                .client_balance(&underlying_token, &insurance.underlying_ata),
+            insurance_strike_balance: market.client_balance(&strike_token, &insurance.strike_ata),
+            system_program: system_program::ID,
+        },
+        instruction::LiquidateClient {
+            client: client.keypair.pubkey(),
+            underlying_amount: amounts.0,
+            strike_amount: amounts.1,
+            backend_id,
+        },
+    );
+    // (contract, position, insurance fund position) triple for every closed out contract
+    for contract_id in contract_ids {
+        let contract = pda::contract(&market.ledger, *contract_id).0;
+        instruction.accounts.extend([
+            AccountMeta::new(contract, false),
+            AccountMeta::new(pda::position(&contract, &client.keypair.pubkey()).0, false),
+            AccountMeta::new(
+                pda::position(&contract, &market.insurance_fund.pubkey()).0,
+                false,
+            ),
+        ]);
+    }
+    instruction
+}
+
+// Market with a trader long 10 of contract 1, an insurance fund and a liquidator
+async fn liquidation_market() -> (Market, Trader, Trader, Keypair) {
+    let mut market = Market::new().await;
+    let client = market.trader(1_000_000, 100_000).await;
+    let insurance = market.insurance_trader().await;
+    let call = market.call(1).await;
+    let positions_ix = market.positions_ix(
+        vec![PositionsParam {
+            contract_id: 1,
+            client: client.keypair.pubkey(),
+            size: 10,
+        }],
+        vec![call],
+        1,
+    );
+    market.env.process(&[positions_ix], &[]).await.unwrap();
+    let liquidator = Keypair::new();
+    market
+        .env
+        .grant_role(Roles::Liquidator, liquidator.pubkey())
+        .await;
+    // The liquidator pays for the insurance fund's positions
+    let fund_liquidator_ix = system_instruction::transfer(
+        &market.env.admin.pubkey(),
+        &liquidator.pubkey(),
+        1_000_000_000,
+    );
+    market
+        .env
+        .process(&[fund_liquidator_ix], &[])
+        .await
+        .unwrap();
+    (market, client, insurance, liquidator)
+}
+
+#[tokio::test]
+async fn liquidation_closes_out_positions_and_credits_the_insurance_fund() {
+    let (mut market, client, insurance, liquidator) = liquidation_market().await;
+
+    let liquidate_ix = liquidate_ix(
+        &market,
+        &liquidator.pubkey(),
+        &client,
+        &insurance,
+        &[1],
+        (400_000, 30_000),
+        2,
+    );
+    market
+        .env
+        .process(&[liquidate_ix], &[&liquidator])
+        .await
+        .unwrap();
+
+    let contract_pda = pda::contract(&market.ledger, 1).0;
+    let position: Position = market
+        .env
+        .fetch(pda::position(&contract_pda, &client.keypair.pubkey()).0)
+        .await;
+    assert_eq!(position.size, 0);
+    // The insurance fund takes the position over, so the contract's open interest is unchanged
+    let insurance_position: Position = market
+        .env
+        .fetch(pda::position(&contract_pda, &insurance.keypair.pubkey()).0)
+        .await;
+    assert_eq!(insurance_position.size, 10);
+    assert_eq!(insurance_position.client, insurance.keypair.pubkey());
+    let contract: Contract = market.env.fetch(contract_pda).await;
+    assert_eq!(contract.long_open_interest, 10);
+    assert_eq!(contract.position_count, 2);
+
+    assert_eq!(market.balances(&client).await, (600_000, 70_000));
+    assert_eq!(market.balances(&insurance).await, (400_001, 30_001));
+    let ledger: Ledger = market.env.fetch(market.ledger).await;
+    assert_eq!(ledger.recent_backend_ids, vec![1, 2]);
+}
+
+#[tokio::test]
+async fn seized_funds_only_go_to_the_insurance_fund() {
+    let (mut market, client, _insurance, liquidator) = liquidation_market().await;
+    let accomplice = market.trader(1, 1).await;
+
+    let liquidate_ix = liquidate_ix(
+        &market,
+        &liquidator.pubkey(),
+        &client,
+        &accomplice,
+        &[],
+        (400_000, 0),
+        2,
+    );
+    let err = market
+        .env
+        .process(&[liquidate_ix], &[&liquidator])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(LiquidationError::InsuranceFundMismatch)
+    );
+    assert_eq!(market.balances(&client).await, (1_000_000, 100_000));
+}
+
+#[tokio::test]
+async fn liquidators_cannot_liquidate_themselves_or_the_insurance_fund() {
+    let (mut market, client, insurance, _liquidator) = liquidation_market().await;
+    let client_pk = client.keypair.pubkey();
+    market.env.grant_role(Roles::Liquidator, client_pk).await;
+
+    let self_liquidation_ix =
+        liquidate_ix(&market, &client_pk, &client, &insurance, &[1], (0, 0), 2);
+    let err = market
+        .env
+        .process(&[self_liquidation_ix], &[&client.keypair])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(LiquidationError::SelfLiquidation)
+    );
+
+    let insurance_liquidation_ix =
+        liquidate_ix(&market, &client_pk, &insurance, &insurance, &[], (1, 0), 2);
+    let err = market
+        .env
+        .process(&[insurance_liquidation_ix], &[&client.keypair])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(LiquidationError::InsuranceFundLiquidation)
+    );
+}
+
+#[tokio::test]
+async fn liquidation_needs_positions_or_amounts() {
+    let (mut market, client, insurance, liquidator) = liquidation_market().await;
+
+    let liquidate_ix = liquidate_ix(
+        &market,
+        &liquidator.pubkey(),
+        &client,
+        &insurance,
+        &[],
+        (0, 0),
+        2,
+    );
+    let err = market
+        .env
+        .process(&[liquidate_ix], &[&liquidator])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(LiquidationError::NothingToLiquidate)
+    );
+}
+
+#[tokio::test]
+async fn only_liquidators_can_liquidate() {
+    let (mut market, client, insurance, _liquidator) = liquidation_market().await;
+    let utility_account = Keypair::new();
+    market
+        .env
+        .grant_role(Roles::UtilityAccount, utility_account.pubkey())
+        .await;
+
+    let liquidate_ix = liquidate_ix(
+        &market,
+        &utility_account.pubkey(),
+        &client,
+        &insurance,
+        &[1],
+        (0, 0),
+        2,
+    );
+    let err = market
+        .env
+        .process(&[liquidate_ix], &[&utility_account])
+        .await
+        .unwrap_err();
+    assert_eq!(error_code(err), u32::from(AccessControlError::NoRole));
+}
+
+#[tokio::test]
+async fn replayed_liquidation_is_rejected() {
+    let (mut market, client, insurance, liquidator) = liquidation_market().await;
+
+    let liquidate_ix = |market: &Market| {
+        liquidate_ix(
+            market,
+            &liquidator.pubkey(),
+            &client,
+            &insurance,
+            &[],
+            (100_000, 0),
+            2,
+        )
+    };
+    let first_ix = liquidate_ix(&market);
+    market
+        .env
+        .process(&[first_ix], &[&liquidator])
+        .await
+        .unwrap();
+    let replayed_ix = liquidate_ix(&market);
+    let err = market
+        .env
+        .process(&[replayed_ix], &[&liquidator])
+        .await
+        .unwrap_err();
+    assert_eq!(error_code(err), u32::from(LedgerError::DuplicateBackendId));
+
+    assert_eq!(market.balances(&client).await, (900_000, 100_000));
+}
@@ -0,0 +1,15 @@
+// Runs the program against a local bank without a validator, with a fake KLend program
+// standing in for Kamino so every flow can be exercised offline. PDAs are derived with the
+// ithaca-client crate, so that its derivations are checked against the program on every run.
+mod env;
+mod fake_klend;
+mod fake_partner;
+
+mod access_control;
+mod fundlock;
+mod governance;
+mod kamino;
+mod ledger;
+mod liquidation;
+mod multisig;
+mod token_validator;
@@ -0,0 +1,313 @@
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::{
+    instruction::{AccountMeta, Instruction},
+    system_instruction,
+};
+use anchor_lang::system_program;
+use ithaca_client::pda;
+use ithaca_smart_contract_sol::error::{AccessControlError, MultisigError};
+use ithaca_smart_contract_sol::{
+    accounts, instruction, AccessController, Member, Multisig, Proposal, ProposalAccount, Roles, ID,
+};
+use solana_sdk::signature::{Keypair, Signer};
+
+use crate::env::{error_code, ix, TestEnv};
+
+// Multisig of the test admin and two other signers, funded so that its authority can pay
+// for the accounts created by the proposed instructions
+struct TestMultisig {
+    multisig: Pubkey,
+    authority: Pubkey,
+    signers: [Keypair; 2],
+}
+
+impl TestMultisig {
+    async fn new(env: &mut TestEnv, threshold: u8) -> Self {
+        let multisig = pda::multisig(&env.access_controller).0;
+        let authority = pda::multisig_authority(&multisig).0;
+        let signers = [Keypair::new(), Keypair::new()];
+        let init_multisig_ix = ix(
+            accounts::InitMultisig {
+                admin: env.admin.pubkey(),
+                access_controller: env.access_controller,
+                multisig,
+                system_program: system_program::ID,
+            },
+            instruction::InitMultisig {
+                signers: vec![env.admin.pubkey(), signers[0].pubkey(), signers[1].pubkey()],
+                threshold,
+            },
+        );
+        let fund_ix = system_instruction::transfer(&env.admin.pubkey(), &authority, 1_000_000_000);
+        env.process(&[init_multisig_ix, fund_ix], &[])
+            .await
+            .unwrap();
+        Self {
+            multisig,
+            authority,
+            signers,
+        }
+    }
+
+    // Proposed by the test admin, which approves it along the way
+    fn create_ix(&self, env: &TestEnv, proposal_id: u64, proposed: &Instruction) -> Instruction {
+        ix(
+            accounts::CreateProposal {
+                proposer: env.admin.pubkey(),
+                multisig: self.multisig,
+                proposal: pda::proposal(&self.multisig, proposal_id).0,
+                system_program: system_program::ID,
+            },
+            instruction::CreateProposal {
+                accounts: proposed
+                    .accounts
+                    .iter()
+                    .map(|account| ProposalAccount {
+                        pubkey: account.pubkey,
+                        is_signer: account.is_signer,
+                        is_writable: account.is_writable,
+                    })
+                    .collect(),
+                data: proposed.data.clone(),
+            },
+        )
+    }
+
+    fn approve_ix(&self, signer: &Keypair, proposal_id: u64) -> Instruction {
+        ix(
+            accounts::ApproveProposal {
+                signer: signer.pubkey(),
+                multisig: self.multisig,
+                proposal: pda::proposal(&self.multisig, proposal_id).0,
+            },
+            instruction::ApproveProposal {},
+        )
+    }
+
+    // The authority only signs within the program, so it isn't a signer of the transaction
+    fn execute_ix(&self, env: &TestEnv, proposal_id: u64, proposed: &Instruction) -> Instruction {
+        let mut execute_ix = ix(
+            accounts::ExecuteProposal {
+                executor: env.admin.pubkey(),
+                multisig: self.multisig,
+                proposal: pda::proposal(&self.multisig, proposal_id).0,
+                authority: self.authority,
+                ithaca_program: ID,
+            },
+            instruction::ExecuteProposal {},
+        );
+        execute_ix
+            .accounts
+            .extend(proposed.accounts.iter().map(|account| AccountMeta {
+                is_signer: false,
+                ..account.clone()
+            }));
+        execute_ix
+    }
+
+    // Makes the multisig authority the admin of the access controller
+    async fn take_over_admin(&self, env: &mut TestEnv) {
+        let propose_admin_ix = ix(
+            accounts::ProposeAdmin {
+                admin: env.admin.pubkey(),
+                access_controller: env.access_controller,
+            },
+            instruction::ProposeAdmin {
+                new_admin: self.authority,
+            },
+        );
+        let accept_admin_ix = ix(
+            accounts::AcceptAdmin {
+                new_admin: self.authority,
+                previous_admin: env.admin.pubkey(),
+                access_controller: env.access_controller,
+                role: env.role(Roles::Admin),
+                previous_admin_member: env.member(&env.admin.pubkey()),
+                new_admin_member: env.member(&self.authority),
+                role_members: pda::role_members(&env.role(Roles::Admin), 0).0,
+                system_program: system_program::ID,
+            },
+            instruction::AcceptAdmin { page_index: 0 },
+        );
+        let create_ix = self.create_ix(env, 0, &accept_admin_ix);
+        let approve_ix = self.approve_ix(&self.signers[0], 0);
+        let execute_ix = self.execute_ix(env, 0, &accept_admin_ix);
+        env.process(
+            &[propose_admin_ix, create_ix, approve_ix, execute_ix],
+            &[&self.signers[0]],
+        )
+        .await
+        .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn multisig_authority_becomes_the_admin() {
+    let mut env = TestEnv::new().await;
+    let test_multisig = TestMultisig::new(&mut env, 2).await;
+
+    let multisig: Multisig = env.fetch(test_multisig.multisig).await;
+    assert_eq!(multisig.access_controller, env.access_controller);
+    assert_eq!(multisig.signers.len(), 3);
+    assert_eq!(multisig.threshold, 2);
+
+    test_multisig.take_over_admin(&mut env).await;
+
+    let access_controller: AccessController = env.fetch(env.access_controller).await;
+    assert_eq!(access_controller.admin, test_multisig.authority);
+    let member: Member = env.fetch(env.member(&test_multisig.authority)).await;
+    assert!(member.has_role(Roles::Admin));
+
+    // The previous admin can't use the admin-gated instructions anymore
+    let grant_role_ix = env.grant_role_ix(Roles::UtilityAccount, Keypair::new().pubkey());
+    let err = env.process(&[grant_role_ix], &[]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(AccessControlError::UnauthorizedAdmin)
+    );
+}
+
+#[tokio::test]
+async fn proposals_are_executed_once_approved_by_the_threshold() {
+    let mut env = TestEnv::new().await;
+    let test_multisig = TestMultisig::new(&mut env, 2).await;
+    test_multisig.take_over_admin(&mut env).await;
+
+    let utility_account = Keypair::new().pubkey();
+    let grant_role_ix = ix(
+        accounts::GrantRole {
+            admin: test_multisig.authority,
+            access_controller: env.access_controller,
+            role: env.role(Roles::UtilityAccount),
+            member: env.member(&utility_account),
+            role_members: pda::role_members(&env.role(Roles::UtilityAccount), 0).0,
+            system_program: system_program::ID,
+        },
+        instruction::GrantRole {
+            role_granted: Roles::UtilityAccount,
+            new_member: utility_account,
+            page_index: 0,
+            expires_at: None,
+        },
+    );
+    let create_ix = test_multisig.create_ix(&env, 1, &grant_role_ix);
+    env.process(&[create_ix], &[]).await.unwrap();
+
+    let execute_ix = test_multisig.execute_ix(&env, 1, &grant_role_ix);
+    let err = env
+        .process(std::slice::from_ref(&execute_ix), &[])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(MultisigError::ThresholdNotReached)
+    );
+
+    // Signers approve once, outsiders can't approve
+    let approve_ix = test_multisig.approve_ix(&env.admin, 1);
+    let err = env.process(&[approve_ix], &[]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(MultisigError::AlreadyApproved));
+    let outsider = Keypair::new();
+    let approve_ix = test_multisig.approve_ix(&outsider, 1);
+    let err = env.process(&[approve_ix], &[&outsider]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(MultisigError::NotASigner));
+
+    let approve_ix = test_multisig.approve_ix(&test_multisig.signers[1], 1);
+    env.process(&[approve_ix], &[&test_multisig.signers[1]])
+        .await
+        .unwrap();
+
+    // The executed instruction has to be the proposed one
+    let mut mismatched_ix = execute_ix.clone();
+    mismatched_ix.accounts.last_mut().unwrap().pubkey = Keypair::new().pubkey();
+    let err = env.process(&[mismatched_ix], &[]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(MultisigError::ProposalAccountMismatch)
+    );
+
+    env.process(std::slice::from_ref(&execute_ix), &[])
+        .await
+        .unwrap();
+    let member: Member = env.fetch(env.member(&utility_account)).await;
+    assert!(member.has_role(Roles::UtilityAccount));
+    let proposal: Proposal = env.fetch(pda::proposal(&test_multisig.multisig, 1).0).await;
+    assert!(proposal.executed);
+    assert_eq!(proposal.approvals.len(), 2);
+
+    let err = env.process(&[execute_ix], &[]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(MultisigError::ProposalExecuted));
+}
+
+#[tokio::test]
+async fn changing_the_signers_invalidates_pending_proposals() {
+    let mut env = TestEnv::new().await;
+    let test_multisig = TestMultisig::new(&mut env, 1).await;
+
+    let set_signers_ix = ix(
+        accounts::SetMultisigSigners {
+            authority: test_multisig.authority,
+            multisig: test_multisig.multisig,
+        },
+        instruction::SetMultisigSigners {
+            signers: vec![env.admin.pubkey(), test_multisig.signers[0].pubkey()],
+            threshold: 2,
+        },
+    );
+    // The signers can only be changed through a proposal
+    let mut unsigned_ix = set_signers_ix.clone();
+    unsigned_ix.accounts[0].is_signer = false;
+    let err = env.process(&[unsigned_ix], &[]).await.unwrap_err();
+    assert_eq!(error_code(err), u32::from(ErrorCode::AccountNotSigner));
+
+    let create_set_signers_ix = test_multisig.create_ix(&env, 0, &set_signers_ix);
+    let create_pending_ix = test_multisig.create_ix(&env, 1, &set_signers_ix);
+    let execute_ix = test_multisig.execute_ix(&env, 0, &set_signers_ix);
+    env.process(&[create_set_signers_ix, create_pending_ix, execute_ix], &[])
+        .await
+        .unwrap();
+
+    let multisig: Multisig = env.fetch(test_multisig.multisig).await;
+    assert_eq!(multisig.signers.len(), 2);
+    assert_eq!(multisig.threshold, 2);
+    assert_eq!(multisig.signers_version, 1);
+
+    let approve_ix = test_multisig.approve_ix(&test_multisig.signers[0], 1);
+    let err = env
+        .process(&[approve_ix], &[&test_multisig.signers[0]])
+        .await
+        .unwrap_err();
+    assert_eq!(error_code(err), u32::from(MultisigError::StaleProposal));
+    let approve_ix = test_multisig.approve_ix(&test_multisig.signers[1], 1);
+    assert!(env
+        .process(&[approve_ix], &[&test_multisig.signers[1]])
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn invalid_signer_sets_are_rejected() {
+    let mut env = TestEnv::new().await;
+    let multisig = pda::multisig(&env.access_controller).0;
+    let signer = Keypair::new().pubkey();
+
+    for (signers, threshold, error) in [
+        (vec![signer], 0, MultisigError::InvalidThreshold),
+        (vec![signer], 2, MultisigError::InvalidThreshold),
+        (vec![signer, signer], 1, MultisigError::DuplicateSigner),
+        (vec![signer; 11], 1, MultisigError::TooManySigners),
+    ] {
+        let init_multisig_ix = ix(
+            accounts::InitMultisig {
+                admin: env.admin.pubkey(),
+                access_controller: env.access_controller,
+                multisig,
+                system_program: system_program::ID,
+            },
+            instruction::InitMultisig { signers, threshold },
+        );
+        let err = env.process(&[init_multisig_ix], &[]).await.unwrap_err();
+        assert_eq!(error_code(err), u32::from(error));
+    }
+}
@@ -0,0 +1,277 @@
+use anchor_lang::{system_program, AnchorSerialize, Discriminator};
+use anchor_spl::token_2022::spl_token_2022::{self, extension::ExtensionType};
+use ithaca_client::pda;
+use ithaca_smart_contract_sol::error::TokenValidatorError;
+use ithaca_smart_contract_sol::{
+    accounts, instruction, Roles, TokenStatus, TokenValidator, WhitelistedToken,
+};
+use solana_sdk::signature::{Keypair, Signer};
+
+use crate::env::{error_code, ix, TestEnv, RELEASE_LOCK};
+
+#[tokio::test]
+async fn whitelisting_stores_decimals_and_precision() {
+    let mut env = TestEnv::new().await;
+
+    let token_validator: TokenValidator = env.fetch(env.token_validator).await;
+    assert_eq!(token_validator.access_controller, env.access_controller);
+
+    let mint = env.whitelisted_mint(9, 4).await;
+    let whitelisted_token: WhitelistedToken = env
+        .fetch(pda::whitelisted_token(&env.token_validator, &mint).0)
+        .await;
+    assert_eq!(whitelisted_token.token_mint, mint);
+    assert_eq!(whitelisted_token.token_decimals, 9);
+    assert_eq!(whitelisted_token.token_precision, 4);
+}
+
+#[tokio::test]
+async fn non_fungible_tokens_cannot_be_whitelisted() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+    let mint = env.create_mint(0, &admin).await;
+
+    let whitelist_token_ix = env.whitelist_token_ix(mint, 0);
+    let err = env.process(&[whitelist_token_ix], &[]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(TokenValidatorError::NonFungibleToken)
+    );
+    assert!(
+        !env.exists(pda::whitelisted_token(&env.token_validator, &mint).0)
+            .await
+    );
+}
+
+#[tokio::test]
+async fn dangerous_token_2022_extensions_need_explicit_approval() {
+    let mut env = TestEnv::new().await;
+    let mint = Keypair::new();
+    let permanent_delegate_ix = spl_token_2022::instruction::initialize_permanent_delegate(
+        &spl_token_2022::ID,
+        &mint.pubkey(),
+        &Keypair::new().pubkey(),
+    )
+    .unwrap();
+    let mint = env
+        .create_mint_2022(
+            &mint,
+            6,
+            &[ExtensionType::PermanentDelegate],
+            vec![permanent_delegate_ix],
+        )
+        .await;
+    let whitelisted_token = pda::whitelisted_token(&env.token_validator, &mint).0;
+
+    let whitelist_token_ix = env.whitelist_token_ix(mint, 2);
+    let err = env.process(&[whitelist_token_ix], &[]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(TokenValidatorError::DangerousMintExtension)
+    );
+    assert!(!env.exists(whitelisted_token).await);
+
+    let whitelist_token_ix = env.whitelist_token_with_extensions_ix(mint, 2, true);
+    env.process(&[whitelist_token_ix], &[]).await.unwrap();
+    let token: WhitelistedToken = env.fetch(whitelisted_token).await;
+    assert_eq!(token.token_mint, mint);
+}
+
+#[tokio::test]
+async fn removed_token_is_withdraw_only() {
+    let mut env = TestEnv::new().await;
+    let mint = env.whitelisted_mint(6, 2).await;
+    let whitelisted_token = pda::whitelisted_token(&env.token_validator, &mint).0;
+    let admin_role = env.role(Roles::Admin);
+    let (access_controller, token_validator) = (env.access_controller, env.token_validator);
+    let (client, client_ata) = env.new_client(&mint, 1_000).await;
+    env.deposit(&client, client_ata, mint, 600).await;
+
+    let remove_ix = |admin: &Keypair| {
+        ix(
+            accounts::RemoveTokenFromWhitelist {
+                admin: admin.pubkey(),
+                access_controller,
+                role: admin_role,
+                member: pda::member(&access_controller, &admin.pubkey()).0,
+                token_validator,
+                token_to_remove: mint,
+                whitelisted_token,
+                system_program: system_program::ID,
+            },
+            instruction::RemoveTokenFromWhitelist {},
+        )
+    };
+
+    // A signer without the admin role can't remove tokens
+    let impostor = Keypair::new();
+    let impostor_remove_ix = remove_ix(&impostor);
+    assert!(env
+        .process(&[impostor_remove_ix], &[&impostor])
+        .await
+        .is_err());
+    let token: WhitelistedToken = env.fetch(whitelisted_token).await;
+    assert_eq!(token.status, TokenStatus::Active);
+
+    let admin_remove_ix = remove_ix(&env.admin);
+    env.process(&[admin_remove_ix], &[]).await.unwrap();
+    let token: WhitelistedToken = env.fetch(whitelisted_token).await;
+    assert_eq!(token.status, TokenStatus::WithdrawOnly);
+
+    // Deposits of a removed token are rejected
+    let deposit_ix = env.deposit_ix(&client.pubkey(), client_ata, mint, 400);
+    let err = env.process(&[deposit_ix], &[&client]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(TokenValidatorError::DepositsDisabled)
+    );
+
+    // The balance left in the fundlock can still be taken out
+    let withdraw_ix = env.withdraw_ix(&client.pubkey(), client_ata, mint, 600);
+    env.process(&[withdraw_ix], &[&client]).await.unwrap();
+    env.advance_clock(RELEASE_LOCK + 1).await;
+    let release_ix = env.release_ix(&client.pubkey(), client_ata, mint, 0);
+    env.process(&[release_ix], &[&client]).await.unwrap();
+    assert_eq!(env.token_balance(client_ata).await, 1_000);
+    let token: WhitelistedToken = env.fetch(whitelisted_token).await;
+    assert_eq!(token.total_deposits, 0);
+}
+
+#[tokio::test]
+async fn deposits_respect_the_token_config() {
+    let mut env = TestEnv::new().await;
+    let mint = env.whitelisted_mint(6, 2).await;
+    let whitelisted_token = pda::whitelisted_token(&env.token_validator, &mint).0;
+    let (alice, alice_ata) = env.new_client(&mint, 1_000).await;
+    let (bob, bob_ata) = env.new_client(&mint, 1_000).await;
+
+    // Only the admin can change the config
+    let mut impostor_config_ix = env.set_token_config_ix(mint, TokenStatus::Active, None, None, 0);
+    let impostor = Keypair::new();
+    impostor_config_ix.accounts[0].pubkey = impostor.pubkey();
+    impostor_config_ix.accounts[3].pubkey =
+        pda::member(&env.access_controller, &impostor.pubkey()).0;
+    assert!(env
+        .process(&[impostor_config_ix], &[&impostor])
+        .await
+        .is_err());
+
+    let config_ix = env.set_token_config_ix(mint, TokenStatus::Active, Some(1_000), Some(600), 100);
+    env.process(&[config_ix], &[]).await.unwrap();
+
+    let deposit_ix = env.deposit_ix(&alice.pubkey(), alice_ata, mint, 99);
+    let err = env.process(&[deposit_ix], &[&alice]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(TokenValidatorError::DepositBelowMinimum)
+    );
+
+    env.deposit(&alice, alice_ata, mint, 500).await;
+    let deposit_ix = env.deposit_ix(&alice.pubkey(), alice_ata, mint, 101);
+    let err = env.process(&[deposit_ix], &[&alice]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(TokenValidatorError::ClientDepositCapExceeded)
+    );
+
+    // The client cap applies per client balance, which is keyed by the deposit token account,
+    // so only the fundlock wide cap bounds a client spreading deposits over token accounts
+    let alice_second_ata = env.fund_client(&alice, &mint, 100).await;
+    env.deposit(&alice, alice_second_ata, mint, 100).await;
+
+    env.deposit(&bob, bob_ata, mint, 400).await;
+    let deposit_ix = env.deposit_ix(&bob.pubkey(), bob_ata, mint, 100);
+    let err = env.process(&[deposit_ix], &[&bob]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(TokenValidatorError::DepositCapExceeded)
+    );
+    let token: WhitelistedToken = env.fetch(whitelisted_token).await;
+    assert_eq!(token.total_deposits, 1_000);
+
+    // Disabled deposits leave withdrawals open
+    let config_ix = env.set_token_config_ix(
+        mint,
+        TokenStatus::DepositDisabled,
+        Some(1_000),
+        Some(600),
+        100,
+    );
+    env.process(&[config_ix], &[]).await.unwrap();
+    let deposit_ix = env.deposit_ix(&bob.pubkey(), bob_ata, mint, 100);
+    let err = env.process(&[deposit_ix], &[&bob]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(TokenValidatorError::DepositsDisabled)
+    );
+    let withdraw_ix = env.withdraw_ix(&bob.pubkey(), bob_ata, mint, 400);
+    env.process(&[withdraw_ix], &[&bob]).await.unwrap();
+
+    // Reopening the token or loosening its limits has to go through governance
+    let loosened_configs = [
+        (TokenStatus::Active, Some(1_000), Some(600), 100),
+        (TokenStatus::DepositDisabled, None, Some(600), 100),
+        (TokenStatus::DepositDisabled, Some(1_000), Some(601), 100),
+        (TokenStatus::DepositDisabled, Some(1_000), Some(600), 99),
+    ];
+    for (status, deposit_cap, client_deposit_cap, min_deposit) in loosened_configs {
+        let config_ix =
+            env.set_token_config_ix(mint, status, deposit_cap, client_deposit_cap, min_deposit);
+        let err = env.process(&[config_ix], &[]).await.unwrap_err();
+        assert_eq!(
+            error_code(err),
+            u32::from(TokenValidatorError::ConfigNotTightened)
+        );
+    }
+}
+
+#[tokio::test]
+async fn legacy_whitelisted_token_is_migrated() {
+    let mut env = TestEnv::new().await;
+    let mint = env.whitelisted_mint(6, 2).await;
+    let (whitelisted_token, bump) = pda::whitelisted_token(&env.token_validator, &mint);
+    let (client, client_ata) = env.new_client(&mint, 1_000).await;
+
+    // Whitelisted tokens had no config before
+    let mut data = WhitelistedToken::DISCRIMINATOR.to_vec();
+    data.extend((mint, 6u8, 2u8, bump).try_to_vec().unwrap());
+    env.set_program_account(&whitelisted_token, &data);
+    let deposit_ix = env.deposit_ix(&client.pubkey(), client_ata, mint, 400);
+    assert!(env.process(&[deposit_ix], &[&client]).await.is_err());
+
+    let migrate_ix = ix(
+        accounts::MigrateWhitelistedToken {
+            admin: env.admin.pubkey(),
+            access_controller: env.access_controller,
+            token_validator: env.token_validator,
+            token: mint,
+            whitelisted_token,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateWhitelistedToken {
+            total_deposits: 250,
+            ledger_count: 1,
+        },
+    );
+    env.process(std::slice::from_ref(&migrate_ix), &[])
+        .await
+        .unwrap();
+    let token: WhitelistedToken = env.fetch(whitelisted_token).await;
+    assert_eq!(token.token_mint, mint);
+    assert_eq!(token.token_decimals, 6);
+    assert_eq!(token.token_precision, 2);
+    assert_eq!(token.status, TokenStatus::Active);
+    assert_eq!(token.deposit_cap, None);
+    assert_eq!(token.total_deposits, 250);
+    assert_eq!(token.ledger_count, 1);
+
+    env.deposit(&client, client_ata, mint, 400).await;
+    let token: WhitelistedToken = env.fetch(whitelisted_token).await;
+    assert_eq!(token.total_deposits, 650);
+
+    let err = env.process(&[migrate_ix], &[]).await.unwrap_err();
+    assert_eq!(
+        error_code(err),
+        u32::from(TokenValidatorError::AlreadyMigrated)
+    );
+}